@@ -0,0 +1,34 @@
+//! Compare spawning threads for every run with reusing threads of a pool.
+//!
+//! Run with `cargo bench --bench pool`.
+use std::time::Instant;
+
+const RUNS: usize = 1000;
+const BATCH: u64 = 16;
+const THREADS: usize = 4;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    // warm up
+    f();
+    let t = Instant::now();
+    for _ in 0..RUNS { f() }
+    let per_run = t.elapsed() / RUNS as u32;
+    println!("{:>10}: {:?} per run", name, per_run);
+}
+
+fn work(x: u64) -> Result<u64, ()> {
+    Ok((0..x * 100).fold(x, |acc, v| acc.wrapping_mul(31).wrapping_add(v)))
+}
+
+fn main() {
+    bench("spawn", || {
+        let res = parstream::run(0..BATCH, THREADS, work, |_| Ok(()));
+        assert_eq!(res, Ok(BATCH as usize));
+    });
+
+    let pool = parstream::Pool::new(THREADS);
+    bench("pool", || {
+        let res = pool.run(0..BATCH, work, |_| Ok(()));
+        assert_eq!(res, Ok(BATCH as usize));
+    });
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Handle which allows to observe number of workers which are currently
+/// computing results.
+///
+/// Handle is passed to a run using
+/// [`Config::active_workers`](struct.Config.html#method.active_workers).
+/// Workers which wait for new elements, e.g. because input iterator is slow
+/// or the run is paused, are not counted. Counter is decremented after
+/// worker closure has returned, even if it has panicked. The same handle
+/// can be shared by several runs, in which case it counts workers of all
+/// of them.
+///
+/// # Examples
+/// ```
+/// use parstream::{ActiveWorkers, Config};
+///
+/// let active = ActiveWorkers::new();
+/// let a = active.clone();
+/// let res: Result<usize, ()> = Config::new()
+///     .threads(4)
+///     .active_workers(active.clone())
+///     .run(0..100u64, move |x| {
+///         assert!(a.count() >= 1);
+///         Ok(x*x)
+///     }, |_| Ok(()));
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(active.count(), 0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ActiveWorkers {
+    count: Arc<AtomicUsize>,
+}
+
+impl ActiveWorkers {
+    /// Create new handle with zero active workers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of workers which are currently computing results.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Mark worker as active until the returned guard is dropped.
+    pub(crate) fn enter(&self) -> ActiveGuard<'_> {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        ActiveGuard(&self.count)
+    }
+}
+
+/// Guard which decrements number of active workers on drop, so the counter
+/// stays correct if worker closure panics.
+pub(crate) struct ActiveGuard<'a>(&'a AtomicUsize);
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
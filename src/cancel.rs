@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::coord::{self, Status};
+
+/// Token which allows to cancel a run from another thread.
+///
+/// Token is passed to a run using
+/// [`Config::cancel_token`](struct.Config.html#method.cancel_token) and
+/// cancelled using [`CancelHandle`](struct.CancelHandle.html). After
+/// cancellation no new tasks will be started and report closure will not be
+/// called anymore. Note that token should not be used by several runs
+/// simultaneously.
+///
+/// # Examples
+/// ```
+/// use parstream::{CancelToken, Config, RunError};
+///
+/// let (token, handle) = CancelToken::new();
+/// let res: Result<usize, RunError<()>> = Config::new()
+///     .threads(4)
+///     .cancel_token(token)
+///     .run_checked(0.., |x: u64| Ok(x), |y| {
+///         if y == 10 { handle.cancel(); }
+///         Ok(())
+///     });
+/// match res {
+///     Err(RunError::Cancelled(n)) => assert!(n >= 11),
+///     _ => panic!("unexpected result"),
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CancelToken {
+    flag: Arc<AtomicU8>,
+    // flag of the token from which this one was derived, cancellation of
+    // the parent token cancels runs which use this one
+    parent: Option<Arc<AtomicU8>>,
+}
+
+/// Handle which is used for cancellation of runs which use associated
+/// [`CancelToken`](struct.CancelToken.html).
+#[derive(Clone, Debug)]
+pub struct CancelHandle {
+    flag: Arc<AtomicU8>,
+}
+
+impl CancelToken {
+    /// Create new cancellation token and associated cancellation handle.
+    pub fn new() -> (CancelToken, CancelHandle) {
+        let flag = Arc::new(AtomicU8::new(Status::Running as u8));
+        let token = CancelToken { flag: flag.clone(), parent: None };
+        (token, CancelHandle { flag })
+    }
+
+    /// Get token which is cancelled when the process receives `SIGINT`,
+    /// e.g. after user has pressed Ctrl-C.
+    ///
+    /// On the first `SIGINT` runs which use the token are stopped
+    /// gracefully: no new tasks are started, workers finish their current
+    /// elements and all threads are joined before the run returns. Default
+    /// handler is restored after that, so the second `SIGINT` terminates
+    /// the process as usual. All calls return tokens which share the same
+    /// state and reinstall the handler.
+    ///
+    /// Requires `signal` feature and supported only on Unix targets.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use parstream::{CancelToken, Config};
+    ///
+    /// let token = CancelToken::ctrl_c().expect("failed to install handler");
+    /// let res: Result<usize, ()> = Config::new()
+    ///     .cancel_token(token)
+    ///     .run(0.., |x: u64| Ok(x), |y| {
+    ///         println!("{}", y);
+    ///         Ok(())
+    ///     });
+    /// eprintln!("interrupted after {} elements", res.unwrap());
+    /// ```
+    #[cfg(all(feature = "signal", unix))]
+    pub fn ctrl_c() -> std::io::Result<CancelToken> {
+        crate::signal::install()
+            .map(|flag| CancelToken { flag, parent: None })
+    }
+
+    /// Check if token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        let cancelled = |flag: &AtomicU8| {
+            flag.load(Ordering::Acquire) == Status::Cancelled as u8
+        };
+        cancelled(&self.flag)
+            || matches!(self.parent.as_deref(), Some(p) if cancelled(p))
+    }
+
+    /// Check if a run which uses the token was stopped, e.g. by
+    /// cancellation or an error.
+    pub(crate) fn is_stopped(&self) -> bool {
+        coord::is_stopped(&self.flag) || self.is_cancelled()
+    }
+
+    /// Create token with a separate state, which is cancelled together with
+    /// this token. Stopping runs which use the new token does not affect
+    /// runs which use this one.
+    pub(crate) fn child(&self) -> CancelToken {
+        let flag = Arc::new(AtomicU8::new(Status::Running as u8));
+        CancelToken { flag, parent: Some(self.flag.clone()) }
+    }
+
+    /// Flag of the parent token, if this token was created by `child`.
+    pub(crate) fn parent(&self) -> Option<Arc<AtomicU8>> {
+        self.parent.clone()
+    }
+
+    /// Create cancellation handle associated with this token.
+    pub(crate) fn handle(&self) -> CancelHandle {
+        CancelHandle { flag: self.flag.clone() }
+    }
+
+    /// Get flag for a new run, state of the previous run is discarded.
+    pub(crate) fn flag(&self) -> Arc<AtomicU8> {
+        let cancelled = Status::Cancelled as u8;
+        let _ = self.flag.fetch_update(Ordering::AcqRel, Ordering::Acquire,
+            |v| if v == cancelled { None } else { Some(Status::Running as u8) });
+        self.flag.clone()
+    }
+}
+
+impl CancelHandle {
+    /// Cancel runs which use associated token.
+    ///
+    /// If run has already completed, the next run which will use the
+    /// associated token may be cancelled right away.
+    pub fn cancel(&self) {
+        coord::try_stop(&self.flag, Status::Cancelled);
+    }
+}
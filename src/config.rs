@@ -0,0 +1,1942 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::io;
+use std::iter;
+use std::mem;
+use std::ops::ControlFlow;
+use std::num::NonZeroUsize;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{BoxedReport, CancelToken, CircuitError, Interrupted, ParMap};
+use crate::{ActiveWorkers, ReportPressure, RunError, RunFailure, RunStats};
+use crate::{ErrorPolicy, Executor, MergeOrder, PauseHandle, RunHandle};
+use crate::{engine, join, pipeline, timeout};
+use crate::coord::Status;
+use crate::error::unchecked;
+use crate::executor::FnExecutor;
+use crate::expand::WorkQueue;
+use crate::merge::Merged;
+use crate::priority::Prioritized;
+use crate::spawn::SpawnHook;
+use crate::stats::Counters;
+
+enum Flow<E> {
+    Error(E),
+    Break,
+}
+
+enum Stage<EF, ER> {
+    Map(EF),
+    Report(ER),
+}
+
+/// Accumulator of a worker, which is moved into `out` after the worker
+/// has exited.
+struct Partial<'a, A> {
+    acc: Option<A>,
+    out: &'a Mutex<Vec<A>>,
+}
+
+impl<'a, A> Drop for Partial<'a, A> {
+    fn drop(&mut self) {
+        if let Some(acc) = self.acc.take() {
+            // lock can be poisoned only by another drop, which does not panic
+            self.out.lock().unwrap().push(acc);
+        }
+    }
+}
+
+/// Configuration of a parallel run.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::Config::new()
+///     .threads(4)
+///     .input_capacity(16)
+///     .report_capacity(16)
+///     .run(0..100u64, |x| Ok(x*x), |y| { ys.push(y); Ok(()) });
+///
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+#[derive(Clone, Default)]
+pub struct Config {
+    pub(crate) threads: usize,
+    pub(crate) input_capacity: Option<usize>,
+    pub(crate) unbounded_input: bool,
+    pub(crate) report_capacity: Option<usize>,
+    pub(crate) report_pressure: ReportPressure,
+    pub(crate) max_reorder_buffer: Option<usize>,
+    pub(crate) max_in_flight: Option<usize>,
+    pub(crate) prefetch: usize,
+    pub(crate) cancel_token: Option<CancelToken>,
+    pub(crate) on_progress: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    pub(crate) thread_name: Option<String>,
+    pub(crate) stack_size: Option<usize>,
+    pub(crate) pin_workers: bool,
+    pub(crate) spawn_hook: Option<SpawnHook>,
+    pub(crate) deadline: Option<Instant>,
+    pub(crate) max_rate: Option<u32>,
+    pub(crate) pause_handle: Option<PauseHandle>,
+    pub(crate) active_workers: Option<ActiveWorkers>,
+    pub(crate) heartbeat: Option<Duration>,
+    pub(crate) min_threads: Option<usize>,
+    pub(crate) stats: Option<Arc<Counters>>,
+    pub(crate) max_buffer_bytes: Option<usize>,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("threads", &self.threads)
+            .field("input_capacity", &self.input_capacity)
+            .field("unbounded_input", &self.unbounded_input)
+            .field("report_capacity", &self.report_capacity)
+            .field("report_pressure", &self.report_pressure)
+            .field("max_reorder_buffer", &self.max_reorder_buffer)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("prefetch", &self.prefetch)
+            .field("cancel_token", &self.cancel_token)
+            .field("on_progress", &self.on_progress.as_ref().map(|_| ".."))
+            .field("thread_name", &self.thread_name)
+            .field("stack_size", &self.stack_size)
+            .field("pin_workers", &self.pin_workers)
+            .field("spawn_hook", &self.spawn_hook.as_ref().map(|_| ".."))
+            .field("deadline", &self.deadline)
+            .field("max_rate", &self.max_rate)
+            .field("pause_handle", &self.pause_handle)
+            .field("active_workers", &self.active_workers)
+            .field("heartbeat", &self.heartbeat)
+            .field("min_threads", &self.min_threads)
+            .field("stats", &self.stats)
+            .field("max_buffer_bytes", &self.max_buffer_bytes)
+            .finish()
+    }
+}
+
+impl Config {
+    /// Create new configuration with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set number of threads in the workers pool.
+    ///
+    /// If `threads` is equal to 0 (default), number of threads will be equal
+    /// to [`std::thread::available_parallelism`] or to 1 if it can not be
+    /// determined.
+    ///
+    /// [`std::thread::available_parallelism`]: https://doc.rust-lang.org/std/thread/fn.available_parallelism.html
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Set capacity of the channel which is used for sending elements to
+    /// worker threads, by default it's equal to `2*threads`.
+    ///
+    /// See [`run_with_capacity`](fn.run_with_capacity.html) for details.
+    pub fn input_capacity(mut self, cap: usize) -> Self {
+        self.input_capacity = Some(cap);
+        self
+    }
+
+    /// Use unbounded channel for sending elements to worker threads.
+    ///
+    /// By default the input channel is bounded by
+    /// [`input_capacity`](#method.input_capacity), so the input iterator is
+    /// advanced only as fast as workers take elements. With enabled option
+    /// the iterator is drained as fast as possible, which can be useful
+    /// for a fast iterator which holds a lock or a file handle. The cost is
+    /// memory: all elements which were not taken by workers are buffered.
+    ///
+    /// Options which limit the feeder, e.g.
+    /// [`max_in_flight`](#method.max_in_flight), still apply.
+    ///
+    /// # Examples
+    /// ```
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(2)
+    ///     .unbounded_input(true)
+    ///     .run(0..100u64, |x| Ok(x*x), |_| Ok(()));
+    /// assert_eq!(res, Ok(100));
+    /// ```
+    pub fn unbounded_input(mut self, unbounded: bool) -> Self {
+        self.unbounded_input = unbounded;
+        self
+    }
+
+    /// Set capacity of the channel which is used for sending results to
+    /// the report thread, by default it's equal to `2*threads`.
+    ///
+    /// See [`run_with_capacity`](fn.run_with_capacity.html) for details.
+    pub fn report_capacity(mut self, cap: usize) -> Self {
+        self.report_capacity = Some(cap);
+        self
+    }
+
+    /// Set behavior of the run when the report closure does not keep up
+    /// with workers, by default workers are blocked.
+    ///
+    /// Dropping of results applies only to runs which report results in
+    /// order of elements, e.g. [`run`](#method.run). Dropped results are
+    /// counted as reported ones.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use parstream::{Config, ReportPressure};
+    ///
+    /// let dropped = Arc::new(Mutex::new(Vec::new()));
+    /// let d = dropped.clone();
+    /// let mut ys = Vec::new();
+    /// let res: Result<usize, ()> = Config::new()
+    ///     .threads(4)
+    ///     .report_capacity(4)
+    ///     .report_pressure(ReportPressure::shed(move |i| {
+    ///         d.lock().unwrap().push(i);
+    ///     }))
+    ///     .run(0..20u64, |x| {
+    ///         if x == 0 {
+    ///             std::thread::sleep(std::time::Duration::from_millis(100));
+    ///         }
+    ///         Ok(x)
+    ///     }, |y| { ys.push(y); Ok(()) });
+    /// assert_eq!(res, Ok(20));
+    /// let dropped = dropped.lock().unwrap();
+    /// assert!(!dropped.is_empty());
+    /// assert_eq!(ys.len() + dropped.len(), 20);
+    /// ```
+    pub fn report_pressure(mut self, pressure: ReportPressure) -> Self {
+        self.report_pressure = pressure;
+        self
+    }
+
+    /// Limit number of results which can be buffered for reordering.
+    ///
+    /// Element at position `i` will be sent to workers only after at
+    /// least `i - max + 1` elements were reported, i.e. if one element takes
+    /// significantly longer to process than others, feeder will wait for it
+    /// instead of consuming more elements from the input iterator. Thus
+    /// reorder buffer never holds more than `max - 1` results. By default
+    /// size of the reorder buffer is not limited.
+    ///
+    /// Note that setting `max` smaller than number of threads will reduce
+    /// parallelism.
+    ///
+    /// # Panics
+    /// If `max` is equal to zero.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut ys = Vec::new();
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(4)
+    ///     .max_reorder_buffer(8)
+    ///     .run(0..100u64, |x| Ok(x*x), |y| { ys.push(y); Ok(()) });
+    /// assert_eq!(res, Ok(100));
+    /// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+    /// ```
+    pub fn max_reorder_buffer(mut self, max: usize) -> Self {
+        assert!(max > 0, "reorder buffer size must be non-zero");
+        self.max_reorder_buffer = Some(max);
+        self
+    }
+
+    /// Limit number of elements which were sent to workers, but whose
+    /// results were not reported yet.
+    ///
+    /// Feeder waits before sending an element until the limit allows it,
+    /// independently of channel capacities, so at most `max` elements are
+    /// simultaneously processed, sent to the report thread or buffered for
+    /// reordering. This allows to control peak memory usage if results are
+    /// large. If [`max_reorder_buffer`](#method.max_reorder_buffer) is set
+    /// too, the smaller limit is used. By default number of elements in
+    /// flight is not limited.
+    ///
+    /// # Panics
+    /// If `max` is equal to zero.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut ys = Vec::new();
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(4)
+    ///     .max_in_flight(2)
+    ///     .run(0..100u64, |x| Ok(x*x), |y| { ys.push(y); Ok(()) });
+    /// assert_eq!(res, Ok(100));
+    /// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+    /// ```
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        assert!(max > 0, "number of elements in flight must be non-zero");
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Set number of elements which are taken from the input iterator
+    /// before spawning threads.
+    ///
+    /// Prefetched elements are sent to workers first, so workers do not
+    /// wait for a slow iterator at startup. Number of prefetched elements
+    /// is limited by capacity of the input channel. Prefetching affects
+    /// only the start of the run and has no effect after the channel was
+    /// filled. By default elements are not prefetched.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut ys = Vec::new();
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(4)
+    ///     .prefetch(8)
+    ///     .run(0..100u64, |x| Ok(x*x), |y| { ys.push(y); Ok(()) });
+    /// assert_eq!(res, Ok(100));
+    /// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+    /// ```
+    pub fn prefetch(mut self, n: usize) -> Self {
+        self.prefetch = n;
+        self
+    }
+
+    /// Set token which can be used for cancellation of the run.
+    ///
+    /// On cancellation [`run`](#method.run) returns number of elements
+    /// reported before cancellation, while [`run_checked`](#method.run_checked)
+    /// returns [`RunError::Cancelled`](enum.RunError.html#variant.Cancelled).
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    /// Set handle which can be used for pausing and resuming of the run.
+    ///
+    /// See [`PauseHandle`](struct.PauseHandle.html) for details.
+    pub fn pause_handle(mut self, handle: PauseHandle) -> Self {
+        self.pause_handle = Some(handle);
+        self
+    }
+
+    /// Set handle which can be used for observing number of workers which
+    /// are currently computing results.
+    ///
+    /// See [`ActiveWorkers`](struct.ActiveWorkers.html) for details.
+    pub fn active_workers(mut self, handle: ActiveWorkers) -> Self {
+        self.active_workers = Some(handle);
+        self
+    }
+
+    /// Set closure which will be called with number of reported elements
+    /// after every successful call of the report closure.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let progress = Arc::new(AtomicUsize::new(0));
+    /// let p = progress.clone();
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(4)
+    ///     .on_progress(move |n| p.store(n, Ordering::Relaxed))
+    ///     .run(0..100u64, |x| Ok(x*x), |_| Ok(()));
+    /// assert_eq!(res, Ok(100));
+    /// assert_eq!(progress.load(Ordering::Relaxed), 100);
+    /// ```
+    pub fn on_progress(
+        mut self, on_progress: impl Fn(usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(Arc::new(on_progress));
+        self
+    }
+
+    /// Set prefix for names of the spawned threads.
+    ///
+    /// Workers will be named `{prefix}-worker-{i}` and the report thread
+    /// will be named `{prefix}-report` (or the feeder thread will be named
+    /// `{prefix}-feeder` if results are reported on the calling thread).
+    /// By default threads are unnamed.
+    ///
+    /// # Examples
+    /// ```
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(2)
+    ///     .thread_name("parstream")
+    ///     .run(0..10u64,
+    ///         |x| {
+    ///             let name = std::thread::current().name().unwrap().to_owned();
+    ///             assert!(name.starts_with("parstream-worker-"));
+    ///             Ok(x)
+    ///         },
+    ///         |_| {
+    ///             assert_eq!(std::thread::current().name(), Some("parstream-report"));
+    ///             Ok(())
+    ///         },
+    ///     );
+    /// assert_eq!(res, Ok(10));
+    /// ```
+    pub fn thread_name(mut self, prefix: impl Into<String>) -> Self {
+        self.thread_name = Some(prefix.into());
+        self
+    }
+
+    /// Set stack size in bytes for the spawned threads.
+    ///
+    /// By default the platform default stack size is used. Stack size is
+    /// ignored by [`spawn_hook`](#method.spawn_hook).
+    ///
+    /// # Examples
+    /// ```
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(2)
+    ///     .stack_size(16 << 20)
+    ///     .run(0..10u64, |x| Ok(x*x), |_| Ok(()));
+    /// assert_eq!(res, Ok(10));
+    /// ```
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Pin every worker to a distinct CPU core.
+    ///
+    /// Worker `w` is pinned to core `w % cores`. If CPU affinity is not
+    /// supported by the platform, this option is ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(2)
+    ///     .pin_workers(true)
+    ///     .run(0..10u64, |x| Ok(x*x), |_| Ok(()));
+    /// assert_eq!(res, Ok(10));
+    /// ```
+    #[cfg(feature = "affinity")]
+    pub fn pin_workers(mut self, pin: bool) -> Self {
+        self.pin_workers = pin;
+        self
+    }
+
+    /// Set closure which will be used for spawning worker and report
+    /// threads instead of the scoped threads.
+    ///
+    /// The closure receives thread body which must be eventually run on
+    /// a separate thread. The run will not return until all bodies are
+    /// finished or dropped, so running them on the thread which has called
+    /// `run` or delaying them indefinitely will result in a deadlock.
+    /// Dropping body without running it stops the run with a
+    /// worker panic. Thread names set by [`thread_name`](#method.thread_name)
+    /// and [`stack_size`](#method.stack_size) are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let spawned = Arc::new(AtomicUsize::new(0));
+    /// let s = spawned.clone();
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(4)
+    ///     .spawn_hook(move |task| {
+    ///         s.fetch_add(1, Ordering::Relaxed);
+    ///         std::thread::spawn(task);
+    ///     })
+    ///     .run(0..100u64, |x| Ok(x*x), |_| Ok(()));
+    /// assert_eq!(res, Ok(100));
+    /// // 4 workers and the report thread
+    /// assert_eq!(spawned.load(Ordering::Relaxed), 5);
+    /// ```
+    pub fn spawn_hook(
+        mut self,
+        hook: impl Fn(Box<dyn FnOnce() + Send>) + Send + Sync + 'static,
+    ) -> Self {
+        self.spawn_hook = Some(Arc::new(FnExecutor(hook)));
+        self
+    }
+
+    /// Set executor which will be used for spawning worker and report
+    /// threads instead of the scoped threads.
+    ///
+    /// Replaces closure set by [`spawn_hook`](#method.spawn_hook), see it
+    /// for details. Thread names and stack size are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use parstream::Executor;
+    ///
+    /// #[derive(Default)]
+    /// struct Counting(AtomicUsize);
+    ///
+    /// impl Executor for Counting {
+    ///     fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///         std::thread::spawn(task);
+    ///     }
+    /// }
+    ///
+    /// let executor = Arc::new(Counting::default());
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(2)
+    ///     .executor(executor.clone())
+    ///     .run(0..10u64, |x| Ok(x*x), |_| Ok(()));
+    /// assert_eq!(res, Ok(10));
+    /// // 2 workers and the report thread
+    /// assert_eq!(executor.0.load(Ordering::Relaxed), 3);
+    /// ```
+    pub fn executor(mut self, executor: impl Executor + 'static) -> Self {
+        self.spawn_hook = Some(Arc::new(executor));
+        self
+    }
+
+    /// Set deadline after which no new elements will be sent to workers and
+    /// report closure will not be called anymore.
+    ///
+    /// Reaching deadline is not an error, the run returns number of elements
+    /// reported before the deadline. Results of elements computed after the
+    /// deadline are discarded.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Enable watchdog which stops the run if no element was reported during
+    /// `timeout`, while some elements sent to workers are still pending.
+    ///
+    /// Watchdog runs on a separate thread (named `{prefix}-watchdog` if
+    /// [`thread_name`](#method.thread_name) is set) and measures progress
+    /// using wall-clock time, so `timeout` should be significantly larger
+    /// than the expected time of processing and reporting one element.
+    /// Stalled run is stopped like a cancelled one and
+    /// [`run_checked`](#method.run_checked) returns
+    /// [`RunError::Stalled`](enum.RunError.html#variant.Stalled), while
+    /// unchecked runs panic. Note that threads blocked inside callbacks can
+    /// not be interrupted, so the run returns only after they return.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use parstream::{Config, RunError};
+    ///
+    /// let res: Result<usize, RunError<()>> = Config::new()
+    ///     .threads(2)
+    ///     .heartbeat(Duration::from_millis(50))
+    ///     .run_checked(0..10u64,
+    ///         |x| {
+    ///             if x == 3 { std::thread::sleep(Duration::from_millis(200)); }
+    ///             Ok(x)
+    ///         },
+    ///         |_| Ok(()),
+    ///     );
+    /// match res {
+    ///     Err(RunError::Stalled(n)) => assert_eq!(n, 3),
+    ///     _ => panic!("unexpected result"),
+    /// }
+    /// ```
+    pub fn heartbeat(mut self, timeout: Duration) -> Self {
+        self.heartbeat = Some(timeout);
+        self
+    }
+
+    /// Set maximum number of elements which are sent to workers per second.
+    ///
+    /// Note that it limits rate of dispatching elements, not rate of their
+    /// completion, and elements are sent evenly spaced in time without
+    /// bursts.
+    ///
+    /// # Panics
+    /// If `rate` is equal to 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let t = Instant::now();
+    /// let res: Result<usize, ()> = parstream::Config::new()
+    ///     .threads(4)
+    ///     .max_rate(100)
+    ///     .run(0..10u64, |x| Ok(x*x), |_| Ok(()));
+    /// assert_eq!(res, Ok(10));
+    /// assert!(t.elapsed() >= Duration::from_millis(90));
+    /// ```
+    pub fn max_rate(mut self, rate: u32) -> Self {
+        assert!(rate > 0, "rate must be positive");
+        self.max_rate = Some(rate);
+        self
+    }
+
+    pub(crate) fn thread_name_for(
+        &self, f: impl FnOnce(&str) -> String,
+    ) -> Option<String> {
+        self.thread_name.as_ref().map(|p| f(p))
+    }
+
+    pub(crate) fn get_threads(&self) -> usize {
+        match self.threads {
+            0 => thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1),
+            n => n,
+        }
+    }
+
+    pub(crate) fn get_input_capacity(&self) -> usize {
+        self.input_capacity.unwrap_or_else(|| 2*self.get_threads())
+    }
+
+    pub(crate) fn get_report_capacity(&self) -> usize {
+        self.report_capacity.unwrap_or_else(|| 2*self.get_threads())
+    }
+
+    /// Run computation using this configuration.
+    ///
+    /// See [`run`](fn.run.html) for details.
+    pub fn run<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        unchecked(self.run_checked(xs, f, report))
+    }
+
+    /// Run computation using this configuration for elements of `xs` taken
+    /// from the back.
+    ///
+    /// See [`run_rev`](fn.run_rev.html) for details.
+    pub fn run_rev<X: Send, Y: Send, E: Send, I>(
+        &self,
+        xs: impl IntoIterator<Item=X, IntoIter=I>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E>
+        where I: DoubleEndedIterator<Item=X>
+    {
+        self.run(xs.into_iter().rev(), f, report)
+    }
+
+    /// Run computation using this configuration with adaptive number of
+    /// active workers.
+    ///
+    /// Configured number of threads is ignored.
+    /// See [`run_adaptive`](fn.run_adaptive.html) for details.
+    ///
+    /// # Panics
+    /// If `min` is equal to zero or larger than `max`.
+    pub fn run_adaptive<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        min: usize,
+        max: usize,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        assert!(min > 0 && min <= max, "invalid range of worker counts");
+        let mut config = self.clone().threads(max);
+        config.min_threads = Some(min);
+        config.run(xs, f, report)
+    }
+
+    /// Run computation using this configuration and collect its
+    /// statistics.
+    ///
+    /// See [`run_with_stats`](fn.run_with_stats.html) for details.
+    pub fn run_with_stats<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> (Result<usize, E>, RunStats) {
+        let start = Instant::now();
+        let counters = Arc::new(Counters::new(self.get_threads()));
+        let mut config = self.clone();
+        config.stats = Some(counters.clone());
+        let res = config.run(xs, f, report);
+        drop(config);
+        let counters = Arc::try_unwrap(counters)
+            .expect("counters are used after the run");
+        (res, counters.into_stats(start.elapsed()))
+    }
+
+    /// Run computation using this configuration and measure peak size of
+    /// the reorder buffer.
+    ///
+    /// See [`run_with_peak`](fn.run_with_peak.html) for details.
+    pub fn run_with_peak<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> (Result<usize, E>, usize) {
+        let (res, stats) = self.run_with_stats(xs, f, report);
+        (res, stats.peak_buffered)
+    }
+
+    /// Run computation using this configuration for references to elements
+    /// of `xs`.
+    ///
+    /// See [`run_ref`](fn.run_ref.html) for details.
+    pub fn run_ref<'a, X: Sync, Y: Send, E: Send>(
+        &self,
+        xs: &'a [X],
+        f: impl Fn(&'a X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        self.run(0..xs.len(), |i| f(&xs[i]), report)
+    }
+
+    /// Run computation using this configuration and limit total size of
+    /// buffered results.
+    ///
+    /// See [`run_with_buffer_bytes`](fn.run_with_buffer_bytes.html) for
+    /// details.
+    pub fn run_with_buffer_bytes<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        max_bytes: usize,
+        size_of: impl Fn(&Y) -> usize + Send,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let mut config = self.clone();
+        config.max_buffer_bytes = Some(max_bytes);
+        let res = engine::run(&config, xs, f, |rx, shared| {
+            engine::run_report_sized(rx, size_of, |_, y| report(y), shared)
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and return callback panics
+    /// as errors.
+    ///
+    /// See [`run_checked`](fn.run_checked.html) for details.
+    pub fn run_checked<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, RunError<E>> {
+        engine::run(self, xs, f, |rx, flag| {
+            engine::run_report(rx, report, flag)
+        })
+    }
+
+    /// Run computation using this configuration and return the remaining
+    /// part of the iterator if the run exits early.
+    ///
+    /// See [`run_resumable`](fn.run_resumable.html) for details.
+    pub fn run_resumable<X: Send, Y: Send, E: Send, I: Iterator<Item=X>>(
+        &self,
+        xs: impl IntoIterator<Item=X, IntoIter=I>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, Interrupted<E, I>> {
+        let mut xs = xs.into_iter();
+        let len_hint = engine::capacity_hint(xs.size_hint());
+        let mut dispatched = 0;
+        let mut exhausted = false;
+        let mut reported = 0;
+        let next = |_: &_| {
+            let x = xs.next();
+            match x {
+                Some(_) => dispatched += 1,
+                None => exhausted = true,
+            }
+            x
+        };
+        let report = |y| {
+            report(y)?;
+            reported += 1;
+            Ok(())
+        };
+        let res = engine::run_source(
+            self, len_hint, next, |_| (), |_, x| f(x),
+            |rx, shared| engine::run_report(rx, report, shared),
+        );
+        match res {
+            Ok(n) if exhausted && n == dispatched => Ok(n),
+            res => Err(Interrupted {
+                error: res.err(),
+                reported,
+                dispatched,
+                rest: xs,
+            }),
+        }
+    }
+
+    /// Run computation using this configuration with a per-worker state.
+    ///
+    /// See [`run_with_init`](fn.run_with_init.html) for details.
+    pub fn run_with_init<S, X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        init: impl Fn() -> S + Sync,
+        f: impl Fn(&mut S, X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let res = engine::run_with_init(self, xs, |_| init(), f, |rx, shared| {
+            engine::run_report(rx, report, shared)
+        });
+        unchecked(res)
+    }
+
+    /// Run two-stage computation using this configuration.
+    ///
+    /// See [`run_pipeline`](fn.run_pipeline.html) for details.
+    pub fn run_pipeline<X: Send, M: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        stage1: impl Fn(X) -> Result<M, E> + Sync,
+        stage2: impl Fn(M) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        pipeline::run(self, xs, stage1, stage2, report)
+    }
+
+    /// Run computation using this configuration with a clone of `f` for
+    /// every worker.
+    ///
+    /// See [`run_clone`](fn.run_clone.html) for details.
+    pub fn run_clone<X: Send, Y: Send, E: Send, F>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: F,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E>
+        where F: FnMut(X) -> Result<Y, E> + Clone + Send
+    {
+        // `f` is not `Sync`, so clones are created under a lock
+        let f = Mutex::new(f);
+        let init = |_| match f.lock() {
+            Ok(f) => f.clone(),
+            Err(err) => err.into_inner().clone(),
+        };
+        let res = engine::run_with_init(self, xs, init, |f: &mut F, x| f(x),
+            |rx, shared| engine::run_report(rx, report, shared),
+        );
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration for elements of several
+    /// sources merged in `order`.
+    ///
+    /// See [`run_merged`](fn.run_merged.html) for details.
+    pub fn run_merged<X: Send, Y: Send, E: Send>(
+        &self,
+        sources: Vec<impl IntoIterator<Item=X>>,
+        order: MergeOrder,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let sources = sources.into_iter()
+            .map(IntoIterator::into_iter)
+            .collect();
+        self.run(Merged::new(sources, order), f, report)
+    }
+
+    /// Run computation using this configuration with per-worker states
+    /// borrowed from `states`.
+    ///
+    /// Number of threads is equal to length of `states`.
+    ///
+    /// See [`run_with_states`](fn.run_with_states.html) for details.
+    ///
+    /// # Panics
+    /// If `states` is empty.
+    pub fn run_with_states<S: Send, X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        states: &mut [S],
+        f: impl Fn(&mut S, X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        assert!(!states.is_empty(), "at least one state must be provided");
+        let config = self.clone().threads(states.len());
+        // every worker takes the slot with its index
+        let slots: Vec<Mutex<Option<&mut S>>> = states.iter_mut()
+            .map(|s| Mutex::new(Some(s)))
+            .collect();
+        let init = |w: usize| {
+            slots[w].lock().unwrap().take().expect("state is already taken")
+        };
+        let res = engine::run_with_init(&config, xs, init, |s, x| f(s, x),
+            |rx, shared| engine::run_report(rx, report, shared));
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and pass index of worker
+    /// to `f`.
+    ///
+    /// See [`run_with_worker_index`](fn.run_with_worker_index.html) for
+    /// details.
+    pub fn run_with_worker_index<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(usize, X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let res = engine::run_with_init(self, xs, |w| w, |w, x| f(*w, x),
+            |rx, shared| engine::run_report(rx, report, shared));
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and return number of
+    /// reported elements together with error.
+    ///
+    /// See [`run_with_count`](fn.run_with_count.html) for details.
+    pub fn run_with_count<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, RunFailure<E>> {
+        let mut completed = 0;
+        let res = self.run(xs, f, |y| {
+            report(y)?;
+            completed += 1;
+            Ok(())
+        });
+        res.map_err(|error| RunFailure { error, completed })
+    }
+
+    /// Run computation using this configuration and retry failed elements.
+    ///
+    /// See [`run_with_retries`](fn.run_with_retries.html) for details.
+    pub fn run_with_retries<X: Send + Clone, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        retries: usize,
+        backoff: Duration,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        // status of the run is shared with its token, so elements are not
+        // retried after the run was stopped
+        let token = match &self.cancel_token {
+            Some(token) => token.clone(),
+            None => CancelToken::new().0,
+        };
+        let config = self.clone().cancel_token(token.clone());
+        let deadline = self.deadline;
+        let stopped = || {
+            token.is_stopped()
+                || matches!(deadline, Some(d) if Instant::now() >= d)
+        };
+        let f = |x: X| {
+            for _ in 0..retries {
+                let err = match f(x.clone()) {
+                    Ok(y) => return Ok(y),
+                    Err(err) => err,
+                };
+                if stopped() { return Err(err) }
+                if backoff != Duration::from_secs(0) {
+                    thread::sleep(backoff);
+                    if stopped() { return Err(err) }
+                }
+            }
+            f(x)
+        };
+        config.run(xs, f, report)
+    }
+
+    /// Run computation using this configuration and report results on
+    /// the calling thread.
+    ///
+    /// See [`run_inline_report`](fn.run_inline_report.html) for details.
+    pub fn run_inline_report<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X, IntoIter=impl Iterator<Item=X> + Send>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E>,
+    ) -> Result<usize, E> {
+        let res = engine::run_inline_report(self, xs, |_| (), |_, x| f(x),
+            |rx, shared| engine::run_report(rx, report, shared));
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and pass position of every
+    /// result to `report`.
+    ///
+    /// See [`run_indexed_report`](fn.run_indexed_report.html) for details.
+    pub fn run_indexed_report<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(usize, Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let res = engine::run(self, xs, f, |rx, shared| {
+            engine::run_report_indexed(rx, report, shared)
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and report results in
+    /// the order of keys provided together with elements.
+    ///
+    /// See [`run_by_key`](fn.run_by_key.html) for details.
+    pub fn run_by_key<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=(usize, X)>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(usize, Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let f = |(key, x)| f(x).map(|y| (key, y));
+        let res = engine::run(self, xs, f, |rx, shared| {
+            engine::run_report_by_key(rx, report, shared)
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration for elements which generate
+    /// new elements.
+    ///
+    /// See [`run_expanding`](fn.run_expanding.html) for details.
+    pub fn run_expanding<X: Send, Y: Send, E: Send>(
+        &self,
+        seeds: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<(Vec<Y>, Vec<X>), E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        // prefetched elements are not sent to workers until prefetching
+        // is done, so it would wait for their completion forever
+        let config = Config { prefetch: 0, ..self.clone() };
+        let queue = &WorkQueue::new(seeds);
+        let len_hint = queue.len();
+        let report = move |(ys, xs): (Vec<Y>, Vec<X>)| {
+            queue.complete(xs);
+            ys.into_iter().try_for_each(&mut report)
+        };
+        let res = engine::run_source(&config, len_hint,
+            |shared| queue.pop(shared),
+            |_| (),
+            |_, x| f(x),
+            |rx, shared| engine::run_report(rx, report, shared),
+        );
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and report results in
+    /// the order of keys skipping keys which are missing for `timeout`.
+    ///
+    /// See [`run_by_key_gapped`](fn.run_by_key_gapped.html) for details.
+    pub fn run_by_key_gapped<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=(usize, X)>,
+        timeout: Duration,
+        on_gap: impl FnMut(usize) + Send,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(usize, Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let f = |(key, x)| f(x).map(|y| (key, y));
+        let res = engine::run(self, xs, f, |rx, shared| {
+            engine::run_report_by_key_gapped(rx, timeout, on_gap, report, shared)
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and send elements with
+    /// higher priorities to workers first.
+    ///
+    /// See [`run_prioritized`](fn.run_prioritized.html) for details.
+    pub fn run_prioritized<P: Ord, X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=(P, X)>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        // results of elements sent ahead can not be reported before results
+        // of preceding elements, so window over positions of sent elements
+        // would block feeding of the preceding ones forever
+        let config = Config {
+            max_reorder_buffer: None,
+            max_in_flight: None,
+            ..self.clone()
+        };
+        // positions are unique and contiguous, so reporting by key
+        // restores the original order
+        let xs = Prioritized::new(xs.into_iter(), config.get_input_capacity());
+        config.run_by_key(xs, f, |_, y| report(y))
+    }
+
+    /// Run computation using this configuration and report only `Some`
+    /// results.
+    ///
+    /// See [`run_filter_map`](fn.run_filter_map.html) for details.
+    pub fn run_filter_map<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Option<Y>, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        self.run(xs, f, |y| match y {
+            Some(y) => report(y),
+            None => Ok(()),
+        })
+    }
+
+    /// Run computation using this configuration and skip results equal to
+    /// the previously reported one.
+    ///
+    /// See [`run_dedup`](fn.run_dedup.html) for details.
+    pub fn run_dedup<X: Send, Y: Send + PartialEq, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(&Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let mut last = None;
+        self.run(xs, f, |y| {
+            if last.as_ref() == Some(&y) { return Ok(()) }
+            report(&y)?;
+            last = Some(y);
+            Ok(())
+        })
+    }
+
+    /// Run computation using this configuration and report every element
+    /// produced by results.
+    ///
+    /// See [`run_flat_map`](fn.run_flat_map.html) for details.
+    pub fn run_flat_map<X: Send, I, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<I, E> + Sync,
+        mut report: impl FnMut(I::Item) -> Result<(), E> + Send
+    ) -> Result<usize, E>
+        where I: IntoIterator + Send
+    {
+        self.run(xs, f, |ys| ys.into_iter().try_for_each(&mut report))
+    }
+
+    /// Run computation using this configuration with distinct error types
+    /// for worker and report closures.
+    ///
+    /// See [`run_split_errors`](fn.run_split_errors.html) for details.
+    pub fn run_split_errors<X: Send, Y: Send, EF: Send, ER: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, EF> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), ER> + Send
+    ) -> Result<usize, RunError<EF, ER>> {
+        let res = self.run_checked(
+            xs,
+            |x| f(x).map_err(Stage::Map),
+            |y| report(y).map_err(Stage::Report),
+        );
+        res.map_err(|err| match err {
+            RunError::Callback(Stage::Map(err)) => RunError::Callback(err),
+            RunError::Callback(Stage::Report(err)) => RunError::Report(err),
+            RunError::WorkerPanic(payload) => RunError::WorkerPanic(payload),
+            RunError::ReportPanic(payload) => RunError::ReportPanic(payload),
+            RunError::SourcePanic(payload) => RunError::SourcePanic(payload),
+            RunError::Cancelled(n) => RunError::Cancelled(n),
+            RunError::TimedOut(i) => RunError::TimedOut(i),
+            RunError::Stalled(n) => RunError::Stalled(n),
+            RunError::WorkersStuck(n) => RunError::WorkersStuck(n),
+            RunError::Report(_) => unreachable!(),
+        })
+    }
+
+    /// Run computation using this configuration for chunks of elements.
+    ///
+    /// See [`run_chunked`](fn.run_chunked.html) for details.
+    pub fn run_chunked<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        chunk_size: usize,
+        f: impl Fn(Vec<X>) -> Result<Vec<Y>, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        let mut xs = xs.into_iter();
+        let chunks = iter::from_fn(|| {
+            let chunk: Vec<X> = xs.by_ref().take(chunk_size).collect();
+            if chunk.is_empty() { None } else { Some(chunk) }
+        });
+        // elements taken from `xs` may not be reported if the run is stopped
+        let mut n = 0;
+        self.run_flat_map(chunks, f, |y| { report(y)?; n += 1; Ok(()) })?;
+        Ok(n)
+    }
+
+    /// Run computation using this configuration and allow report closure
+    /// to stop the run.
+    ///
+    /// See [`run_control_flow`](fn.run_control_flow.html) for details.
+    pub fn run_control_flow<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<ControlFlow<()>, E> + Send
+    ) -> Result<usize, E> {
+        let mut n = 0;
+        let res = self.run_checked(
+            xs,
+            |x| f(x).map_err(Flow::Error),
+            |y| {
+                let flow = report(y).map_err(Flow::Error)?;
+                n += 1;
+                match flow {
+                    ControlFlow::Continue(()) => Ok(()),
+                    ControlFlow::Break(()) => Err(Flow::Break),
+                }
+            },
+        );
+        let res = res.map_err(|err| match err {
+            RunError::Callback(Flow::Error(err)) => RunError::Callback(err),
+            RunError::Callback(Flow::Break) => RunError::Cancelled(n),
+            RunError::Report(_) => unreachable!(),
+            RunError::WorkerPanic(payload) => RunError::WorkerPanic(payload),
+            RunError::ReportPanic(payload) => RunError::ReportPanic(payload),
+            RunError::SourcePanic(payload) => RunError::SourcePanic(payload),
+            RunError::Cancelled(n) => RunError::Cancelled(n),
+            RunError::TimedOut(i) => RunError::TimedOut(i),
+            RunError::Stalled(n) => RunError::Stalled(n),
+            RunError::WorkersStuck(n) => RunError::WorkersStuck(n),
+        });
+        unchecked(res)
+    }
+
+    /// Find the first element in order of `xs` for which `f` returns
+    /// `Some` using this configuration.
+    ///
+    /// See [`find_first`](fn.find_first.html) for details.
+    pub fn find_first<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Option<Y>, E> + Sync,
+    ) -> Result<Option<(usize, Y)>, E> {
+        let mut found = None;
+        self.run_control_flow(
+            xs.into_iter().enumerate(),
+            |(i, x)| Ok(f(x)?.map(|y| (i, y))),
+            |y| match y {
+                Some(y) => {
+                    found = Some(y);
+                    Ok(ControlFlow::Break(()))
+                },
+                None => Ok(ControlFlow::Continue(())),
+            },
+        )?;
+        Ok(found)
+    }
+
+    /// Run computation using this configuration and pass every result to
+    /// several report closures.
+    ///
+    /// See [`run_tee`](fn.run_tee.html) for details.
+    pub fn run_tee<'a, X: Send, Y: Send + Clone, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut reports: Vec<BoxedReport<'a, Y, E>>,
+    ) -> Result<usize, E> {
+        self.run(xs, f, |y| {
+            if let Some((last, rest)) = reports.split_last_mut() {
+                for report in rest {
+                    report(y.clone())?;
+                }
+                last(y)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Run computation using this configuration without preserving order
+    /// of results.
+    ///
+    /// See [`run_unordered`](fn.run_unordered.html) for details.
+    pub fn run_unordered<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let res = engine::run(self, xs, f, |rx, flag| {
+            engine::run_report_unordered(rx, report, flag)
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration with a bounded reorder
+    /// window.
+    ///
+    /// See [`run_windowed`](fn.run_windowed.html) for details.
+    ///
+    /// # Panics
+    /// If `window` is equal to zero.
+    pub fn run_windowed<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        window: usize,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        assert!(window > 0, "reorder window must be non-zero");
+        let res = engine::run(self, xs, f, |rx, shared| {
+            engine::run_report_windowed(rx, window, report, shared)
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration with a bounded latency of
+    /// buffered results.
+    ///
+    /// See [`run_with_max_latency`](fn.run_with_max_latency.html) for
+    /// details.
+    pub fn run_with_max_latency<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        max_latency: Duration,
+        on_reorder_violation: impl FnMut(usize) + Send,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let res = engine::run(self, xs, f, |rx, shared| {
+            engine::run_report_max_latency(
+                rx, max_latency, on_reorder_violation, report, shared,
+            )
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration with the reorder buffer
+    /// spilled to a temporary file.
+    ///
+    /// See [`run_spilled`](fn.run_spilled.html) for details.
+    #[cfg(feature = "spill")]
+    pub fn run_spilled<X: Send, Y: Send, E: Send + From<io::Error>>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        max_buffered: usize,
+        encode: impl FnMut(&Y, &mut Vec<u8>) + Send,
+        decode: impl FnMut(&[u8]) -> Result<Y, E> + Send,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let res = engine::run(self, xs, f, |rx, shared| {
+            engine::run_report_spilled(
+                rx, max_buffered, encode, decode, report, shared,
+            )
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration and call `on_finish` after
+    /// the last result was reported.
+    ///
+    /// See [`run_with_finish`](fn.run_with_finish.html) for details.
+    pub fn run_with_finish<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send,
+        on_finish: impl FnOnce() -> Result<(), E> + Send,
+    ) -> Result<usize, E> {
+        let res = engine::run(self, xs, f, |rx, shared| {
+            engine::run_report(rx, report, shared)?;
+            if shared.is_complete() { on_finish() } else { Ok(()) }
+        });
+        unchecked(res)
+    }
+
+    /// Run computation using this configuration for elements received from
+    /// channel.
+    ///
+    /// See [`run_from_channel`](fn.run_from_channel.html) for details.
+    #[cfg(feature = "crossbeam")]
+    pub fn run_from_channel<X: Send, Y: Send, E: Send>(
+        &self,
+        rx: crossbeam_channel::Receiver<X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let res = engine::run_source(
+            self, 0, engine::recv_source(rx), |_| (), |_, x| f(x),
+            |rx, shared| engine::run_report(rx, report, shared),
+        );
+        unchecked(res)
+    }
+
+    /// Collect results of computation using this configuration into vector.
+    ///
+    /// See [`collect_ordered`](fn.collect_ordered.html) for details.
+    pub fn collect_ordered<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+    ) -> Result<Vec<Y>, E> {
+        let xs = xs.into_iter();
+        let cap = engine::capacity_hint(xs.size_hint());
+        let mut ys = Vec::with_capacity(cap);
+        self.run(xs, f, |y| {
+            ys.push(y);
+            Ok(())
+        })?;
+        Ok(ys)
+    }
+
+    /// Collect results of computation using this configuration into vector
+    /// together with number of processed elements.
+    ///
+    /// See [`run_collect`](fn.run_collect.html) for details.
+    pub fn run_collect<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+    ) -> Result<(usize, Vec<Y>), E> {
+        let xs = xs.into_iter();
+        let cap = engine::capacity_hint(xs.size_hint());
+        let mut ys = Vec::with_capacity(cap);
+        let n = self.run(xs, f, |y| {
+            ys.push(y);
+            Ok(())
+        })?;
+        Ok((n, ys))
+    }
+
+    /// Collect results of computation using this configuration into vector
+    /// keeping results collected before an error.
+    ///
+    /// See [`collect_partial`](fn.collect_partial.html) for details.
+    pub fn collect_partial<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+    ) -> (Vec<Y>, Option<E>) {
+        let xs = xs.into_iter();
+        let cap = engine::capacity_hint(xs.size_hint());
+        let mut ys = Vec::with_capacity(cap);
+        let res = self.run(xs, f, |y| {
+            ys.push(y);
+            Ok(())
+        });
+        (ys, res.err())
+    }
+
+    /// Fold results of computation using this configuration in order of
+    /// elements.
+    ///
+    /// See [`fold_ordered`](fn.fold_ordered.html) for details.
+    pub fn fold_ordered<X: Send, Y: Send, A: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        init: A,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut combine: impl FnMut(A, Y) -> Result<A, E> + Send,
+    ) -> Result<A, E> {
+        let mut acc = Some(init);
+        self.run(xs, f, |y| {
+            let a = acc.take().expect("accumulator is present");
+            acc = Some(combine(a, y)?);
+            Ok(())
+        })?;
+        Ok(acc.expect("accumulator is present"))
+    }
+
+    /// Fold results of computation using this configuration in order of
+    /// elements and stop on the first error.
+    ///
+    /// See [`try_fold_ordered`](fn.try_fold_ordered.html) for details.
+    pub fn try_fold_ordered<X: Send, Y: Send, A: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        init: A,
+        map: impl Fn(X) -> Result<Y, E> + Sync,
+        fold: impl FnMut(A, Y) -> Result<A, E> + Send,
+    ) -> Result<A, E> {
+        self.fold_ordered(xs, init, map, fold)
+    }
+
+    /// Scan results of computation using this configuration in order of
+    /// elements.
+    ///
+    /// See [`scan_ordered`](fn.scan_ordered.html) for details.
+    pub fn scan_ordered<X: Send, Y: Send, A: Send, B, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        init: A,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut step: impl FnMut(&mut A, Y) -> Result<B, E> + Send,
+        mut report: impl FnMut(B) -> Result<(), E> + Send,
+    ) -> Result<A, E> {
+        let mut acc = init;
+        self.run(xs, f, |y| report(step(&mut acc, y)?))?;
+        Ok(acc)
+    }
+
+    /// Reduce results of computation using this configuration with
+    /// per-worker accumulators.
+    ///
+    /// See [`run_reduce`](fn.run_reduce.html) for details.
+    pub fn run_reduce<X: Send, Y: Send, A: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        init: impl Fn() -> A + Sync,
+        fold: impl Fn(A, Y) -> A + Sync,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        combine: impl Fn(A, A) -> A,
+    ) -> Result<A, E> {
+        let out = Mutex::new(Vec::new());
+        let res = engine::run_with_init(
+            self, xs,
+            |_| Partial { acc: Some(init()), out: &out },
+            |p, x| {
+                let y = f(x)?;
+                // accumulator is lost if `fold` panics, but the panic
+                // will be propagated anyway
+                let acc = p.acc.take().expect("accumulator is present");
+                p.acc = Some(fold(acc, y));
+                Ok(())
+            },
+            |rx, shared| engine::run_report_unordered(rx, |()| Ok(()), shared),
+        );
+        unchecked(res)?;
+        let accs = out.into_inner().unwrap();
+        Ok(accs.into_iter().reduce(combine).unwrap_or_else(init))
+    }
+
+    /// Fold results of computation using this configuration in order of
+    /// their arrival.
+    ///
+    /// See [`reduce_unordered`](fn.reduce_unordered.html) for details.
+    pub fn reduce_unordered<X: Send, Y: Send, A: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        init: A,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut fold: impl FnMut(&mut A, Y) + Send,
+    ) -> Result<A, E> {
+        let mut acc = init;
+        self.run_unordered(xs, f, |y| {
+            fold(&mut acc, y);
+            Ok(())
+        })?;
+        Ok(acc)
+    }
+
+    /// Run computation using this configuration and pass every error of `f`
+    /// to `on_error`.
+    ///
+    /// See [`run_with_error_hook`](fn.run_with_error_hook.html) for details.
+    pub fn run_with_error_hook<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        on_error: impl Fn(usize, &E) + Sync,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let f = |(i, x)| {
+            let res = f(x);
+            if let Err(err) = &res { on_error(i, err) }
+            res
+        };
+        self.run(xs.into_iter().enumerate(), f, report)
+    }
+
+    /// Run computation using this configuration and handle errors of `f`
+    /// according to `policy`.
+    ///
+    /// See [`run_with_error_policy`](fn.run_with_error_policy.html) for
+    /// details.
+    pub fn run_with_error_policy<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        policy: ErrorPolicy<'_, E>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, Vec<(usize, E)>> {
+        let mut hook = match policy {
+            ErrorPolicy::FailFast => {
+                let f = |(i, x)| f(x).map(|y| (i, y)).map_err(|e| (i, e));
+                let report = |(i, y)| report(y).map_err(|e| (i, e));
+                return self.run(xs.into_iter().enumerate(), f, report)
+                    .map_err(|err| vec![err]);
+            },
+            ErrorPolicy::CollectAll => None,
+            ErrorPolicy::Continue(hook) => Some(hook),
+        };
+        let mut errors = Vec::new();
+        let mut n = 0;
+        let res = self.run_indexed_report(
+            xs,
+            |x| Ok(f(x)),
+            |i, y| {
+                match y {
+                    Ok(y) => {
+                        report(y).map_err(|e| (i, e))?;
+                        n += 1;
+                    },
+                    Err(err) => match &mut hook {
+                        Some(hook) => hook(i, err),
+                        None => errors.push((i, err)),
+                    },
+                }
+                Ok(())
+            },
+        );
+        if let Err(err) = res { errors.push(err) }
+        if errors.is_empty() { Ok(n) } else { Err(errors) }
+    }
+
+    /// Run computation using this configuration and stop it after
+    /// `threshold` consecutive errors of `f`.
+    ///
+    /// See [`run_with_circuit_breaker`](fn.run_with_circuit_breaker.html)
+    /// for details.
+    ///
+    /// # Panics
+    /// If `threshold` is equal to 0.
+    pub fn run_with_circuit_breaker<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        threshold: usize,
+        mut on_error: impl FnMut(usize, E) + Send,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        mut report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, CircuitError<E>> {
+        assert!(threshold > 0, "circuit breaker threshold must be non-zero");
+        // errors of the current run of failed elements
+        let mut streak = Vec::new();
+        let mut n = 0;
+        self.run_indexed_report(
+            xs,
+            |x| Ok(f(x)),
+            |i, y| {
+                match y {
+                    Ok(y) => {
+                        for (j, err) in streak.drain(..) { on_error(j, err) }
+                        report(y).map_err(CircuitError::Report)?;
+                        n += 1;
+                    },
+                    Err(err) => {
+                        streak.push((i, err));
+                        if streak.len() == threshold {
+                            let errors = mem::take(&mut streak);
+                            return Err(CircuitError::Open(errors));
+                        }
+                    },
+                }
+                Ok(())
+            },
+        )?;
+        for (j, err) in streak { on_error(j, err) }
+        Ok(n)
+    }
+
+    /// Run computation using this configuration and collect results of all
+    /// elements including errors.
+    ///
+    /// See [`run_results`](fn.run_results.html) for details.
+    pub fn run_results<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+    ) -> Vec<Result<Y, E>> {
+        let xs = xs.into_iter();
+        let cap = engine::capacity_hint(xs.size_hint());
+        let mut ys = Vec::with_capacity(cap);
+        let res = self.run(xs, |x| Ok::<_, Infallible>(f(x)), |y| {
+            ys.push(y);
+            Ok(())
+        });
+        if let Err(err) = res { match err {} }
+        ys
+    }
+
+    /// Run computation using this configuration and write results to `w`.
+    ///
+    /// See [`run_to_writer`](fn.run_to_writer.html) for details.
+    pub fn run_to_writer<X: Send, E: Send + From<io::Error>>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Vec<u8>, E> + Sync,
+        mut w: impl io::Write + Send,
+    ) -> Result<usize, E> {
+        let n = self.run(xs, f, |buf| w.write_all(&buf).map_err(E::from))?;
+        w.flush()?;
+        Ok(n)
+    }
+
+    /// Run computation using this configuration for lines read from
+    /// `reader`.
+    ///
+    /// See [`run_lines`](fn.run_lines.html) for details.
+    pub fn run_lines<Y: Send, E: Send + From<io::Error>>(
+        &self,
+        reader: impl io::BufRead,
+        f: impl Fn(String) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send,
+    ) -> Result<usize, E> {
+        let mut lines = reader.lines();
+        let mut failed = false;
+        // read error is passed to a worker, so it will be returned
+        // after results of the preceding lines
+        let lines = iter::from_fn(move || {
+            if failed { return None; }
+            let line = lines.next()?;
+            failed = line.is_err();
+            Some(line)
+        });
+        self.run(lines, |line| f(line?), report)
+    }
+
+    /// Run computation using this configuration and collect all results
+    /// and errors without stopping on errors.
+    ///
+    /// See [`run_collect_errors`](fn.run_collect_errors.html) for details.
+    pub fn run_collect_errors<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+    ) -> (Vec<Y>, Vec<(usize, E)>) {
+        let xs = xs.into_iter();
+        let cap = engine::capacity_hint(xs.size_hint());
+        let mut ys = Vec::with_capacity(cap);
+        let mut errors = Vec::new();
+        let res = self.run_indexed_report(
+            xs,
+            |x| Ok::<_, Infallible>(f(x)),
+            |i, y| {
+                match y {
+                    Ok(y) => ys.push(y),
+                    Err(err) => errors.push((i, err)),
+                }
+                Ok(())
+            },
+        );
+        if let Err(err) = res { match err {} }
+        (ys, errors)
+    }
+
+    /// Create iterator over results of computation using this
+    /// configuration.
+    ///
+    /// See [`par_map`](fn.par_map.html) for details.
+    pub fn par_map<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    ) -> ParMap<Y, E>
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        ParMap::new(self, xs, f)
+    }
+
+    /// Run computation using this configuration with a timeout for
+    /// computation of every element.
+    ///
+    /// See [`run_with_timeout`](fn.run_with_timeout.html) for details.
+    pub fn run_with_timeout<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        timeout: Duration,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+        report: impl FnMut(Y) -> Result<(), E> + Send,
+    ) -> Result<usize, RunError<E>>
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        timeout::run(self, xs, timeout, f, report)
+    }
+
+    /// Run computation using this configuration and stop waiting for its
+    /// threads if they do not exit in `join_timeout` after the report
+    /// closure has finished.
+    ///
+    /// See [`run_with_join_timeout`](fn.run_with_join_timeout.html) for
+    /// details.
+    pub fn run_with_join_timeout<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        join_timeout: Duration,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+        report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+    ) -> Result<usize, RunError<E>>
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        join::run(self, xs, join_timeout, f, report)
+    }
+
+    /// Get stream over results of parallel computation using this
+    /// configuration.
+    ///
+    /// See [`par_stream`](fn.par_stream.html) for details.
+    #[cfg(feature = "futures")]
+    pub fn par_stream<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    ) -> crate::ParStream<Y, E>
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        crate::ParStream::new(self, xs, f)
+    }
+
+    /// Run computation on a background thread using this configuration and
+    /// return its result as a future.
+    ///
+    /// See [`run_async`](fn.run_async.html) for details.
+    #[cfg(feature = "async")]
+    pub fn run_async<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+        report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+    ) -> impl std::future::Future<Output=Result<usize, E>>
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        crate::run_async::RunFuture::new(self, xs, f, report)
+    }
+
+    /// Run computation on a background thread using this configuration and
+    /// return handle of the run.
+    ///
+    /// If cancellation token is set, its handle cancels the run as well.
+    ///
+    /// See [`spawn_run`](fn.spawn_run.html) for details.
+    pub fn spawn_run<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+        report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+    ) -> RunHandle<E>
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        RunHandle::new(self, xs, f, report)
+    }
+
+    /// Run computation using this configuration and return right after
+    /// the report closure has finished without waiting for workers.
+    ///
+    /// Cancellation token, if set, cancels the background run as well, but
+    /// the run does not change state of the token, so it can be used by
+    /// other runs right after return.
+    ///
+    /// See [`run_detached`](fn.run_detached.html) for details.
+    pub fn run_detached<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+        report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+    ) -> Result<usize, E>
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        // threads of the run may outlive the call, so they use a separate
+        // token to not affect later runs with the configured one
+        let token = match &self.cancel_token {
+            Some(token) => token.child(),
+            None => CancelToken::new().0,
+        };
+        let config = self.clone().cancel_token(token);
+        let (tx, rx) = mpsc::channel();
+        let handle = RunHandle::spawn(&config, move |config| {
+            engine::run(&config, xs, f, move |rx, shared| {
+                match engine::run_report(rx, report, shared) {
+                    Ok(()) => if shared.is_complete() {
+                        let _ = tx.send(Ok(shared.coord.count().unwrap()));
+                    },
+                    Err(err) => {
+                        // the error is already returned to the caller, so
+                        // the detached run is stopped as a cancelled one
+                        shared.coord.try_stop(Status::Cancelled);
+                        let _ = tx.send(Err(err));
+                    },
+                }
+                Ok(())
+            })
+        });
+        match rx.recv() {
+            Ok(res) => res,
+            // run was stopped by a panic, cancellation or deadline
+            Err(_) => handle.join(),
+        }
+    }
+
+    /// Run computation using this configuration on a background thread and
+    /// send results in order of elements to the returned channel.
+    ///
+    /// See [`run_to_channel`](fn.run_to_channel.html) for details.
+    #[cfg(feature = "crossbeam")]
+    pub fn run_to_channel<X, Y, E>(
+        &self,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    ) -> (crossbeam_channel::Receiver<Result<Y, E>>, RunHandle<Infallible>)
+        where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+    {
+        let (tx, rx) = crossbeam_channel::bounded(self.get_report_capacity());
+        let handle = RunHandle::spawn(self, move |config| {
+            config.run_control_flow(xs, move |x| Ok(f(x)), move |y| {
+                let failed = y.is_err();
+                if tx.send(y).is_err() || failed {
+                    Ok(ControlFlow::Break(()))
+                } else {
+                    Ok(ControlFlow::Continue(()))
+                }
+            }).map_err(RunError::Callback)
+        });
+        (rx, handle)
+    }
+
+    /// Run computation using this configuration on threads spawned in
+    /// the existing crossbeam `scope`.
+    ///
+    /// Spawn hook, thread names and stack size are ignored.
+    ///
+    /// See [`run_in_scope`](fn.run_in_scope.html) for details.
+    #[cfg(feature = "crossbeam")]
+    pub fn run_in_scope<'env, X: Send, Y: Send, E: Send>(
+        &self,
+        scope: &crossbeam_utils::thread::Scope<'env>,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        // SAFETY: the hook is owned by `config`, which is dropped before
+        // this function returns, and the run waits for all tasks passed to
+        // the hook, so the scope is not used after the borrow ends. Tasks
+        // are `'static`, so they satisfy any lifetime of the scope.
+        let scope: &'static crossbeam_utils::thread::Scope<'static> =
+            unsafe { &*(scope as *const _ as *const _) };
+        let config = self.clone()
+            .spawn_hook(move |task| { scope.spawn(move |_| task()); });
+        config.run(xs, f, report)
+    }
+
+    /// Run computation using this configuration on threads of the rayon
+    /// `pool`.
+    ///
+    /// If number of threads is not set, it's equal to number of threads in
+    /// `pool` minus one. Spawn hook, thread names and stack size are ignored.
+    ///
+    /// See [`run_in_pool`](fn.run_in_pool.html) for details.
+    ///
+    /// # Panics
+    /// If `pool` has less than `threads + 1` threads.
+    #[cfg(feature = "rayon")]
+    pub fn run_in_pool<X: Send, Y: Send, E: Send>(
+        &self,
+        pool: &rayon::ThreadPool,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let pool_threads = pool.current_num_threads();
+        let threads = match self.threads {
+            0 => pool_threads.saturating_sub(1),
+            n => n,
+        };
+        assert!(threads > 0 && threads < pool_threads,
+            "rayon pool must have at least {} threads", threads + 1);
+        // SAFETY: the hook is owned by `config`, which is dropped before
+        // this function returns, and the run waits for all tasks passed to
+        // the hook, so the pool is not used after the borrow ends.
+        let pool: &'static rayon::ThreadPool = unsafe { &*(pool as *const _) };
+        let config = self.clone()
+            .threads(threads)
+            .spawn_hook(move |task| pool.spawn(task));
+        config.run(xs, f, report)
+    }
+}
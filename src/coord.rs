@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// Status of a run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub(crate) enum Status {
+    /// Feeder still sends elements to workers.
+    Running = 0,
+    /// Feeder has sent all elements and published their number.
+    Done = 1,
+    /// One of callbacks has returned an error.
+    Error = 2,
+    WorkerPanic = 3,
+    ReportPanic = 4,
+    Cancelled = 5,
+    Deadline = 6,
+    Stalled = 7,
+    SourcePanic = 8,
+}
+
+impl Status {
+    fn from_u8(v: u8) -> Self {
+        match v {
+            0 => Status::Running,
+            1 => Status::Done,
+            2 => Status::Error,
+            3 => Status::WorkerPanic,
+            4 => Status::ReportPanic,
+            5 => Status::Cancelled,
+            6 => Status::Deadline,
+            7 => Status::Stalled,
+            8 => Status::SourcePanic,
+            _ => unreachable!("invalid run status"),
+        }
+    }
+
+    /// Check if status stops the run.
+    fn is_stop(self) -> bool {
+        !matches!(self, Status::Running | Status::Done)
+    }
+}
+
+/// Status of a run together with number of elements published by feeder.
+pub(crate) struct Coordination {
+    // valid only if status is `Done`
+    count: AtomicUsize,
+    // shared with a cancellation token
+    status: Arc<AtomicU8>,
+    // flag of a parent token, it is only checked for cancellation
+    parent: Option<Arc<AtomicU8>>,
+}
+
+impl Coordination {
+    pub(crate) fn new(
+        status: Arc<AtomicU8>, parent: Option<Arc<AtomicU8>>,
+    ) -> Self {
+        Self { count: AtomicUsize::new(0), status, parent }
+    }
+
+    pub(crate) fn status(&self) -> Status {
+        let status = Status::from_u8(self.status.load(Ordering::Acquire));
+        match &self.parent {
+            Some(parent) if !status.is_stop()
+                && parent.load(Ordering::Acquire) == Status::Cancelled as u8
+                => Status::Cancelled,
+            _ => status,
+        }
+    }
+
+    /// Check if the run was stopped by an error, a panic, cancellation,
+    /// deadline or watchdog.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.status().is_stop()
+    }
+
+    /// Number of elements sent to workers if feeder has finished.
+    pub(crate) fn count(&self) -> Option<usize> {
+        match self.status() {
+            Status::Done => Some(self.count.load(Ordering::Acquire)),
+            _ => None,
+        }
+    }
+
+    /// Publish number of elements sent to workers, returns `false` if the
+    /// run was already stopped.
+    pub(crate) fn publish(&self, n: usize) -> bool {
+        // count is written before status, so it's visible to everyone who
+        // observes `Done`
+        self.count.store(n, Ordering::Release);
+        self.status.compare_exchange(
+            Status::Running as u8, Status::Done as u8,
+            Ordering::AcqRel, Ordering::Acquire,
+        ).is_ok()
+    }
+
+    /// Set stop status overriding the previous one.
+    pub(crate) fn stop(&self, status: Status) {
+        debug_assert!(status.is_stop());
+        self.status.store(status as u8, Ordering::Release);
+    }
+
+    /// Set stop status only if the run was not stopped already.
+    pub(crate) fn try_stop(&self, status: Status) {
+        try_stop(&self.status, status);
+    }
+}
+
+/// Set stop `status` only if `flag` does not contain a stop status.
+pub(crate) fn try_stop(flag: &AtomicU8, status: Status) {
+    debug_assert!(status.is_stop());
+    let _ = flag.fetch_update(Ordering::AcqRel, Ordering::Acquire, |v| {
+        if Status::from_u8(v).is_stop() { None } else { Some(status as u8) }
+    });
+}
+
+/// Check if `flag` contains a stop status.
+pub(crate) fn is_stopped(flag: &AtomicU8) -> bool {
+    Status::from_u8(flag.load(Ordering::Acquire)).is_stop()
+}
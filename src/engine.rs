@@ -0,0 +1,1327 @@
+use std::any::Any;
+use std::collections::{BTreeSet, BinaryHeap, VecDeque};
+use std::collections::binary_heap::PeekMut;
+use std::cmp;
+use std::iter;
+use std::ops::Range;
+use std::panic;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "spill")]
+use std::{collections::BTreeMap, io};
+
+use crate::sync::{self, channel};
+
+use crate::{ActiveWorkers, Config, PauseHandle, ReportPressure, RunError};
+use crate::coord::{Coordination, Status};
+use crate::pressure::ShedHook;
+use crate::spawn::{Latch, LatchGuard, Spawner};
+#[cfg(feature = "spill")]
+use crate::spill::SpillFile;
+use crate::stats::Counters;
+
+struct State<T> {
+    pos: usize,
+    payload: T,
+}
+
+impl<T> PartialEq for State<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.pos == other.pos
+    }
+}
+
+impl<T> Eq for State<T> { }
+
+impl<T> Ord for State<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        other.pos.cmp(&self.pos)
+    }
+}
+
+impl<T> PartialOrd for State<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Result buffered for reporting in the order of keys, results with equal
+/// keys are ordered by their arrival.
+struct Keyed<T> {
+    key: usize,
+    // position of the result in the order of arrival
+    seq: usize,
+    payload: T,
+}
+
+impl<T> PartialEq for Keyed<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.key, self.seq) == (other.key, other.seq)
+    }
+}
+
+impl<T> Eq for Keyed<T> { }
+
+impl<T> Ord for Keyed<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (other.key, other.seq).cmp(&(self.key, self.seq))
+    }
+}
+
+impl<T> PartialOrd for Keyed<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub(crate) enum ReportMsg<T, E> {
+    None,
+    NewResult((usize, Result<T, E>)),
+}
+
+pub(crate) type ReportRx<T, E> = channel::Receiver<ReportMsg<T, E>>;
+
+/// Report results in the order of their positions.
+pub(crate) fn run_report<T, E>(
+    rx: ReportRx<T, E>,
+    mut f: impl FnMut(T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    run_report_indexed(rx, |_, payload| f(payload), shared)
+}
+
+/// Same as `run_report`, but `f` also receives position of the result.
+pub(crate) fn run_report_indexed<T, E>(
+    rx: ReportRx<T, E>,
+    f: impl FnMut(usize, T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    run_report_sized(rx, |_| 0, f, shared)
+}
+
+/// Same as `run_report_indexed`, but total size of buffered results
+/// computed using `size_of` is tracked for limiting it by the feeder.
+pub(crate) fn run_report_sized<T, E>(
+    rx: ReportRx<T, E>,
+    size_of: impl Fn(&T) -> usize,
+    mut f: impl FnMut(usize, T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    trace_span!("parstream::report");
+    let size = |res: &Result<T, E>| res.as_ref().map_or(0, &size_of);
+    let coord = &shared.coord;
+    // errors are buffered too, so error of the element with the lowest
+    // position is always returned
+    let mut buf: BinaryHeap<State<Result<T, E>>> =
+        BinaryHeap::with_capacity(shared.reorder_capacity());
+    let mut n = 0;
+    // positions of dropped results which were not reached yet
+    let mut shed = BTreeSet::new();
+
+    use self::ReportMsg::*;
+    while let Some(val) = shared.recv(&rx) {
+        if coord.is_stopped() { break }
+
+        match val {
+            NewResult((i, payload)) => {
+                if i != n {
+                    shared.buffer_bytes(size(&payload), true);
+                    buf.push(State { pos: i, payload });
+                    if let Some(stats) = &shared.stats {
+                        stats.buffered(buf.len());
+                    }
+                    trace_event!(index = i, buffered = buf.len(), "buffered");
+                    if let Some((limit, hook)) = &shared.shed {
+                        while buf.len() > *limit {
+                            let pm = match buf.peek_mut() {
+                                Some(pm) if pm.payload.is_ok() => pm,
+                                _ => break,
+                            };
+                            let State { pos, payload } = PeekMut::pop(pm);
+                            shared.buffer_bytes(size(&payload), false);
+                            trace_event!(index = pos, "shed");
+                            hook(pos);
+                            shed.insert(pos);
+                            shared.reported();
+                        }
+                    }
+                    continue;
+                }
+                f(n, payload?)?;
+
+                n += 1;
+                shared.reported();
+                let start = n;
+                loop {
+                    // buffered results are not reported after a stop
+                    if shared.is_stopped() { return Ok(()) }
+                    while shed.remove(&n) { n += 1 }
+                    let pm = match buf.peek_mut() {
+                        Some(pm) => pm,
+                        Option::None => break,
+                    };
+                    assert!(pm.pos >= n);
+                    if pm.pos != n { break }
+                    let payload = PeekMut::pop(pm).payload;
+                    shared.buffer_bytes(size(&payload), false);
+                    f(n, payload?)?;
+                    n += 1;
+                    shared.reported();
+                }
+                if n != start {
+                    trace_event!(drained = n - start, buffered = buf.len(),
+                        "drained");
+                }
+            },
+            None => (),
+        }
+
+        if coord.count() == Some(n) { break; }
+    }
+    Ok(())
+}
+
+/// Same as `run_report`, but if more than `limit` results are buffered,
+/// results with the highest positions are encoded using `encode` and moved
+/// to a temporary file, from which they are read back and decoded using
+/// `decode` when their turn comes. Errors are never spilled.
+#[cfg(feature = "spill")]
+pub(crate) fn run_report_spilled<T, E: From<io::Error>>(
+    rx: ReportRx<T, E>,
+    limit: usize,
+    mut encode: impl FnMut(&T, &mut Vec<u8>),
+    mut decode: impl FnMut(&[u8]) -> Result<T, E>,
+    mut f: impl FnMut(T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    trace_span!("parstream::report");
+    let coord = &shared.coord;
+    let mut buf: BTreeMap<usize, Result<T, E>> = BTreeMap::new();
+    let mut file = SpillFile::new();
+    let mut bytes = Vec::new();
+    let mut n = 0;
+
+    while let Some(val) = shared.recv(&rx) {
+        if coord.is_stopped() { break }
+
+        if let ReportMsg::NewResult((i, payload)) = val {
+            if i != n {
+                buf.insert(i, payload);
+                while buf.len() > limit {
+                    let pos = match buf.iter().rev().find(|(_, r)| r.is_ok()) {
+                        Some((&pos, _)) => pos,
+                        None => break,
+                    };
+                    if let Some(Ok(val)) = buf.remove(&pos) {
+                        bytes.clear();
+                        encode(&val, &mut bytes);
+                        file.write(pos, &bytes)?;
+                        trace_event!(index = pos, size = bytes.len(),
+                            "spilled");
+                    }
+                }
+                continue;
+            }
+            f(payload?)?;
+            n += 1;
+            shared.reported();
+            loop {
+                if shared.is_stopped() { return Ok(()) }
+                let payload = match buf.remove(&n) {
+                    Some(payload) => payload,
+                    None if file.read(n, &mut bytes)? => decode(&bytes),
+                    None => break,
+                };
+                f(payload?)?;
+                n += 1;
+                shared.reported();
+            }
+        }
+
+        if coord.count() == Some(n) { break; }
+    }
+    Ok(())
+}
+
+/// Report results in the order of their positions, but do not wait for
+/// a missing result after a result with position at least `window` higher
+/// than it was received. In that case buffered results which fell out of
+/// the window are reported in the order of positions, while the missing
+/// results are reported right after their arrival.
+pub(crate) fn run_report_windowed<T, E>(
+    rx: ReportRx<T, E>,
+    window: usize,
+    mut f: impl FnMut(T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    trace_span!("parstream::report");
+    let coord = &shared.coord;
+    let mut buf: BinaryHeap<State<Result<T, E>>> =
+        BinaryHeap::with_capacity(shared.reorder_capacity());
+    // all results with lower positions were either reported or skipped
+    let mut n = 0;
+    let mut reported = 0;
+
+    use self::ReportMsg::*;
+    while let Some(val) = shared.recv(&rx) {
+        if coord.is_stopped() { break }
+
+        if let NewResult((i, payload)) = val {
+            buf.push(State { pos: i, payload });
+            // results with positions below `low` fell out of the window
+            let low = (i + 1).saturating_sub(window);
+            while let Some(pm) = buf.peek_mut() {
+                if pm.pos > n && pm.pos >= low { break }
+                if shared.is_stopped() { return Ok(()) }
+                let State { pos, payload } = PeekMut::pop(pm);
+                if pos > n {
+                    trace_event!(skipped = pos - n, "window slid");
+                }
+                f(payload?)?;
+                n = cmp::max(n, pos + 1);
+                reported += 1;
+                shared.reported();
+            }
+        }
+
+        if coord.count() == Some(reported) { break; }
+    }
+    Ok(())
+}
+
+/// Report results in the order of their positions, but do not wait for
+/// a missing result if the oldest buffered result has waited for
+/// `max_latency`. In that case all buffered results are reported in the
+/// order of positions, while the missing results are reported right after
+/// their arrival and passed to `on_violation`.
+pub(crate) fn run_report_max_latency<T, E>(
+    rx: ReportRx<T, E>,
+    max_latency: Duration,
+    mut on_violation: impl FnMut(usize),
+    mut f: impl FnMut(T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    trace_span!("parstream::report");
+    let coord = &shared.coord;
+    let mut buf: BinaryHeap<State<(Instant, Result<T, E>)>> =
+        BinaryHeap::with_capacity(shared.reorder_capacity());
+    // all results with lower positions were either reported or skipped
+    let mut n = 0;
+    let mut reported = 0;
+    // arrival time of the oldest buffered result
+    let mut oldest: Option<Instant> = None;
+
+    loop {
+        let msg = match oldest {
+            Some(t) => {
+                let left = max_latency.saturating_sub(t.elapsed());
+                shared.recv_timeout(&rx, left)
+            },
+            None => match shared.recv(&rx) {
+                Some(msg) => Recv::Msg(msg),
+                None => Recv::Closed,
+            },
+        };
+        if coord.is_stopped() { break }
+
+        let mut flush = false;
+        match msg {
+            Recv::Msg(ReportMsg::NewResult((i, payload))) if i < n => {
+                on_violation(i);
+                f(payload?)?;
+                reported += 1;
+                shared.reported();
+            },
+            Recv::Msg(ReportMsg::NewResult((i, payload))) => {
+                let now = Instant::now();
+                buf.push(State { pos: i, payload: (now, payload) });
+                oldest = oldest.or(Some(now));
+            },
+            Recv::Msg(ReportMsg::None) => (),
+            Recv::Timeout => flush = true,
+            Recv::Closed => break,
+        }
+
+        let len = buf.len();
+        while let Some(pm) = buf.peek_mut() {
+            if pm.pos > n && !flush { break }
+            if shared.is_stopped() { return Ok(()) }
+            let State { pos, payload: (_, payload) } = PeekMut::pop(pm);
+            if pos > n {
+                trace_event!(skipped = pos - n, "max latency exceeded");
+            }
+            f(payload?)?;
+            n = cmp::max(n, pos + 1);
+            reported += 1;
+            shared.reported();
+        }
+        if buf.len() != len {
+            oldest = buf.iter().map(|s| s.payload.0).min();
+        }
+
+        if coord.count() == Some(reported) { break; }
+    }
+    Ok(())
+}
+
+/// Report results in the order of keys which are passed together with
+/// results. Results with duplicate keys are reported in the order of
+/// their arrival, results which follow a gap in keys are reported after all
+/// results were received.
+pub(crate) fn run_report_by_key<T, E>(
+    rx: ReportRx<(usize, T), E>,
+    mut f: impl FnMut(usize, T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    let coord = &shared.coord;
+    let mut buf: BinaryHeap<Keyed<T>> =
+        BinaryHeap::with_capacity(shared.reorder_capacity());
+    // smallest key which was not reported yet
+    let mut next_key = 0;
+    let mut received = 0;
+
+    use self::ReportMsg::*;
+    while let Some(val) = shared.recv(&rx) {
+        if coord.is_stopped() { break }
+
+        if let NewResult((_, payload)) = val {
+            let (key, payload) = payload?;
+            received += 1;
+            buf.push(Keyed { key, seq: received, payload });
+            while let Some(pm) = buf.peek_mut() {
+                if pm.key > next_key { break }
+                if shared.is_stopped() { return Ok(()) }
+                let Keyed { key, payload, .. } = PeekMut::pop(pm);
+                f(key, payload)?;
+                next_key = cmp::max(next_key, key + 1);
+                shared.reported();
+            }
+        }
+
+        if coord.count() == Some(received) { break; }
+    }
+
+    // all results were received, so gaps will not be filled anymore
+    if coord.count() == Some(received) {
+        while let Some(Keyed { key, payload, .. }) = buf.pop() {
+            if shared.is_stopped() { return Ok(()) }
+            f(key, payload)?;
+            shared.reported();
+        }
+    }
+    Ok(())
+}
+
+/// Same as `run_report_by_key`, but if the smallest unreported key is
+/// missing for `timeout` while bigger keys are buffered, missing keys are
+/// skipped and passed to `on_gap`. Results with skipped keys which arrive
+/// later are dropped.
+pub(crate) fn run_report_by_key_gapped<T, E>(
+    rx: ReportRx<(usize, T), E>,
+    timeout: Duration,
+    mut on_gap: impl FnMut(usize),
+    mut f: impl FnMut(usize, T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    let coord = &shared.coord;
+    let mut buf: BinaryHeap<Keyed<T>> =
+        BinaryHeap::with_capacity(shared.reorder_capacity());
+    let mut next_key = 0;
+    let mut received = 0;
+    let mut skipped: Vec<Range<usize>> = Vec::new();
+    // missing key and time since which it's missing
+    let mut gap: Option<(usize, Instant)> = None;
+
+    loop {
+        let msg = match gap {
+            Some((_, since)) => {
+                let left = timeout.saturating_sub(since.elapsed());
+                shared.recv_timeout(&rx, left)
+            },
+            None => match shared.recv(&rx) {
+                Some(msg) => Recv::Msg(msg),
+                None => Recv::Closed,
+            },
+        };
+        if coord.is_stopped() { break }
+
+        match msg {
+            Recv::Msg(ReportMsg::NewResult((_, payload))) => {
+                let (key, payload) = payload?;
+                received += 1;
+                if key < next_key && skipped.iter().any(|r| r.contains(&key)) {
+                    trace_event!(key, "dropped");
+                } else {
+                    buf.push(Keyed { key, seq: received, payload });
+                }
+            },
+            Recv::Msg(ReportMsg::None) => (),
+            Recv::Timeout => if let Some(pm) = buf.peek() {
+                trace_event!(skipped = pm.key - next_key, "gap skipped");
+                (next_key..pm.key).for_each(&mut on_gap);
+                skipped.push(next_key..pm.key);
+                next_key = pm.key;
+            },
+            Recv::Closed => break,
+        }
+
+        while let Some(pm) = buf.peek_mut() {
+            if pm.key > next_key { break }
+            if shared.is_stopped() { return Ok(()) }
+            let Keyed { key, payload, .. } = PeekMut::pop(pm);
+            f(key, payload)?;
+            next_key = cmp::max(next_key, key + 1);
+            shared.reported();
+        }
+        // buffered results always follow a gap after draining
+        gap = match gap {
+            _ if buf.is_empty() => None,
+            Some((key, since)) if key == next_key => Some((key, since)),
+            _ => Some((next_key, Instant::now())),
+        };
+
+        if coord.count() == Some(received) { break; }
+    }
+
+    // all results were received, so gaps will not be filled anymore
+    if coord.count() == Some(received) {
+        while let Some(Keyed { key, payload, .. }) = buf.pop() {
+            if shared.is_stopped() { return Ok(()) }
+            if key > next_key {
+                (next_key..key).for_each(&mut on_gap);
+            }
+            f(key, payload)?;
+            next_key = cmp::max(next_key, key + 1);
+            shared.reported();
+        }
+    }
+    Ok(())
+}
+
+/// Report results in the order of their arrival.
+pub(crate) fn run_report_unordered<T, E>(
+    rx: ReportRx<T, E>,
+    mut f: impl FnMut(T) -> Result<(), E>,
+    shared: &Shared,
+) -> Result<(), E> {
+    let coord = &shared.coord;
+    let mut n = 0;
+
+    use self::ReportMsg::*;
+    while let Some(val) = shared.recv(&rx) {
+        if coord.is_stopped() { break }
+
+        if let NewResult((_, payload)) = val {
+            f(payload?)?;
+            n += 1;
+            shared.reported();
+        }
+
+        if coord.count() == Some(n) { break; }
+    }
+    Ok(())
+}
+
+/// Maximum number of elements preallocated based on an upper bound of
+/// a size hint, which may be much larger than the actual length.
+const MAX_PREALLOC: usize = 1 << 16;
+
+/// Number of elements to preallocate for an iterator with `size_hint`.
+/// The upper bound is trusted only up to `MAX_PREALLOC` elements, unless
+/// it's equal to the lower bound. Iterator without the upper bound may be
+/// infinite, so its lower bound is trusted only up to `MAX_PREALLOC` too.
+pub(crate) fn capacity_hint((lower, upper): (usize, Option<usize>)) -> usize {
+    match upper {
+        Some(upper) => cmp::min(upper, cmp::max(lower, MAX_PREALLOC)),
+        None => cmp::min(lower, MAX_PREALLOC),
+    }
+}
+
+/// Result of receiving a message with a timeout.
+pub(crate) enum Recv<T> {
+    Msg(T),
+    Timeout,
+    /// Channel is disconnected, deadline has passed or watchdog has stopped
+    /// the run.
+    Closed,
+}
+
+type PanicPayload = Mutex<Option<Box<dyn Any + Send>>>;
+
+/// Limits number of elements which were sent to workers, but were not
+/// reported yet, and total size of buffered results.
+struct Window {
+    size: usize,
+    lock: Mutex<()>,
+    cvar: Condvar,
+}
+
+/// Number of active workers for adaptive runs, workers with index not
+/// smaller than `active` are parked.
+struct Adaptive {
+    min: usize,
+    max: usize,
+    active: Mutex<usize>,
+    cvar: Condvar,
+    last_change: Mutex<Instant>,
+}
+
+/// State shared between feeder, workers and report thread.
+pub(crate) struct Shared {
+    pub(crate) coord: Coordination,
+    panic_payload: PanicPayload,
+    reported: AtomicUsize,
+    // lowest position of element for which worker has returned an error
+    error_pos: AtomicUsize,
+    window: Option<Window>,
+    max_buffer_bytes: Option<usize>,
+    buffered_bytes: AtomicUsize,
+    on_progress: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    deadline: Option<Instant>,
+    // minimal interval between sending elements to workers
+    rate_interval: Option<Duration>,
+    pause: Option<PauseHandle>,
+    active: Option<ActiveWorkers>,
+    // number of elements sent to workers
+    fed: AtomicUsize,
+    // set after report closure has returned
+    report_done: AtomicBool,
+    heartbeat: Option<Duration>,
+    // expected number of elements in the run
+    len_hint: usize,
+    adaptive: Option<Adaptive>,
+    stats: Option<Arc<Counters>>,
+    // limit of buffered results and hook for dropped ones
+    shed: Option<(usize, ShedHook)>,
+}
+
+impl Shared {
+    fn new(config: &Config, len_hint: usize) -> Self {
+        // both limits are enforced by the window on sent elements
+        let size = match (config.max_reorder_buffer, config.max_in_flight) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+        let window = match (size, config.max_buffer_bytes) {
+            (None, None) => None,
+            (size, _) => Some(Window {
+                size: size.unwrap_or(usize::MAX),
+                lock: Mutex::new(()),
+                cvar: Condvar::new(),
+            }),
+        };
+        let (status, parent) = match &config.cancel_token {
+            Some(token) => (token.flag(), token.parent()),
+            None => (Arc::new(AtomicU8::new(Status::Running as u8)), None),
+        };
+        Self {
+            coord: Coordination::new(status, parent),
+            panic_payload: Mutex::new(None),
+            reported: AtomicUsize::new(0),
+            error_pos: AtomicUsize::new(usize::MAX),
+            window,
+            max_buffer_bytes: config.max_buffer_bytes,
+            buffered_bytes: AtomicUsize::new(0),
+            on_progress: config.on_progress.clone(),
+            deadline: config.deadline,
+            rate_interval: config.max_rate
+                .map(|rate| Duration::from_secs(1) / rate),
+            pause: config.pause_handle.clone(),
+            active: config.active_workers.clone(),
+            fed: AtomicUsize::new(0),
+            report_done: AtomicBool::new(false),
+            heartbeat: config.heartbeat,
+            len_hint,
+            adaptive: config.min_threads.map(|min| Adaptive {
+                min,
+                max: config.get_threads(),
+                active: Mutex::new(min),
+                cvar: Condvar::new(),
+                last_change: Mutex::new(Instant::now()),
+            }),
+            stats: config.stats.clone(),
+            shed: match &config.report_pressure {
+                ReportPressure::Shed(hook) => {
+                    Some((config.get_report_capacity(), hook.clone()))
+                },
+                _ => None,
+            },
+        }
+    }
+
+    /// Number of results to preallocate in the reorder buffer, it never
+    /// holds more results than elements in the run or in the window.
+    /// Elements may be never buffered, so the capacity is also limited by
+    /// `MAX_PREALLOC`.
+    fn reorder_capacity(&self) -> usize {
+        let cap = cmp::min(self.len_hint, MAX_PREALLOC);
+        match &self.window {
+            Some(w) => cmp::min(cap, w.size),
+            None => cap,
+        }
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.check_deadline();
+        self.coord.is_stopped()
+    }
+
+    /// Record that worker has returned an error for element with position
+    /// `i`, elements after it will not be processed.
+    fn failed_at(&self, i: usize) {
+        self.error_pos.fetch_min(i, Ordering::AcqRel);
+    }
+
+    /// Check if element with position `i` follows an element for which
+    /// worker has returned an error.
+    fn is_after_error(&self, i: usize) -> bool {
+        i > self.error_pos.load(Ordering::Acquire)
+    }
+
+    /// Block until the next element can be sent without exceeding rate
+    /// limit, `next` is the earliest time of sending the next element.
+    /// Returns `false` if run was stopped while waiting.
+    fn throttle(&self, next: &mut Option<Instant>) -> bool {
+        const RECHECK: Duration = Duration::from_millis(10);
+        let interval = match self.rate_interval {
+            Some(interval) => interval,
+            None => return true,
+        };
+        let now = Instant::now();
+        let at = match *next {
+            Some(at) if at > now => at,
+            _ => now,
+        };
+        loop {
+            let now = Instant::now();
+            if now >= at { break }
+            if self.is_stopped() { return false }
+            thread::sleep(cmp::min(at - now, RECHECK));
+        }
+        *next = Some(at + interval);
+        true
+    }
+
+    /// Stop the run if deadline has passed and run was not stopped already.
+    /// Returns `true` if deadline has passed.
+    fn check_deadline(&self) -> bool {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.coord.try_stop(Status::Deadline);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Receive next message for the report thread, returns `None` if channel
+    /// is disconnected, deadline has passed or watchdog has stopped the run.
+    fn recv<T>(&self, rx: &channel::Receiver<T>) -> Option<T> {
+        // watchdog does not notify report thread, so we re-check the status
+        const RECHECK: Duration = Duration::from_millis(10);
+        if self.deadline.is_none() && self.heartbeat.is_none() {
+            return rx.recv().ok();
+        }
+        loop {
+            if self.check_deadline() { return None }
+            if self.coord.status() == Status::Stalled { return None }
+            let timeout = match self.deadline {
+                Some(d) => d.saturating_duration_since(Instant::now()),
+                None => RECHECK,
+            };
+            let timeout = cmp::min(timeout, RECHECK);
+            match rx.recv_timeout(timeout) {
+                // result may be received right before the deadline
+                Ok(_) if self.check_deadline() => return None,
+                Ok(val) => return Some(val),
+                Err(channel::RecvTimeoutError::Timeout) => (),
+                Err(channel::RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    /// Same as `recv`, but waits for a message at most for `timeout`.
+    fn recv_timeout<T>(
+        &self, rx: &channel::Receiver<T>, timeout: Duration,
+    ) -> Recv<T> {
+        const RECHECK: Duration = Duration::from_millis(10);
+        let until = Instant::now() + timeout;
+        loop {
+            if self.check_deadline() { return Recv::Closed }
+            if self.coord.status() == Status::Stalled { return Recv::Closed }
+            let now = Instant::now();
+            if now >= until { return Recv::Timeout }
+            let mut wait = cmp::min(until - now, RECHECK);
+            if let Some(d) = self.deadline {
+                wait = cmp::min(wait, d.saturating_duration_since(now));
+            }
+            match rx.recv_timeout(wait) {
+                Ok(_) if self.check_deadline() => return Recv::Closed,
+                Ok(val) => return Recv::Msg(val),
+                Err(channel::RecvTimeoutError::Timeout) => (),
+                Err(channel::RecvTimeoutError::Disconnected) => {
+                    return Recv::Closed;
+                },
+            }
+        }
+    }
+
+    /// Stop the run with `status` and wake up feeder if it waits for
+    /// the window.
+    fn stop(&self, status: Status) {
+        self.coord.stop(status);
+        if let Some(w) = &self.window {
+            let _guard = w.lock.lock().unwrap();
+            w.cvar.notify_all();
+        }
+    }
+
+    /// Save panic payload and stop the run with `status` atomically relative
+    /// to other panicking threads, so payload will always correspond to the
+    /// final status.
+    pub(crate) fn store_panic(
+        &self, payload: Box<dyn Any + Send>, status: Status,
+    ) {
+        let mut guard = self.panic_payload.lock().unwrap();
+        *guard = Some(payload);
+        self.stop(status);
+    }
+
+    /// Add or remove `size` bytes to total size of buffered results. Feeder
+    /// is notified after the result is reported.
+    fn buffer_bytes(&self, size: usize, added: bool) {
+        if size == 0 || self.max_buffer_bytes.is_none() { return }
+        if added {
+            self.buffered_bytes.fetch_add(size, Ordering::AcqRel);
+        } else {
+            self.buffered_bytes.fetch_sub(size, Ordering::AcqRel);
+        }
+    }
+
+    /// Check if total size of buffered results exceeds the limit.
+    fn bytes_exceeded(&self) -> bool {
+        match self.max_buffer_bytes {
+            Some(max) => self.buffered_bytes.load(Ordering::Acquire) > max,
+            None => false,
+        }
+    }
+
+    /// Number of elements reported so far.
+    pub(crate) fn reported_count(&self) -> usize {
+        self.reported.load(Ordering::Acquire)
+    }
+
+    /// Check if the run was not stopped and all elements sent to workers
+    /// were reported.
+    pub(crate) fn is_complete(&self) -> bool {
+        let reported = self.reported.load(Ordering::Acquire);
+        self.coord.count() == Some(reported)
+    }
+
+    /// Notify feeder and progress closure that one more element was
+    /// reported.
+    pub(crate) fn reported(&self) {
+        let n = self.reported.fetch_add(1, Ordering::AcqRel) + 1;
+        if let Some(w) = &self.window {
+            let _guard = w.lock.lock().unwrap();
+            w.cvar.notify_one();
+        }
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(n);
+        }
+    }
+
+    /// Block while worker `w` is parked or until the run is stopped.
+    fn wait_active(&self, w: usize) {
+        const RECHECK: Duration = Duration::from_millis(10);
+        if let Some(a) = &self.adaptive {
+            let mut active = a.active.lock().unwrap();
+            while w >= *active && !self.is_stopped() {
+                active = a.cvar.wait_timeout(active, RECHECK).unwrap().0;
+            }
+        }
+    }
+
+    /// Change number of active workers by one, changes are made at most
+    /// once per `INTERVAL`, so a short burst will not activate all workers.
+    fn scale(&self, up: bool) {
+        const INTERVAL: Duration = Duration::from_millis(10);
+        let a = match &self.adaptive {
+            Some(a) => a,
+            None => return,
+        };
+        let mut last_change = a.last_change.lock().unwrap();
+        if last_change.elapsed() < INTERVAL { return }
+        let mut active = a.active.lock().unwrap();
+        if up && *active < a.max {
+            *active += 1;
+            a.cvar.notify_all();
+        } else if !up && *active > a.min {
+            *active -= 1;
+        } else {
+            return;
+        }
+        trace_event!(active = *active, "scaled");
+        *last_change = Instant::now();
+    }
+
+    /// Unpark all workers, so they will process remaining elements and
+    /// exit after the input channel is disconnected.
+    fn activate_all(&self) {
+        if let Some(a) = &self.adaptive {
+            *a.active.lock().unwrap() = a.max;
+            a.cvar.notify_all();
+        }
+    }
+
+    /// Block while the run is paused or until it's stopped.
+    fn wait_resumed(&self) {
+        if let Some(pause) = &self.pause {
+            pause.wait(|| self.is_stopped());
+        }
+    }
+
+    /// Block until element with position `i` fits into the window and
+    /// buffered results do not exceed the size limit, or until computation
+    /// is stopped. Results missing in the buffer were already sent to
+    /// workers, so the buffer will be drained after they are computed.
+    fn wait_window(&self, i: usize) {
+        // run can be cancelled from outside without notification,
+        // so we periodically re-check the status
+        const RECHECK: Duration = Duration::from_millis(10);
+        if let Some(w) = &self.window {
+            let mut guard = w.lock.lock().unwrap();
+            loop {
+                let reported = self.reported.load(Ordering::Acquire);
+                let fits = i < reported.saturating_add(w.size)
+                    && !self.bytes_exceeded();
+                if fits || self.is_stopped() { break }
+                guard = w.cvar.wait_timeout(guard, RECHECK).unwrap().0;
+            }
+        }
+    }
+}
+
+/// Send `val` to workers, returns `false` if run was stopped before `val`
+/// was sent.
+fn feed<T>(tx: &channel::Sender<T>, mut val: T, shared: &Shared) -> bool {
+    // workers keep consuming messages after run was stopped, but tasks
+    // passed to a spawn hook may never run, so we periodically check status
+    const RECHECK: Duration = Duration::from_millis(10);
+    // idle workers mean that feeder does not keep up with them, while
+    // a full channel means that active workers do not keep up with feeder
+    if shared.adaptive.is_some() && tx.is_empty() { shared.scale(false) }
+    loop {
+        match tx.send_timeout(val, RECHECK) {
+            Ok(()) => return true,
+            Err(channel::SendTimeoutError::Timeout(v)) => {
+                if shared.is_stopped() { return false }
+                shared.scale(true);
+                val = v;
+            },
+            Err(channel::SendTimeoutError::Disconnected(_)) => return false,
+        }
+    }
+}
+
+/// Run `f` for every element of `xs` in the workers pool, results are
+/// processed by `report` in a separate thread.
+pub(crate) fn run<X: Send, Y: Send, E: Send>(
+    config: &Config,
+    xs: impl IntoIterator<Item=X>,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnOnce(ReportRx<Y, E>, &Shared) -> Result<(), E> + Send,
+) -> Result<usize, RunError<E>> {
+    run_with_init(config, xs, |_| (), |_, x| f(x), report)
+}
+
+/// Same as `run`, but every worker creates its state using `init` with
+/// worker index before processing elements.
+pub(crate) fn run_with_init<S, X: Send, Y: Send, E: Send>(
+    config: &Config,
+    xs: impl IntoIterator<Item=X>,
+    init: impl Fn(usize) -> S + Sync,
+    f: impl Fn(&mut S, X) -> Result<Y, E> + Sync,
+    report: impl FnOnce(ReportRx<Y, E>, &Shared) -> Result<(), E> + Send,
+) -> Result<usize, RunError<E>> {
+    let mut xs = xs.into_iter();
+    let len_hint = capacity_hint(xs.size_hint());
+    run_source(config, len_hint, move |_| xs.next(), init, f, report)
+}
+
+/// Receive elements from `rx` until it is disconnected or run is stopped.
+#[cfg(feature = "crossbeam")]
+pub(crate) fn recv_source<X>(
+    rx: channel::Receiver<X>,
+) -> impl FnMut(&Shared) -> Option<X> {
+    const RECHECK: Duration = Duration::from_millis(10);
+    move |shared| loop {
+        if shared.is_stopped() { return None }
+        match rx.recv_timeout(RECHECK) {
+            Ok(x) => return Some(x),
+            Err(channel::RecvTimeoutError::Timeout) => (),
+            Err(channel::RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+}
+
+/// Pin current thread to a CPU core selected by worker index.
+#[cfg(feature = "affinity")]
+fn pin_worker(w: usize) {
+    if let Some(cores) = core_affinity::get_core_ids() {
+        if !cores.is_empty() {
+            core_affinity::set_for_current(cores[w % cores.len()]);
+        }
+    }
+}
+
+#[cfg(not(feature = "affinity"))]
+fn pin_worker(_: usize) {}
+
+/// Spawn workers which compute results for elements received from `rx`
+/// and send them to `tx2`.
+fn spawn_workers<'env, S, X, Y, E>(
+    spawner: &Spawner<'_, 'env>,
+    config: &Config,
+    rx: &channel::Receiver<(usize, X)>,
+    tx2: &channel::Sender<ReportMsg<Y, E>>,
+    init: &'env (impl Fn(usize) -> S + Sync),
+    f: &'env (impl Fn(&mut S, X) -> Result<Y, E> + Sync),
+) where X: Send + 'env, Y: Send + 'env, E: Send + 'env {
+    let shared = spawner.shared;
+    let pin = config.pin_workers;
+    for w in 0..config.get_threads() {
+        let rxc = rx.clone();
+        let txc = tx2.clone();
+        let name = config.thread_name_for(|p| format!("{}-worker-{}", p, w));
+        spawner.spawn(name, move || {
+            trace_span!("parstream::worker", worker = w);
+            if pin { pin_worker(w) }
+            let state = panic::catch_unwind(
+                panic::AssertUnwindSafe(|| init(w))
+            );
+            let mut state = match state {
+                Ok(state) => Some(state),
+                Err(payload) => {
+                    shared.store_panic(payload, Status::WorkerPanic);
+                    None
+                },
+            };
+            // workers consume messages until channel is disconnected even
+            // after an error or panic, so feeder will not block on
+            // a full channel
+            let next = || {
+                shared.wait_active(w);
+                rxc.recv().ok()
+            };
+            for (i, x) in iter::from_fn(next) {
+                if shared.is_stopped() || shared.is_after_error(i) { continue }
+                let state = match &mut state {
+                    Some(state) => state,
+                    None => continue,
+                };
+
+                // tasks are timed only if statistics are collected
+                let start = shared.stats.as_ref().map(|_| Instant::now());
+                let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                    trace_span!("parstream::map", index = i);
+                    let _active = shared.active.as_ref().map(|a| a.enter());
+                    f(state, x)
+                }));
+                if let (Some(stats), Some(start)) = (&shared.stats, start) {
+                    stats.processed(w, start.elapsed());
+                }
+                match res {
+                    Ok(res) => {
+                        if res.is_err() { shared.failed_at(i) }
+                        let _ = txc.send(ReportMsg::NewResult((i, res)));
+                    },
+                    Err(payload) => {
+                        shared.store_panic(payload, Status::WorkerPanic);
+                    },
+                }
+            }
+        });
+    }
+}
+
+/// Run `report` and stop the run if it returns an error or panics.
+fn report_task<E>(
+    report: impl FnOnce() -> Result<(), E>,
+    err_slot: &mut Option<E>,
+    shared: &Shared,
+) {
+    let r = panic::catch_unwind(panic::AssertUnwindSafe(report));
+    match r {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => {
+            *err_slot = Some(err);
+            shared.stop(Status::Error);
+        },
+        Err(payload) => {
+            shared.store_panic(payload, Status::ReportPanic);
+        },
+    }
+    shared.report_done.store(true, Ordering::Release);
+}
+
+/// Stop the run with `Status::Stalled` if number of reported elements has not
+/// changed for `timeout`, while some elements sent to workers are still
+/// pending. Returns after the report closure has returned or the run was
+/// stopped.
+fn watchdog(shared: &Shared, timeout: Duration) {
+    trace_span!("parstream::watchdog");
+    const RECHECK: Duration = Duration::from_millis(10);
+    let mut last = shared.reported.load(Ordering::Acquire);
+    let mut since = Instant::now();
+    loop {
+        if shared.report_done.load(Ordering::Acquire) || shared.is_stopped() {
+            return;
+        }
+        thread::sleep(cmp::min(timeout, RECHECK));
+        let reported = shared.reported.load(Ordering::Acquire);
+        let fed = shared.fed.load(Ordering::Acquire);
+        if reported != last || fed <= reported {
+            last = reported;
+            since = Instant::now();
+        } else if since.elapsed() >= timeout {
+            trace_event!(reported, "stalled");
+            shared.coord.try_stop(Status::Stalled);
+            return;
+        }
+    }
+}
+
+/// Spawn watchdog thread if heartbeat is enabled. Watchdog is always spawned
+/// on the scope, since tasks passed to a spawn hook may never run.
+fn spawn_watchdog<'s, 'env>(
+    scope: sync::Scope<'s, 'env>,
+    config: &Config,
+    shared: &'env Shared,
+) {
+    if let Some(timeout) = config.heartbeat {
+        let name = config.thread_name_for(|p| format!("{}-watchdog", p));
+        scope.spawn(name, None, move || watchdog(shared, timeout))
+            .expect("failed to spawn thread");
+    }
+}
+
+/// Stop the run with `Status::SourcePanic` if `next` panics, so the panic
+/// is propagated after threads cleanup like panics of callbacks.
+fn catch_source<X>(
+    mut next: impl FnMut(&Shared) -> Option<X>,
+) -> impl FnMut(&Shared) -> Option<X> {
+    move |shared| {
+        let r = panic::catch_unwind(panic::AssertUnwindSafe(|| next(shared)));
+        r.unwrap_or_else(|payload| {
+            shared.store_panic(payload, Status::SourcePanic);
+            None
+        })
+    }
+}
+
+/// Take up to `prefetch` elements from `next` limited by capacity of
+/// the input channel and return source which yields them before
+/// the remaining elements.
+fn prefetch<X>(
+    config: &Config,
+    mut next: impl FnMut(&Shared) -> Option<X>,
+    shared: &Shared,
+) -> impl FnMut(&Shared) -> Option<X> {
+    let n = cmp::min(config.prefetch, config.get_input_capacity());
+    let mut buf = VecDeque::with_capacity(n);
+    let mut exhausted = false;
+    while buf.len() < n && !shared.is_stopped() {
+        match next(shared) {
+            Some(x) => buf.push_back(x),
+            None => {
+                exhausted = true;
+                break;
+            },
+        }
+    }
+    if n != 0 {
+        trace_event!(prefetched = buf.len(), "prefetched");
+    }
+    move |shared| match buf.pop_front() {
+        Some(x) => Some(x),
+        // source may be not fused
+        None if exhausted => None,
+        None => next(shared),
+    }
+}
+
+/// Create channel for sending elements to workers.
+fn input_channel<T>(
+    config: &Config,
+) -> (channel::Sender<T>, channel::Receiver<T>) {
+    if config.unbounded_input {
+        channel::unbounded()
+    } else {
+        channel::bounded(config.get_input_capacity())
+    }
+}
+
+/// Create channel for sending results to the report thread.
+fn report_channel<T>(
+    config: &Config,
+) -> (channel::Sender<T>, channel::Receiver<T>) {
+    match config.report_pressure {
+        ReportPressure::Spill => channel::unbounded(),
+        _ => channel::bounded(config.get_report_capacity()),
+    }
+}
+
+/// Send elements produced by `next` to workers and publish their number.
+fn feed_all<X, T>(
+    mut next: impl FnMut(&Shared) -> Option<X>,
+    tx: channel::Sender<(usize, X)>,
+    rx: channel::Receiver<(usize, X)>,
+    tx2: channel::Sender<ReportMsg<T, impl Sized>>,
+    shared: &Shared,
+) {
+    trace_span!("parstream::feed");
+    let mut n = 0;
+    let mut next_send = None;
+    loop {
+        shared.wait_window(n);
+        if shared.is_stopped() || shared.is_after_error(n) { break }
+        let x = match next(shared) {
+            Some(x) => x,
+            None => break,
+        };
+        if !shared.throttle(&mut next_send) { break }
+        shared.wait_resumed();
+        if !feed(&tx, (n, x), shared) { break }
+        trace_event!(index = n, queued = tx.len(), "fed");
+        n += 1;
+        shared.fed.store(n, Ordering::Release);
+    }
+
+    shared.activate_all();
+    if !shared.coord.publish(n) {
+        // clear all messages in the channel if there is an error or panic
+        while rx.try_recv().is_ok() {}
+    }
+    // wake up report thread, so it will notice the published count,
+    // it may have already exited, so we ignore send error
+    let _ = tx2.send(ReportMsg::None);
+    // report channel will be disconnected after all workers will exit,
+    // so report thread will not wait for results which will never come
+    drop(tx2);
+    // workers will exit after processing remaining elements
+    drop(tx);
+}
+
+/// Same as `run_with_init`, but elements are produced by `next` until it
+/// returns `None`. Feeder checks whether run was stopped before every call
+/// of `next`. `len_hint` is the expected number of elements.
+pub(crate) fn run_source<S, X: Send, Y: Send, E: Send>(
+    config: &Config,
+    len_hint: usize,
+    next: impl FnMut(&Shared) -> Option<X>,
+    init: impl Fn(usize) -> S + Sync,
+    f: impl Fn(&mut S, X) -> Result<Y, E> + Sync,
+    report: impl FnOnce(ReportRx<Y, E>, &Shared) -> Result<(), E> + Send,
+) -> Result<usize, RunError<E>> {
+    let (tx, rx) = input_channel(config);
+    let (tx2, rx2) = report_channel(config);
+    let shared = &Shared::new(config, len_hint);
+    let next = prefetch(config, catch_source(next), shared);
+    let mut error = None;
+    let latch = &Latch::new();
+    let latch_guard = LatchGuard(latch);
+
+    sync::scope(|scope| {
+        let spawner = Spawner {
+            scope,
+            hook: config.spawn_hook.as_ref(),
+            stack_size: config.stack_size,
+            latch,
+            shared,
+        };
+        spawn_workers(&spawner, config, &rx, &tx2, &init, &f);
+        spawn_watchdog(scope, config, shared);
+
+        let err_slot = &mut error;
+        let name = config.thread_name_for(|p| format!("{}-report", p));
+        spawner.spawn(name, move || {
+            report_task(|| report(rx2, shared), err_slot, shared);
+        });
+
+        feed_all(next, tx, rx, tx2, shared);
+    });
+    drop(latch_guard);
+
+    finish(shared, error)
+}
+
+/// Same as `run_with_init`, but results are reported on the calling thread,
+/// while elements are sent to workers by a separate feeder thread.
+pub(crate) fn run_inline_report<S, X: Send, Y: Send, E: Send>(
+    config: &Config,
+    xs: impl IntoIterator<Item=X, IntoIter=impl Iterator<Item=X> + Send>,
+    init: impl Fn(usize) -> S + Sync,
+    f: impl Fn(&mut S, X) -> Result<Y, E> + Sync,
+    report: impl FnOnce(ReportRx<Y, E>, &Shared) -> Result<(), E>,
+) -> Result<usize, RunError<E>> {
+    let (tx, rx) = input_channel(config);
+    let (tx2, rx2) = report_channel(config);
+    let mut xs = xs.into_iter();
+    let shared = &Shared::new(config, capacity_hint(xs.size_hint()));
+    let next = prefetch(config, catch_source(move |_| xs.next()), shared);
+    let mut error = None;
+    let latch = &Latch::new();
+    let latch_guard = LatchGuard(latch);
+
+    sync::scope(|scope| {
+        let spawner = Spawner {
+            scope,
+            hook: config.spawn_hook.as_ref(),
+            stack_size: config.stack_size,
+            latch,
+            shared,
+        };
+        spawn_workers(&spawner, config, &rx, &tx2, &init, &f);
+        spawn_watchdog(scope, config, shared);
+
+        let name = config.thread_name_for(|p| format!("{}-feeder", p));
+        spawner.spawn(name, move || {
+            feed_all(next, tx, rx, tx2, shared);
+        });
+
+        report_task(|| report(rx2, shared), &mut error, shared);
+    });
+    drop(latch_guard);
+
+    finish(shared, error)
+}
+
+/// Convert final state of the run into its result.
+fn finish<E>(shared: &Shared, error: Option<E>) -> Result<usize, RunError<E>> {
+    let status = shared.coord.status();
+    match status {
+        Status::Running => unreachable!("number of elements is not published"),
+        Status::Done => Ok(shared.coord.count().unwrap()),
+        Status::Error => Err(RunError::Callback(error.unwrap())),
+        Status::WorkerPanic | Status::ReportPanic | Status::SourcePanic => {
+            let payload = shared.panic_payload.lock().unwrap().take()
+                .expect("panic payload is missing");
+            match status {
+                Status::WorkerPanic => Err(RunError::WorkerPanic(payload)),
+                Status::ReportPanic => Err(RunError::ReportPanic(payload)),
+                _ => Err(RunError::SourcePanic(payload)),
+            }
+        },
+        Status::Cancelled => {
+            Err(RunError::Cancelled(shared.reported.load(Ordering::Acquire)))
+        },
+        Status::Deadline => Ok(shared.reported.load(Ordering::Acquire)),
+        Status::Stalled => {
+            Err(RunError::Stalled(shared.reported.load(Ordering::Acquire)))
+        },
+    }
+}
@@ -0,0 +1,286 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::panic;
+
+/// Error returned by [`run_checked`](fn.run_checked.html).
+///
+/// `ER` is used only by [`run_split_errors`](fn.run_split_errors.html).
+pub enum RunError<E, ER = E> {
+    /// One of callbacks has returned an error.
+    ///
+    /// For [`run_split_errors`](fn.run_split_errors.html) only errors of
+    /// worker closure are returned using this variant.
+    Callback(E),
+    /// Report closure has returned an error, used only by
+    /// [`run_split_errors`](fn.run_split_errors.html).
+    Report(ER),
+    /// Worker closure has panicked, contains panic payload.
+    WorkerPanic(Box<dyn Any + Send>),
+    /// Report closure has panicked, contains panic payload.
+    ReportPanic(Box<dyn Any + Send>),
+    /// Input iterator has panicked, contains panic payload.
+    SourcePanic(Box<dyn Any + Send>),
+    /// Run was cancelled using [`CancelToken`](struct.CancelToken.html),
+    /// contains number of elements reported before cancellation.
+    Cancelled(usize),
+    /// Computation of element has exceeded timeout set by
+    /// [`run_with_timeout`](fn.run_with_timeout.html), contains position
+    /// of the element.
+    TimedOut(usize),
+    /// No element was reported during the timeout set by
+    /// [`Config::heartbeat`](struct.Config.html#method.heartbeat), contains
+    /// number of elements reported before the run was stopped.
+    Stalled(usize),
+    /// Threads of the run have not exited during the timeout set by
+    /// [`run_with_join_timeout`](fn.run_with_join_timeout.html) after
+    /// the report closure has finished, contains number of reported elements.
+    WorkersStuck(usize),
+}
+
+/// Error returned by [`run_with_count`](fn.run_with_count.html).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RunFailure<E> {
+    /// Error returned by one of callbacks.
+    pub error: E,
+    /// Number of elements which were successfully reported before the error.
+    pub completed: usize,
+}
+
+impl<E: fmt::Display> fmt::Display for RunFailure<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (after {} reported elements)", self.error, self.completed)
+    }
+}
+
+impl<E: Error + 'static> Error for RunFailure<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Error returned by
+/// [`run_with_circuit_breaker`](fn.run_with_circuit_breaker.html).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CircuitError<E> {
+    /// Report closure has returned an error.
+    Report(E),
+    /// Threshold of consecutive failed elements was reached, contains their
+    /// errors together with positions of elements.
+    Open(Vec<(usize, E)>),
+}
+
+impl<E: fmt::Display> fmt::Display for CircuitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CircuitError::Report(err) => err.fmt(f),
+            CircuitError::Open(errors) => {
+                write!(f, "circuit is open after {} consecutive errors",
+                    errors.len())?;
+                match errors.last() {
+                    Some((_, err)) => write!(f, ", last error: {}", err),
+                    None => Ok(()),
+                }
+            },
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CircuitError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CircuitError::Report(err) => Some(err),
+            CircuitError::Open(errors) => match errors.last() {
+                Some((_, err)) => Some(err),
+                None => None,
+            },
+        }
+    }
+}
+
+/// Handling of worker errors used by
+/// [`run_with_error_policy`](fn.run_with_error_policy.html).
+///
+/// Errors returned by the report closure always stop the run.
+pub enum ErrorPolicy<'a, E> {
+    /// Stop the run on the first error, the returned vector contains only
+    /// this error.
+    FailFast,
+    /// Continue the run and return all errors with positions of elements
+    /// after the run has finished.
+    CollectAll,
+    /// Continue the run and pass errors with positions of elements to
+    /// the hook in order of elements, errors are not returned.
+    Continue(Box<dyn FnMut(usize, E) + Send + 'a>),
+}
+
+// `#[default]` attribute requires Rust 1.62
+#[allow(clippy::derivable_impls)]
+impl<'a, E> Default for ErrorPolicy<'a, E> {
+    fn default() -> Self {
+        ErrorPolicy::FailFast
+    }
+}
+
+impl<'a, E> fmt::Debug for ErrorPolicy<'a, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorPolicy::FailFast => f.write_str("FailFast"),
+            ErrorPolicy::CollectAll => f.write_str("CollectAll"),
+            ErrorPolicy::Continue(_) => f.write_str("Continue(..)"),
+        }
+    }
+}
+
+/// Error returned by [`run_resumable`](fn.run_resumable.html).
+///
+/// Elements with positions in `reported..dispatched` were taken from
+/// the iterator, but their results were not reported, so they have to be
+/// processed again together with `rest`.
+pub struct Interrupted<E, I> {
+    /// Reason of the early exit, `None` if the run has reached deadline set
+    /// by [`Config::deadline`](struct.Config.html#method.deadline).
+    pub error: Option<RunError<E>>,
+    /// Number of elements which were successfully reported.
+    pub reported: usize,
+    /// Number of elements which were taken from the iterator.
+    pub dispatched: usize,
+    /// Iterator over elements which were not taken.
+    pub rest: I,
+}
+
+impl<E: fmt::Debug, I> fmt::Debug for Interrupted<E, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Interrupted")
+            .field("error", &self.error)
+            .field("reported", &self.reported)
+            .field("dispatched", &self.dispatched)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: fmt::Display, I> fmt::Display for Interrupted<E, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.error {
+            Some(err) => write!(f, "{}", err)?,
+            None => f.write_str("run has reached deadline")?,
+        }
+        write!(f, " (after {} reported elements)", self.reported)
+    }
+}
+
+impl<E: Error + 'static, I> Error for Interrupted<E, I> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.error {
+            Some(err) => Some(err),
+            None => None,
+        }
+    }
+}
+
+/// Convert result of a checked run into result of unchecked one: panics are
+/// resumed and cancellation is treated as a successful run.
+pub(crate) fn unchecked<E>(res: Result<usize, RunError<E>>) -> Result<usize, E> {
+    match res {
+        Ok(n) | Err(RunError::Cancelled(n)) => Ok(n),
+        Err(RunError::Callback(err)) | Err(RunError::Report(err)) => Err(err),
+        Err(RunError::WorkerPanic(payload))
+        | Err(RunError::ReportPanic(payload))
+        | Err(RunError::SourcePanic(payload)) => panic::resume_unwind(payload),
+        Err(RunError::TimedOut(_)) => {
+            unreachable!("unchecked runs do not use timeouts")
+        },
+        Err(RunError::Stalled(n)) => {
+            panic!("run has stalled after reporting {} elements", n)
+        },
+        Err(RunError::WorkersStuck(_)) => {
+            unreachable!("unchecked runs do not use join timeouts")
+        },
+    }
+}
+
+fn payload_msg(payload: &(dyn Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+impl<E: fmt::Debug, ER: fmt::Debug> fmt::Debug for RunError<E, ER> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Callback(err) => {
+                f.debug_tuple("Callback").field(err).finish()
+            },
+            RunError::Report(err) => {
+                f.debug_tuple("Report").field(err).finish()
+            },
+            RunError::WorkerPanic(p) => {
+                f.debug_tuple("WorkerPanic").field(&payload_msg(&**p)).finish()
+            },
+            RunError::ReportPanic(p) => {
+                f.debug_tuple("ReportPanic").field(&payload_msg(&**p)).finish()
+            },
+            RunError::SourcePanic(p) => {
+                f.debug_tuple("SourcePanic").field(&payload_msg(&**p)).finish()
+            },
+            RunError::Cancelled(n) => {
+                f.debug_tuple("Cancelled").field(n).finish()
+            },
+            RunError::TimedOut(i) => {
+                f.debug_tuple("TimedOut").field(i).finish()
+            },
+            RunError::Stalled(n) => {
+                f.debug_tuple("Stalled").field(n).finish()
+            },
+            RunError::WorkersStuck(n) => {
+                f.debug_tuple("WorkersStuck").field(n).finish()
+            },
+        }
+    }
+}
+
+impl<E: fmt::Display, ER: fmt::Display> fmt::Display for RunError<E, ER> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Callback(err) => err.fmt(f),
+            RunError::Report(err) => err.fmt(f),
+            RunError::WorkerPanic(p) => {
+                write!(f, "worker thread has panicked: {}", payload_msg(&**p))
+            },
+            RunError::ReportPanic(p) => {
+                write!(f, "report thread has panicked: {}", payload_msg(&**p))
+            },
+            RunError::SourcePanic(p) => {
+                write!(f, "input iterator has panicked: {}", payload_msg(&**p))
+            },
+            RunError::Cancelled(n) => {
+                write!(f, "run was cancelled after reporting {} elements", n)
+            },
+            RunError::TimedOut(i) => {
+                write!(f, "computation of element {} has timed out", i)
+            },
+            RunError::Stalled(n) => {
+                write!(f, "run has stalled after reporting {} elements", n)
+            },
+            RunError::WorkersStuck(n) => {
+                write!(f, "workers are stuck after reporting {} elements", n)
+            },
+        }
+    }
+}
+
+impl<E, ER> Error for RunError<E, ER>
+    where E: Error + 'static, ER: Error + 'static
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RunError::Callback(err) => Some(err),
+            RunError::Report(err) => Some(err),
+            _ => None,
+        }
+    }
+}
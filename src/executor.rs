@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+/// Executor of worker and report thread bodies, set using
+/// [`Config::executor`](struct.Config.html#method.executor).
+///
+/// By default runs spawn scoped threads, executor allows to run thread
+/// bodies on threads managed by other code, e.g. an existing thread pool.
+///
+/// # Examples
+/// ```
+/// use parstream::Executor;
+///
+/// struct Detached;
+///
+/// impl Executor for Detached {
+///     fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+///         std::thread::spawn(task);
+///     }
+/// }
+///
+/// let res: Result<usize, ()> = parstream::Config::new()
+///     .threads(4)
+///     .executor(Detached)
+///     .run(0..100u64, |x| Ok(x*x), |_| Ok(()));
+/// assert_eq!(res, Ok(100));
+/// ```
+pub trait Executor: Send + Sync {
+    /// Run `task` on a separate thread.
+    ///
+    /// The run will not return until all tasks are finished or dropped, so
+    /// running them on the calling thread or delaying them indefinitely
+    /// will result in a deadlock. Dropping task without running it stops
+    /// the run with a worker panic.
+    fn execute(&self, task: Box<dyn FnOnce() + Send>);
+}
+
+impl<T: Executor + ?Sized> Executor for Arc<T> {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        (**self).execute(task)
+    }
+}
+
+impl<T: Executor + ?Sized> Executor for Box<T> {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        (**self).execute(task)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Executor for rayon::ThreadPool {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        self.spawn(task)
+    }
+}
+
+/// Executor built from a closure passed to
+/// [`Config::spawn_hook`](struct.Config.html#method.spawn_hook).
+pub(crate) struct FnExecutor<F>(pub(crate) F);
+
+impl<F> Executor for FnExecutor<F>
+    where F: Fn(Box<dyn FnOnce() + Send>) + Send + Sync
+{
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        (self.0)(task)
+    }
+}
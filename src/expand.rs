@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use crate::engine::Shared;
+
+struct State<X> {
+    queue: VecDeque<X>,
+    // number of taken elements which were not completed yet
+    pending: usize,
+}
+
+/// Queue of elements used by
+/// [`run_expanding`](fn.run_expanding.html), elements generated while
+/// processing are appended to it.
+pub(crate) struct WorkQueue<X> {
+    state: Mutex<State<X>>,
+    cvar: Condvar,
+}
+
+impl<X> WorkQueue<X> {
+    pub(crate) fn new(seeds: impl IntoIterator<Item=X>) -> Self {
+        let state = State { queue: seeds.into_iter().collect(), pending: 0 };
+        Self { state: Mutex::new(state), cvar: Condvar::new() }
+    }
+
+    /// Number of queued elements.
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().queue.len()
+    }
+
+    /// Take the next element. Blocks while queue is empty, but some taken
+    /// elements are not completed, since they may generate new elements.
+    /// Returns `None` if run was stopped or no elements are left.
+    pub(crate) fn pop(&self, shared: &Shared) -> Option<X> {
+        // run can be stopped without notification of the condition variable,
+        // so we periodically re-check it
+        const RECHECK: Duration = Duration::from_millis(10);
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if shared.is_stopped() { return None }
+            if let Some(x) = state.queue.pop_front() {
+                state.pending += 1;
+                return Some(x);
+            }
+            if state.pending == 0 { return None }
+            state = self.cvar.wait_timeout(state, RECHECK).unwrap().0;
+        }
+    }
+
+    /// Complete a taken element and append elements generated by it.
+    pub(crate) fn complete(&self, xs: Vec<X>) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.extend(xs);
+        state.pending -= 1;
+        self.cvar.notify_one();
+    }
+}
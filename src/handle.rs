@@ -0,0 +1,65 @@
+use std::thread;
+
+use crate::{CancelHandle, CancelToken, Config, RunError};
+use crate::error::unchecked;
+
+/// Handle of a run executed on a background thread.
+///
+/// This struct is created by the [`spawn_run`](fn.spawn_run.html) function.
+/// Dropping the handle detaches the run, it will continue until completion.
+#[derive(Debug)]
+pub struct RunHandle<E> {
+    thread: thread::JoinHandle<Result<usize, RunError<E>>>,
+    cancel: CancelHandle,
+}
+
+impl<E: Send + 'static> RunHandle<E> {
+    pub(crate) fn new<X, Y>(
+        config: &Config,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+        report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+    ) -> Self
+        where X: Send + 'static, Y: Send + 'static
+    {
+        Self::spawn(config, move |config| config.run_checked(xs, f, report))
+    }
+
+    /// Execute `run` with a copy of `config` on a background thread.
+    pub(crate) fn spawn(
+        config: &Config,
+        run: impl FnOnce(Config) -> Result<usize, RunError<E>> + Send + 'static,
+    ) -> Self {
+        // reuse configured token, so its handle will cancel this run as well
+        let token = match &config.cancel_token {
+            Some(token) => token.clone(),
+            None => CancelToken::new().0,
+        };
+        let cancel = token.handle();
+        let config = config.clone().cancel_token(token);
+        let thread = thread::spawn(move || run(config));
+        Self { thread, cancel }
+    }
+}
+
+impl<E> RunHandle<E> {
+    /// Wait for the run to finish and return its result.
+    ///
+    /// Result of a cancelled run is number of elements reported before
+    /// cancellation.
+    ///
+    /// # Panics
+    /// If `f` or `report` has panicked, the panic is propagated to caller.
+    pub fn join(self) -> Result<usize, E> {
+        match self.thread.join() {
+            Ok(res) => unchecked(res),
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+
+    /// Cancel the run, no new tasks will be started and report closure will
+    /// not be called anymore.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
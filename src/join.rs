@@ -0,0 +1,68 @@
+use std::panic;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::engine::{self, Shared};
+use crate::{Config, RunError};
+
+enum Msg<E> {
+    // report closure has finished, contains number of reported elements
+    Reported(usize),
+    Finished(Result<usize, RunError<E>>),
+}
+
+/// Notifies the caller when dropped, i.e. after the report closure has
+/// returned or panicked.
+struct ReportGuard<'a, E> {
+    tx: mpsc::Sender<Msg<E>>,
+    shared: &'a Shared,
+}
+
+impl<E> Drop for ReportGuard<'_, E> {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Msg::Reported(self.shared.reported_count()));
+    }
+}
+
+pub(crate) fn run<X, Y, E>(
+    config: &Config,
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    join_timeout: Duration,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+) -> Result<usize, RunError<E>>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    let (tx, rx) = mpsc::channel();
+    let report_tx = tx.clone();
+    let config = config.clone();
+    // the run is executed on a separate thread, so it can be abandoned
+    // if its threads do not exit
+    let thread = thread::spawn(move || {
+        let res = engine::run(&config, xs, f, move |rx, shared| {
+            let _guard = ReportGuard { tx: report_tx, shared };
+            engine::run_report(rx, report, shared)
+        });
+        let _ = tx.send(Msg::Finished(res));
+    });
+    let n = match rx.recv() {
+        Ok(Msg::Reported(n)) => n,
+        Ok(Msg::Finished(res)) => return res,
+        Err(_) => resume(thread),
+    };
+    match rx.recv_timeout(join_timeout) {
+        Ok(Msg::Finished(res)) => res,
+        Ok(Msg::Reported(_)) => unreachable!(),
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(RunError::WorkersStuck(n)),
+        Err(mpsc::RecvTimeoutError::Disconnected) => resume(thread),
+    }
+}
+
+/// Propagate panic of the run thread, which has exited without a result.
+fn resume(thread: thread::JoinHandle<()>) -> ! {
+    match thread.join() {
+        Ok(()) => unreachable!("run thread has exited without a result"),
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
@@ -26,7 +26,9 @@
 //!
 //! If one of callbacks will return error, no new tasks will be started and
 //! `run` will end as soon as possible (after threads cleanup) to report this
-//! error to caller.
+//! error to caller. Results of elements preceding the failed one are still
+//! reported, so if several elements fail, error of the element with the
+//! lowest position is returned.
 //! ```
 //! #[derive(Eq, PartialEq, Debug)]
 //! struct MyError(usize);
@@ -47,170 +49,2271 @@
 //! );
 //!
 //! assert_eq!(res, Err(MyError(5)));
-//! assert_eq!(ys.len(), 0);
+//! assert_eq!(ys, vec![100*100, 4*4, 3*3, 2*2, 1*1]);
 //! ```
 //!
-//! # Warnings
-//! The first closure in `run` should not panic as it will lead to a deadlock!
-//! Also report thread will not recover after the second closure panic, it will
-//! not result in deadlock, but the second closure will not be called anymore.
-use std::collections::BinaryHeap;
-use std::collections::binary_heap::PeekMut;
-use std::cmp;
-use std::sync::atomic::AtomicIsize;
-use std::sync::atomic::Ordering;
-
-use crossbeam_channel as channel;
-use crossbeam_utils::thread as cb_thread;
-
-struct State<T> {
-    pos: usize,
-    payload: T,
-}
-
-impl<T> PartialEq for State<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.pos == other.pos
+//! # Infinite iterators
+//! Elements are taken from the input iterator only when there is space in
+//! the bounded input channel, so the iterator can be infinite. Such run
+//! ends only after an error, cancellation or a break returned by report
+//! closure of [`run_control_flow`](fn.run_control_flow.html). Memory used by
+//! the run does not grow with number of processed elements, except the
+//! reorder buffer which holds results waiting for a slow element. Use
+//! [`Config::max_in_flight`](struct.Config.html#method.max_in_flight) to
+//! bound it.
+//! ```
+//! use std::ops::ControlFlow;
+//!
+//! let res: Result<usize, ()> = parstream::Config::new()
+//!     .threads(4)
+//!     .max_in_flight(64)
+//!     .run_control_flow(0u64.., |x| Ok(x*x), |y| {
+//!         Ok(if y < 10_000 { ControlFlow::Continue(()) } else { ControlFlow::Break(()) })
+//!     });
+//! assert_eq!(res, Ok(101));
+//! ```
+//!
+//! # Panics
+//! If one of callbacks or the input iterator panics, no new tasks will be
+//! started and the panic will be propagated to caller after threads
+//! cleanup.
+//!
+//! # Crate features
+//! - `async`: enables [`run_async`](fn.run_async.html) which allows to
+//!   await a run from an asynchronous code.
+//! - `futures`: enables [`par_stream`](fn.par_stream.html) which returns
+//!   results as a [`Stream`](https://docs.rs/futures-core/0.3/futures_core/stream/trait.Stream.html).
+//! - `tracing`: emits [`tracing`](https://docs.rs/tracing) spans for feeder,
+//!   workers and report thread together with events for the reorder buffer.
+//! - `affinity`: enables [`Config::pin_workers`](struct.Config.html#method.pin_workers)
+//!   which pins workers to CPU cores.
+//! - `rayon`: enables [`run_in_pool`](fn.run_in_pool.html) which runs
+//!   workers on an existing `rayon` thread pool and implements
+//!   [`Executor`](trait.Executor.html) for `rayon::ThreadPool`.
+//! - `spill`: enables [`run_spilled`](fn.run_spilled.html) which spills
+//!   the reorder buffer to a temporary file.
+//! - `signal`: enables [`CancelToken::ctrl_c`](struct.CancelToken.html#method.ctrl_c)
+//!   which cancels runs on Ctrl-C, supported only on Unix targets.
+//! - `crossbeam` (enabled by default): uses `crossbeam-channel` and
+//!   `crossbeam-utils` for channels and scoped threads and enables
+//!   [`run_from_channel`](fn.run_from_channel.html) and
+//!   [`run_in_scope`](fn.run_in_scope.html). If disabled, crate
+//!   uses only `std` (`std::thread::scope` requires Rust 1.63).
+#[macro_use]
+mod trace;
+
+mod active;
+mod cancel;
+mod config;
+mod coord;
+mod engine;
+mod error;
+mod executor;
+mod expand;
+mod handle;
+mod join;
+mod merge;
+mod par_map;
+#[cfg(feature = "futures")]
+mod par_stream;
+mod pause;
+mod pipeline;
+mod pool;
+mod pressure;
+#[cfg(all(feature = "signal", unix))]
+mod signal;
+#[cfg(feature = "spill")]
+mod spill;
+mod priority;
+#[cfg(feature = "async")]
+mod run_async;
+mod spawn;
+mod stats;
+mod sync;
+mod timeout;
+
+pub use crate::active::ActiveWorkers;
+pub use crate::cancel::{CancelHandle, CancelToken};
+pub use crate::config::Config;
+pub use crate::error::{CircuitError, ErrorPolicy, Interrupted, RunError};
+pub use crate::error::RunFailure;
+pub use crate::executor::Executor;
+pub use crate::handle::RunHandle;
+pub use crate::merge::MergeOrder;
+pub use crate::par_map::ParMap;
+pub use crate::pause::PauseHandle;
+pub use crate::pool::Pool;
+pub use crate::pressure::ReportPressure;
+pub use crate::stats::{DurationHistogram, RunStats};
+#[cfg(feature = "futures")]
+pub use crate::par_stream::ParStream;
+
+/// Boxed report closure used by [`run_tee`](fn.run_tee.html).
+pub type BoxedReport<'a, Y, E> = Box<dyn FnMut(Y) -> Result<(), E> + Send + 'a>;
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and call `report`
+/// for every result and preserve order of elements.
+///
+/// Retutns either number of elements successfully processed or first
+/// enocuntered error.
+///
+/// Number of threads in the workers pool will be equal to `threads`. If
+/// `threads` is equal to 0, it will be equal to number of available CPUs,
+/// see [`run_auto`](fn.run_auto.html).
+///
+/// All threads are joined before `run` returns, so `xs`, `f` and `report`
+/// can borrow data from the calling function, `'static` bounds and wrapping
+/// data into `Arc` are not required. `f` is shared between workers, so it
+/// must be `Sync`, while `report` is moved to the report thread, so it must
+/// be `Send`.
+///
+/// # Panics
+/// If `f` or `report` panics, the panic is propagated to caller after
+/// threads cleanup.
+///
+/// # Examples
+/// ```
+/// let words = vec!["foo".to_string(), "bar".to_string()];
+/// let mut out = Vec::new();
+/// let res: Result<usize, ()> = parstream::run(0..words.len(), 2,
+///     // borrows `words`
+///     |i| Ok(words[i].len()),
+///     // borrows `out` mutably
+///     |n| { out.push(n); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(2));
+/// assert_eq!(out, vec![3, 3]);
+/// ```
+pub fn run<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but elements are processed sequentially
+/// on the calling thread without spawning any threads.
+///
+/// Results are reported in the same order as by `run`, so swapping the two
+/// functions helps to check whether a bug is caused by concurrency. Panics
+/// of `f` and `report` are not caught, so they are raised with a backtrace
+/// of the calling thread.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_inline(0..100u64,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_inline<X, Y, E>(
+    xs: impl IntoIterator<Item=X>,
+    f: impl Fn(X) -> Result<Y, E>,
+    mut report: impl FnMut(Y) -> Result<(), E>,
+) -> Result<usize, E> {
+    let mut n = 0;
+    for x in xs {
+        report(f(x)?)?;
+        n += 1;
     }
+    Ok(n)
 }
 
-impl<T> Eq for State<T> { }
+/// Same as [`run`](fn.run.html), but number of active workers changes
+/// between `min` and `max` depending on load.
+///
+/// All `max` workers are spawned upfront, but only `min` of them process
+/// elements initially, while others are parked. If feeder can not send
+/// an element because the input channel is full, one more worker is
+/// activated, and if the channel is empty before sending an element, one
+/// worker is parked again after finishing its current element. Number of
+/// active workers changes at most once per 10 milliseconds.
+///
+/// # Panics
+/// If `min` is equal to zero or larger than `max`.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_adaptive(0..100u64, 1, 4,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_adaptive<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    min: usize,
+    max: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().run_adaptive(xs, min, max, f, report)
+}
 
-impl<T> Ord for State<T> {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        other.pos.cmp(&self.pos)
-    }
+/// Same as [`run`](fn.run.html), but also returns statistics of the run.
+///
+/// Statistics are collected only by this function, so other functions are
+/// not slowed down by updating of the counters and timing of tasks.
+/// Durations of `f` calls are collected into
+/// [`DurationHistogram`](struct.DurationHistogram.html), which allows to
+/// find tail latency hidden by average throughput.
+///
+/// # Examples
+/// ```
+/// let (res, stats) = parstream::run_with_stats(0..100u64, 4,
+///     |x| Ok::<u64, ()>(x*x),
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(stats.worker_items.len(), 4);
+/// assert_eq!(stats.worker_items.iter().sum::<usize>(), 100);
+/// assert!(stats.peak_buffered <= stats.total_buffered);
+/// assert_eq!(stats.task_durations.count(), 100);
+/// assert!(stats.task_durations.p50() <= stats.task_durations.p99());
+/// assert!(stats.task_durations.p99() <= stats.task_durations.max());
+/// ```
+pub fn run_with_stats<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> (Result<usize, E>, RunStats) {
+    Config::new().threads(threads).run_with_stats(xs, f, report)
 }
 
-impl<T> PartialOrd for State<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+/// Same as [`run`](fn.run.html), but also returns maximum number of results
+/// which were simultaneously held in the reorder buffer.
+///
+/// Shortcut for [`RunStats::peak_buffered`](struct.RunStats.html#structfield.peak_buffered)
+/// returned by [`run_with_stats`](fn.run_with_stats.html). A peak close to
+/// the input channel capacity means that results are often delayed by slow
+/// elements.
+///
+/// # Examples
+/// ```
+/// let (res, peak) = parstream::run_with_peak(0..100u64, 4,
+///     |x| Ok::<u64, ()>(x*x),
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert!(peak < 100);
+/// ```
+pub fn run_with_peak<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> (Result<usize, E>, usize) {
+    Config::new().threads(threads).run_with_peak(xs, f, report)
 }
 
-enum ReportMsg<T, E> {
-    None,
-    NewResult((usize, Result<T, E>)),
+/// Same as [`run`](fn.run.html), but elements with higher priorities are
+/// sent to workers first, while results are still reported in the order of
+/// elements in `xs`.
+///
+/// Priority is best-effort: feeder buffers a limited number of elements
+/// (equal to the input channel capacity) and sends the element with the
+/// highest priority among them, elements with equal priorities are sent in
+/// their original order. Elements which were already sent to the input
+/// channel are processed in the order of sending. If several elements fail,
+/// error of the first failed element in the order of processing is
+/// returned.
+///
+/// Limits set by
+/// [`Config::max_reorder_buffer`](struct.Config.html#method.max_reorder_buffer)
+/// and [`Config::max_in_flight`](struct.Config.html#method.max_in_flight)
+/// are not applied, since results of elements with higher priorities have
+/// to be buffered until all preceding elements are processed. Thus an
+/// element with a low priority may hold back reporting of all results until
+/// the end of the input.
+///
+/// # Examples
+/// ```
+/// let xs = (0..100u64).map(|x| (x % 3 == 0, x));
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_prioritized(xs, 4,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_prioritized<P: Ord, X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=(P, X)>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_prioritized(xs, f, report)
 }
 
-fn run_report<T, E>(
-    rx: channel::Receiver<ReportMsg<T, E>>,
-    mut f: impl FnMut(T) -> Result<(), E>,
-    flag: &AtomicIsize,
-) -> Result<(), E> {
-    let mut buf: BinaryHeap<State<T>> = BinaryHeap::new();
-    let mut n = 0;
+/// Same as [`run`](fn.run.html), but `f` is called for references to
+/// elements of the slice `xs`.
+///
+/// Only positions of elements are sent to workers, so large elements are
+/// neither moved nor cloned.
+///
+/// # Examples
+/// ```
+/// let xs: Vec<Vec<u64>> = (0..10).map(|i| vec![i; 1000]).collect();
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_ref(&xs, 4,
+///     |v| Ok(v.iter().sum::<u64>()),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(10));
+/// assert_eq!(ys, (0..10).map(|i| i * 1000).collect::<Vec<_>>());
+/// ```
+pub fn run_ref<'a, X: Sync, Y: Send, E: Send>(
+    xs: &'a [X],
+    threads: usize,
+    f: impl Fn(&'a X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_ref(xs, f, report)
+}
 
-    use self::ReportMsg::*;
-    for val in rx.iter() {
-        let target = flag.load(Ordering::Acquire);
-        if target < 0 { break }
-
-        match val {
-            NewResult((i, payload)) => {
-                let payload = payload.map_err(Into::into)?;
-                if i != n {
-                    buf.push(State { pos: i, payload: payload });
-                    continue;
-                }
-                f(payload).map_err(Into::into)?;
-
-                n += 1;
-                while let Some(pm) = buf.peek_mut() {
-                    assert!(pm.pos >= n);
-                    if pm.pos != n { break }
-                    f(PeekMut::pop(pm).payload).map_err(Into::into)?;
-                    n += 1
-                }
-            },
-            None => (),
-        }
-
-        if target as usize == n { break; }
-    }
-    Ok(())
+/// Same as [`run_checked`](fn.run_checked.html), but if the run exits
+/// early, the remaining part of the iterator is returned together with
+/// the error.
+///
+/// Run is considered complete only if all elements were taken from the
+/// iterator and reported. Otherwise [`Interrupted`](struct.Interrupted.html)
+/// is returned, which allows to retry the unfinished tail: elements which
+/// were taken from the iterator, but were not reported, are lost and only
+/// their positions are known.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res = parstream::run_resumable(0..100u64, 4,
+///     |x| if x == 10 { Err(x) } else { Ok(x) },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// let int = res.unwrap_err();
+/// assert_eq!(int.reported, 10);
+/// assert!(int.dispatched > 10);
+/// // element 10 has failed, the following in-flight elements were lost
+/// let rest: Vec<u64> = int.rest.collect();
+/// assert_eq!(rest, (int.dispatched as u64..100).collect::<Vec<_>>());
+/// ```
+pub fn run_resumable<X: Send, Y: Send, E: Send, I: Iterator<Item=X>>(
+    xs: impl IntoIterator<Item=X, IntoIter=I>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, Interrupted<E, I>> {
+    Config::new().threads(threads).run_resumable(xs, f, report)
 }
 
-const FLAG_INIT: isize = 0;
-const FLAG_ERROR: isize = -1;
-const FLAG_WORKER_PANIC: isize = -2;
-const FLAG_REPORT_PANIC: isize = -3;
+/// Same as [`run`](fn.run.html), but total size of results in the reorder
+/// buffer computed using `size_of` is limited by `max_bytes`.
+///
+/// While the limit is exceeded, no new elements are sent to workers. Results
+/// missing in the buffer belong to elements which were already sent to
+/// workers, so the buffer is drained after they are computed. The limit can
+/// be exceeded by results of elements which were sent to workers before it
+/// was reached, so memory is bounded by `max_bytes` plus size of results of
+/// in-flight elements. `size_of` is called twice for every buffered result,
+/// so it must return the same value for it.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_with_buffer_bytes(
+///     0..100usize, 4, 1 << 20,
+///     |s: &String| s.len(),
+///     |x| Ok("a".repeat(x)),
+///     |y| { ys.push(y.len()); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100).collect::<Vec<_>>());
+/// ```
+pub fn run_with_buffer_bytes<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    max_bytes: usize,
+    size_of: impl Fn(&Y) -> usize + Send,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads)
+        .run_with_buffer_bytes(xs, max_bytes, size_of, f, report)
+}
 
-/// Compute `f(x)` for every `x` in `xs` using thread pool and call `report`
-/// for every result and preserve order of elements.
+/// Same as [`run`](fn.run.html), but panics in callbacks are returned as
+/// [`RunError`](enum.RunError.html) instead of being propagated to caller.
 ///
-/// Retutns either number of elements successfully processed or first
-/// enocuntered error.
+/// A panic of `report` is caught on the report thread and returned as
+/// [`RunError::ReportPanic`] with the panic payload. Workers stop taking new
+/// elements in the same way as after an error, so a supervising caller can
+/// log the failure and continue without unwinding.
 ///
-/// Number of threads in the workers pool will be equal to `threads`.
-pub fn run<X: Send, Y: Send, E: Send>(
+/// [`RunError::ReportPanic`]: enum.RunError.html#variant.ReportPanic
+///
+/// # Examples
+/// ```
+/// use parstream::RunError;
+///
+/// let res = parstream::run_checked(0..10u64, 4,
+///     |x| {
+///         if x == 5 { panic!("oops"); }
+///         Ok::<_, ()>(x)
+///     },
+///     |_| Ok(()),
+/// );
+/// match res {
+///     Err(RunError::WorkerPanic(payload)) => {
+///         assert_eq!(payload.downcast_ref::<&str>(), Some(&"oops"));
+///     },
+///     _ => panic!("unexpected result"),
+/// }
+/// ```
+pub fn run_checked<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, RunError<E>> {
+    Config::new().threads(threads).run_checked(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but allows to set capacities of the
+/// internal channels.
+///
+/// `input_cap` is a capacity of the channel which is used for sending
+/// elements to worker threads, i.e. it determines how far ahead of workers
+/// `xs` will be consumed. `report_cap` is a capacity of the channel which
+/// is used for sending results to the report thread.
+///
+/// Note that results which were received out of order are moved from the
+/// report channel to the unbounded reorder buffer, so `report_cap` does not
+/// limit memory used for reordering. If one element takes significantly
+/// longer to process than others, the reorder buffer will grow until this
+/// element is processed and will be limited only by number of elements
+/// which can be consumed from `xs` in the meantime. To bound size of the
+/// reorder buffer use
+/// [`Config::max_reorder_buffer`](struct.Config.html#method.max_reorder_buffer).
+///
+/// The two channels have different roles, so they are sized independently.
+/// Input channel only keeps workers busy, so `input_cap` close to number of
+/// threads is usually enough, larger values only consume `xs` further
+/// ahead. Report channel absorbs bursts of results while `report` is busy,
+/// increasing `report_cap` helps when `report` is slow compared to workers,
+/// while heavy reordering is handled by the reorder buffer regardless of it.
+///
+/// [`run`](fn.run.html) uses `2*threads` for both capacities.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_with_capacity(
+///     0..100u64, 4, 4, 64,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_with_capacity<X: Send, Y: Send, E: Send>(
     xs: impl IntoIterator<Item=X>,
     threads: usize,
+    input_cap: usize,
+    report_cap: usize,
     f: impl Fn(X) -> Result<Y, E> + Sync,
     report: impl FnMut(Y) -> Result<(), E> + Send
 ) -> Result<usize, E> {
-    let (tx, rx) = channel::bounded(2*threads);
-    let (tx2, rx2) = channel::bounded(2*threads);
-    // FLAG_INIT = 0 represents default value
-    // FLAG_INIT > 0 represents number of elements in the non-empty iterator
-    // FLAG_INIT < 0 rуpresents error or panics which have happened in threads
-    let flag = &AtomicIsize::new(FLAG_INIT);
-    let mut result = Ok(0);
-
-    cb_thread::scope(|scope| {
-        for _ in 0..threads {
-            let rxc = rx.clone();
-            let txc = tx2.clone();
-            let fp = &f;
-            scope.spawn(move |_| {
-                for x in rxc.iter() {
-                    if flag.load(Ordering::Acquire) < 0 { break }
-
-                    match x {
-                        Some((i, x)) => {
-                            let res = (i, fp(x)) ;
-                            let r = txc.send(ReportMsg::NewResult(res));
-                            if r.is_err() { break; }
-                        },
-                        None => break,
-                    }
-                }
-            });
-        }
-
-        let res = &mut result;
-        scope.spawn(move |_| {
-            if let Err(err) = run_report(rx2, report, flag) {
-                flag.store(FLAG_ERROR, Ordering::Release);
-                *res = Err(err);
-            }
-        });
-
-        let mut n = 0;
-        for val in xs.into_iter().enumerate() {
-            if flag.load(Ordering::Acquire) < 0 { break }
-            n += 1;
-            tx.send(Some(val)).unwrap();
-        }
-
-        if flag.load(Ordering::Acquire) >= 0 {
-            flag.store(n as isize, Ordering::Release);
-            tx2.send(ReportMsg::None).unwrap();
-        } else {
-            // clear all messages in the channel if there is an error or panic
-            while let Ok(_) = rx.try_recv() {}
-        }
-
-        for _ in 0..threads {
-            tx.send(None).unwrap();
-        }
-    }).unwrap();
-
-    match flag.load(Ordering::Acquire) {
-        n if n >= 0 => { Ok(n as usize) },
-        FLAG_ERROR => result,
-        FLAG_WORKER_PANIC => panic!("worker thread has panicked"),
-        FLAG_REPORT_PANIC => panic!("report thread has panicked"),
-        _ => unreachable!(),
-    }
+    Config::new()
+        .threads(threads)
+        .input_capacity(input_cap)
+        .report_capacity(report_cap)
+        .run(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but number of threads in the workers pool
+/// will be equal to [`std::thread::available_parallelism`] or to 1 if it
+/// can not be determined.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_auto(
+///     0..100u64,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+///
+/// [`std::thread::available_parallelism`]: https://doc.rust-lang.org/std/thread/fn.available_parallelism.html
+pub fn run_auto<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().run(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `report` is called for results in the
+/// order of their completion instead of the order of elements in `xs`.
+///
+/// Results are not buffered, so a slow element will not delay reporting of
+/// results computed after it.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_unordered(0..100u64, 4,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// ys.sort();
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_unordered<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_unordered(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but results are only mostly ordered, so
+/// a slow element does not delay reporting of all results following it.
+///
+/// Result at position `i` is reported as soon as all results at preceding
+/// positions were reported, or when a result at position `i + window` or
+/// higher is received. In the latter case results which were waiting for
+/// the missing ones are reported in the order of their positions, and the
+/// missing results are reported right after they are received. Thus with
+/// `window` larger than number of elements in `xs` the order is fully
+/// preserved.
+///
+/// # Panics
+/// If `window` is equal to zero.
+///
+/// # Examples
+/// ```
+/// use std::{thread, time::Duration};
+///
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_windowed(0..20u64, 4, 8,
+///     |x| {
+///         if x == 0 { thread::sleep(Duration::from_millis(100)); }
+///         Ok(x)
+///     },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(20));
+/// // the slow first element has not delayed all remaining results
+/// assert_ne!(ys[0], 0);
+/// ys.sort();
+/// assert_eq!(ys, (0..20).collect::<Vec<_>>());
+/// ```
+pub fn run_windowed<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    window: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_windowed(xs, window, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but results are not held in the reorder
+/// buffer for longer than `max_latency`.
+///
+/// Results are reported in order of elements, while the oldest buffered
+/// result has waited for less than `max_latency`. After that all buffered
+/// results are reported in the order of their positions without waiting
+/// for the missing ones. The missing results are reported right after they
+/// are received and their positions are passed to `on_reorder_violation`.
+///
+/// Thus ordering becomes best-effort: every result reported out of order is
+/// known to `on_reorder_violation`, and results which were already received
+/// are never delayed much longer than `max_latency` by a slow element.
+///
+/// # Examples
+/// ```
+/// use std::{thread, time::Duration};
+///
+/// let mut late = Vec::new();
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_with_max_latency(0..20u64, 4,
+///     Duration::from_millis(10),
+///     |i| late.push(i),
+///     |x| {
+///         if x == 0 { thread::sleep(Duration::from_millis(200)); }
+///         Ok(x)
+///     },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(20));
+/// assert_eq!(late, vec![0]);
+/// assert_eq!(ys.last(), Some(&0));
+/// ```
+pub fn run_with_max_latency<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    max_latency: std::time::Duration,
+    on_reorder_violation: impl FnMut(usize) + Send,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads)
+        .run_with_max_latency(xs, max_latency, on_reorder_violation, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but the reorder buffer keeps at most
+/// `max_buffered` results in memory, the rest is spilled to a temporary file.
+///
+/// If a slow element holds back many results, results with the highest
+/// positions are encoded by `encode` and appended to a file in
+/// [`std::env::temp_dir`]. When their turn comes they are read back and
+/// decoded by `decode`. Errors returned by workers are always kept in
+/// memory. The file is created only if results are spilled and is removed
+/// when the run returns, with success or not.
+///
+/// Every record in the file consists of position of the result and length
+/// of the encoded bytes, both stored as little endian `u64`, followed by
+/// the bytes. `encode` and `decode` are called on the report thread. IO
+/// errors are converted into the error type of `f` using `From`.
+///
+/// Note that spilling does not block the feeder, so memory used by elements
+/// in flight is limited only by
+/// [`Config::max_in_flight`](struct.Config.html#method.max_in_flight).
+///
+/// [`std::env::temp_dir`]: https://doc.rust-lang.org/std/env/fn.temp_dir.html
+///
+/// # Examples
+/// ```
+/// use std::convert::TryInto;
+/// use std::{thread, time::Duration};
+///
+/// let mut ys = Vec::new();
+/// let res: Result<usize, std::io::Error> = parstream::run_spilled(
+///     0..100u64, 4, 8,
+///     |y: &u64, buf| buf.extend_from_slice(&y.to_le_bytes()),
+///     |buf| Ok(u64::from_le_bytes(buf.try_into().unwrap())),
+///     |x| {
+///         if x == 0 { thread::sleep(Duration::from_millis(100)); }
+///         Ok(x*x)
+///     },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res.unwrap(), 100);
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+#[cfg(feature = "spill")]
+pub fn run_spilled<X: Send, Y: Send, E: Send + From<std::io::Error>>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    max_buffered: usize,
+    encode: impl FnMut(&Y, &mut Vec<u8>) + Send,
+    decode: impl FnMut(&[u8]) -> Result<Y, E> + Send,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads)
+        .run_spilled(xs, max_buffered, encode, decode, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `on_finish` is called on the report
+/// thread after the last result was reported.
+///
+/// `on_finish` is called exactly once if all results were reported
+/// successfully, e.g. for flushing a buffered writer or committing
+/// a transaction used by `report`. It's not called if the run was stopped
+/// by an error, a panic or cancellation. Error returned by `on_finish` is
+/// returned by the run.
+///
+/// # Examples
+/// ```
+/// use std::sync::Mutex;
+///
+/// let batch = Mutex::new(Vec::new());
+/// let mut committed = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_with_finish(0..10u64, 4,
+///     |x| Ok(x*x),
+///     |y| { batch.lock().unwrap().push(y); Ok(()) },
+///     || { committed.append(&mut batch.lock().unwrap()); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(10));
+/// assert_eq!(committed, (0..10u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_with_finish<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+    on_finish: impl FnOnce() -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_with_finish(xs, f, report, on_finish)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and collect results
+/// into vector preserving order of elements.
+///
+/// Returns either collected results or first encountered error, in the
+/// latter case already computed results are discarded.
+///
+/// # Examples
+/// ```
+/// let xs: &[u64] = &[100, 4, 3, 2, 1, 0, 1, 2, 3, 4, 5];
+/// let res: Result<Vec<u64>, ()> = parstream::collect_ordered(xs, 4, |x| {
+///     std::thread::sleep(std::time::Duration::from_millis(*x));
+///     Ok(x*x)
+/// });
+/// assert_eq!(res, Ok(xs.iter().map(|x| x*x).collect()));
+/// ```
+pub fn collect_ordered<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+) -> Result<Vec<Y>, E> {
+    Config::new().threads(threads).collect_ordered(xs, f)
+}
+
+/// Create iterator which yields results of `f(x)` for every `x` in `xs`
+/// computed using thread pool and preserves order of elements.
+///
+/// Computation is performed in a background thread. If `f` returns an error,
+/// it will be yielded by the iterator as the last item, similarly to
+/// [`run`](fn.run.html) results which were not yielded before the error
+/// are discarded. If `f` panics, the panic will be propagated on the next call
+/// of `next`. Dropping the iterator stops computation and waits for all
+/// threads to exit.
+///
+/// # Examples
+/// ```
+/// let ys: Vec<Result<u64, ()>> = parstream::par_map(0..100u64, 4, |x| Ok(x*x))
+///     .filter(|y| y.map(|y| y % 2 == 0).unwrap_or(true))
+///     .take(3)
+///     .collect();
+/// assert_eq!(ys, vec![Ok(0), Ok(4), Ok(16)]);
+/// ```
+pub fn par_map<X, Y, E>(
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+) -> ParMap<Y, E>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads).par_map(xs, f)
+}
+
+/// Same as [`run_checked`](fn.run_checked.html), but computation of every
+/// element is limited by `timeout`.
+///
+/// If `f(x)` does not finish in time, the run is stopped as if `f` has
+/// returned an error and [`RunError::TimedOut`] with position of the element
+/// is returned.
+///
+/// To enforce timeout `f` is computed by helper threads. Note that a helper
+/// thread runs `f` synchronously and can not be interrupted, so after timeout
+/// it is abandoned and keeps running until `f` returns, which is why `f` and
+/// its arguments must be `'static`.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use parstream::RunError;
+///
+/// let res = parstream::run_with_timeout(0..10u64, 4, Duration::from_millis(50),
+///     |x| {
+///         if x == 5 { std::thread::sleep(Duration::from_secs(1)); }
+///         Ok::<_, ()>(x)
+///     },
+///     |_| Ok(()),
+/// );
+/// match res {
+///     Err(RunError::TimedOut(5)) => (),
+///     _ => panic!("unexpected result"),
+/// }
+/// ```
+///
+/// [`RunError::TimedOut`]: enum.RunError.html#variant.TimedOut
+pub fn run_with_timeout<X, Y, E>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    timeout: std::time::Duration,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, RunError<E>>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads).run_with_timeout(xs, timeout, f, report)
+}
+
+/// Same as [`run_checked`](fn.run_checked.html), but returns
+/// [`RunError::WorkersStuck`] instead of blocking indefinitely if threads of
+/// the run do not exit in `join_timeout` after the report closure has
+/// finished.
+///
+/// A run returns only after all its threads have exited, so a worker stuck
+/// inside `f(x)` (e.g. in an infinite loop or a blocked system call) makes
+/// it hang even after the last result or the first error was reported. This
+/// function runs the computation on a background thread and abandons it
+/// on timeout. Stuck threads can not be interrupted, so they are leaked and
+/// keep running until `f` returns, which is why `xs`, `f`, `report` and
+/// elements must be `'static`. Error returned by callbacks is not returned
+/// in this case.
+///
+/// The timeout starts only after the report closure has finished, so
+/// a worker stuck on an element which was not reported yet stalls the run
+/// instead. Use it together with
+/// [`Config::heartbeat`](struct.Config.html#method.heartbeat) to stop such
+/// runs as well.
+///
+/// # Examples
+/// ```
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::{thread, time::Duration};
+/// use parstream::RunError;
+///
+/// static STUCK: AtomicBool = AtomicBool::new(false);
+///
+/// let res = parstream::run_with_join_timeout(0..10u64, 4,
+///     Duration::from_millis(50),
+///     |x| {
+///         if x == 3 {
+///             while !STUCK.load(Ordering::Acquire) { thread::yield_now() }
+///             return Err(x);
+///         }
+///         if x == 5 {
+///             // result of this element would be discarded after the error
+///             STUCK.store(true, Ordering::Release);
+///             thread::sleep(Duration::from_secs(1));
+///         }
+///         Ok(x)
+///     },
+///     |_| Ok(()),
+/// );
+/// match res {
+///     Err(RunError::WorkersStuck(3)) => (),
+///     _ => panic!("unexpected result"),
+/// }
+/// ```
+///
+/// [`RunError::WorkersStuck`]: enum.RunError.html#variant.WorkersStuck
+pub fn run_with_join_timeout<X, Y, E>(
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    threads: usize,
+    join_timeout: std::time::Duration,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+) -> Result<usize, RunError<E>>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads)
+        .run_with_join_timeout(xs, join_timeout, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but the run is stopped after `deadline`.
+///
+/// After deadline no new elements are sent to workers and report closure
+/// is not called anymore. Returns number of elements reported before
+/// the deadline. Note that `f` and iteration over `xs` are not interrupted,
+/// so the function may return later than `deadline` if they block.
+///
+/// # Examples
+/// ```
+/// use std::time::{Duration, Instant};
+///
+/// let deadline = Instant::now() + Duration::from_millis(50);
+/// let res: Result<usize, ()> = parstream::run_until(0.., 4, deadline,
+///     |x: u64| {
+///         std::thread::sleep(Duration::from_millis(1));
+///         Ok(x)
+///     },
+///     |_| Ok(()),
+/// );
+/// assert!(res.unwrap() > 0);
+/// ```
+pub fn run_until<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    deadline: std::time::Instant,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).deadline(deadline).run(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but the run is executed on a background
+/// thread and its result is returned as a future.
+///
+/// This function is a bridge for using a blocking run from an asynchronous
+/// code without blocking the executor, not a fully asynchronous executor:
+/// `f` and `report` are still called synchronously on dedicated threads, so
+/// they, `xs` and elements must be `'static`. The future does not depend on
+/// a specific runtime. Dropping the future does not stop the run, use
+/// [`Config::cancel_token`] for that.
+///
+/// # Panics
+/// If `f` or `report` panics, the panic is propagated on polling of the
+/// completed future.
+///
+/// # Examples
+/// ```
+/// # async fn example() {
+/// let res: Result<usize, ()> = parstream::run_async(
+///     0..100u64, 4,
+///     |x| Ok(x*x),
+///     |_| Ok(()),
+/// ).await;
+/// assert_eq!(res, Ok(100));
+/// # }
+/// ```
+///
+/// [`Config::cancel_token`]: struct.Config.html#method.cancel_token
+#[cfg(feature = "async")]
+pub fn run_async<X, Y, E>(
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+) -> impl std::future::Future<Output=Result<usize, E>>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads).run_async(xs, f, report)
+}
+
+/// Same as [`par_map`](fn.par_map.html), but returns a stream of results.
+///
+/// Results are passed to the stream through a bounded channel with
+/// the report capacity, so if the stream consumer is slow, workers will
+/// be blocked. Dropping the stream stops the run, which finishes on
+/// a background thread without blocking the dropping task.
+///
+/// # Panics
+/// If `f` panics, the panic is propagated on polling of the stream after
+/// all results preceding the panic.
+///
+/// # Examples
+/// ```
+/// use futures_core::Stream;
+///
+/// fn assert_stream<S: Stream<Item=Result<u64, ()>>>(_: &S) {}
+///
+/// let stream = parstream::par_stream(0..100u64, 4, |x| Ok(x*x));
+/// assert_stream(&stream);
+/// ```
+#[cfg(feature = "futures")]
+pub fn par_stream<X, Y, E>(
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+) -> ParStream<Y, E>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads).par_stream(xs, f)
+}
+
+/// Same as [`run`](fn.run.html), but elements are received from `rx` until
+/// it is disconnected.
+///
+/// Unlike passing `rx` as an iterator to `run`, waiting for new elements is
+/// interrupted if the run is stopped by an error, a panic, a deadline or
+/// cancellation (see [`Config::run_from_channel`]), so a long-lived
+/// channel will not keep the run open.
+///
+/// # Examples
+/// ```
+/// let (tx, rx) = crossbeam_channel::unbounded();
+/// let producer = std::thread::spawn(move || {
+///     for x in 0..100u64 { tx.send(x).unwrap(); }
+/// });
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_from_channel(rx, 4,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// producer.join().unwrap();
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+///
+/// [`Config::run_from_channel`]: struct.Config.html#method.run_from_channel
+#[cfg(feature = "crossbeam")]
+pub fn run_from_channel<X: Send, Y: Send, E: Send>(
+    rx: crossbeam_channel::Receiver<X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_from_channel(rx, f, report)
+}
+
+/// Compute `f(x)` for every `x` in `xs` on a background thread and send
+/// results in order of elements to the returned bounded channel.
+///
+/// Capacity of the channel is equal to capacity of the report channel, so
+/// workers are stopped if results are not received. The run stops after
+/// an error was sent or if the receiver was dropped. The returned handle
+/// yields number of reported results including the error or the result
+/// which could not be sent.
+///
+/// # Examples
+/// ```
+/// let (rx, handle) = parstream::run_to_channel(0..100u64, 4,
+///     |x| Ok::<u64, ()>(x*x),
+/// );
+/// let ys: Vec<u64> = rx.iter().map(Result::unwrap).collect();
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// assert_eq!(handle.join(), Ok(100));
+/// ```
+#[cfg(feature = "crossbeam")]
+pub fn run_to_channel<X, Y, E>(
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+) -> (
+    crossbeam_channel::Receiver<Result<Y, E>>,
+    RunHandle<std::convert::Infallible>,
+)
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads).run_to_channel(xs, f)
+}
+
+/// Same as [`run`](fn.run.html), but elements are taken from several
+/// `sources` and results are reported in the global order defined by
+/// `order`.
+///
+/// # Examples
+/// ```
+/// use parstream::MergeOrder;
+///
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_merged(
+///     vec![0..3u64, 10..12, 20..24], 4, MergeOrder::RoundRobin,
+///     |x| Ok(x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(9));
+/// assert_eq!(ys, vec![0, 10, 20, 1, 11, 21, 2, 22, 23]);
+/// ```
+pub fn run_merged<X: Send, Y: Send, E: Send>(
+    sources: Vec<impl IntoIterator<Item=X>>,
+    threads: usize,
+    order: MergeOrder,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_merged(sources, order, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but every worker creates its own state
+/// by calling `init` once before processing elements, the state is passed
+/// to `f` for every element processed by the worker.
+///
+/// It's useful for expensive resources which can not be shared between
+/// threads, e.g. connections or scratch buffers.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_with_init(0..100u64, 4,
+///     || Vec::with_capacity(16),
+///     |buf: &mut Vec<u64>, x| {
+///         buf.clear();
+///         buf.extend(0..x);
+///         Ok(buf.iter().sum::<u64>())
+///     },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| (0..x).sum()).collect::<Vec<_>>());
+/// ```
+pub fn run_with_init<S, X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    init: impl Fn() -> S + Sync,
+    f: impl Fn(&mut S, X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_with_init(xs, init, f, report)
+}
+
+/// Compute `stage2(stage1(x))` for every `x` in `xs` using two thread pools
+/// connected by a channel and report results in order of elements.
+///
+/// Results of the first stage are streamed to the second one in order of
+/// elements instead of being collected, so with
+/// [`Config::max_in_flight`](struct.Config.html#method.max_in_flight),
+/// which applies to both stages, memory used by intermediate results does
+/// not depend on number of elements. Half of `threads` (at least one) is
+/// used by the first stage and the rest (at least one) by the second stage,
+/// so total number of workers is at least two.
+///
+/// First encountered error of either stage stops the run.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, String> = parstream::run_pipeline(
+///     vec!["1", "2", "3", "4"], 4,
+///     |s| s.parse::<u64>().map_err(|e| e.to_string()),
+///     |x| Ok(x * x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(4));
+/// assert_eq!(ys, vec![1, 4, 9, 16]);
+/// ```
+pub fn run_pipeline<X: Send, M: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    stage1: impl Fn(X) -> Result<M, E> + Sync,
+    stage2: impl Fn(M) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_pipeline(xs, stage1, stage2, report)
+}
+
+/// Same as [`run`](fn.run.html), but every worker uses its own clone of `f`,
+/// so `f` does not have to be `Sync` and can mutate its captured state.
+///
+/// # Examples
+/// ```
+/// use std::cell::Cell;
+///
+/// // `Cell` is not `Sync`, so this closure can not be used with `run`
+/// let calls = Cell::new(0u64);
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_clone(0..100u64, 4,
+///     move |x| {
+///         calls.set(calls.get() + 1);
+///         assert!(calls.get() <= 100);
+///         Ok(x*x)
+///     },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_clone<X: Send, Y: Send, E: Send, F>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: F,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E>
+    where F: FnMut(X) -> Result<Y, E> + Clone + Send
+{
+    Config::new().threads(threads).run_clone(xs, f, report)
+}
+
+/// Same as [`run_with_init`](fn.run_with_init.html), but workers borrow
+/// their states from `states` instead of creating them.
+///
+/// Number of workers is equal to length of `states` and every worker gets
+/// a mutable reference to a separate element of `states`, so no locking
+/// is needed. After the run states are available to the caller again,
+/// e.g. for reusing scratch buffers between runs.
+///
+/// # Panics
+/// If `states` is empty.
+///
+/// # Examples
+/// ```
+/// let mut counts = vec![0usize; 4];
+/// let res: Result<usize, ()> = parstream::run_with_states(0..100u64,
+///     &mut counts,
+///     |count, x| { *count += 1; Ok(x) },
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(counts.iter().sum::<usize>(), 100);
+/// ```
+pub fn run_with_states<S: Send, X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    states: &mut [S],
+    f: impl Fn(&mut S, X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().run_with_states(xs, states, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `f` also receives index of the worker
+/// which computes it, indices are in the range `0..threads`.
+///
+/// # Examples
+/// ```
+/// let res: Result<usize, ()> = parstream::run_with_worker_index(0..100u64, 4,
+///     |w, x| {
+///         assert!(w < 4);
+///         Ok(x*x)
+///     },
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Ok(100));
+/// ```
+pub fn run_with_worker_index<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(usize, X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_with_worker_index(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `report` is called only for results
+/// which are equal to `Some`, order of reported results is preserved.
+///
+/// Returns number of processed elements including filtered ones.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_filter_map(0..100u64, 4,
+///     |x| Ok(if x % 3 == 0 { Some(x*x) } else { None }),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).filter(|x| x % 3 == 0).map(|x| x*x)
+///     .collect::<Vec<_>>());
+/// ```
+pub fn run_filter_map<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Option<Y>, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_filter_map(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `report` is not called for results
+/// equal to the previously reported one, i.e. consecutive duplicates in order
+/// of elements are collapsed into a single result.
+///
+/// The last reported result is kept for comparison, so `report` receives
+/// results by reference. Returns number of processed elements including
+/// skipped ones.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_dedup(0..100u64, 4,
+///     |x| Ok(x / 10),
+///     |&y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..10u64).collect::<Vec<_>>());
+/// ```
+pub fn run_dedup<X: Send, Y: Send + PartialEq, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(&Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_dedup(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `f` returns a collection and `report`
+/// is called for every its element. Elements produced by `f(xs[0])` are
+/// reported first, then elements produced by `f(xs[1])` and so on.
+///
+/// Returns number of processed input elements. Results waiting for
+/// preceding results are buffered as a whole, so to bound memory usage when
+/// `f` produces large collections use
+/// [`Config::max_reorder_buffer`](struct.Config.html#method.max_reorder_buffer)
+/// or return lazy iterators, which are consumed by the report thread.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_flat_map(0..10u64, 4,
+///     |x| Ok(vec![x; x as usize]),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(10));
+/// assert_eq!(ys, (0..10u64).flat_map(|x| vec![x; x as usize])
+///     .collect::<Vec<_>>());
+/// ```
+pub fn run_flat_map<X: Send, I, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<I, E> + Sync,
+    report: impl FnMut(I::Item) -> Result<(), E> + Send,
+) -> Result<usize, E>
+    where I: IntoIterator + Send
+{
+    Config::new().threads(threads).run_flat_map(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `report` also receives position of
+/// the input element which has produced the result.
+///
+/// # Examples
+/// ```
+/// let mut ys = vec![0; 100];
+/// let res: Result<usize, ()> = parstream::run_indexed_report(0..100u64, 4,
+///     |x| Ok(x*x),
+///     |i, y| { ys[i] = y; Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_indexed_report<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(usize, Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_indexed_report(xs, f, report)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and reduce
+/// results using per-worker accumulators.
+///
+/// Every worker creates its accumulator using `init` and folds its results
+/// into it using `fold` in order of their computation. After all elements
+/// were processed, accumulators of all workers are merged on the calling
+/// thread using `combine`. Results are not reordered, so `fold` and
+/// `combine` should be associative and commutative to get deterministic
+/// results. Returns either the merged accumulator or first encountered
+/// error.
+///
+/// # Examples
+/// ```
+/// let res: Result<u64, ()> = parstream::run_reduce(0..100u64, 4,
+///     || 0,
+///     |acc, y| acc + y,
+///     |x| Ok(x*x),
+///     |a, b| a + b,
+/// );
+/// assert_eq!(res, Ok((0..100u64).map(|x| x*x).sum()));
+/// ```
+pub fn run_reduce<X: Send, Y: Send, A: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    init: impl Fn() -> A + Sync,
+    fold: impl Fn(A, Y) -> A + Sync,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    combine: impl Fn(A, A) -> A,
+) -> Result<A, E> {
+    Config::new().threads(threads).run_reduce(xs, init, fold, f, combine)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and fold results
+/// in order of their arrival using `fold`.
+///
+/// Results are not reordered, so no reorder buffer is used and a slow
+/// element does not delay folding of results following it. `fold` is
+/// called sequentially, so it does not have to be `Sync`, but it should be
+/// commutative to get deterministic results. Returns either the
+/// accumulator or first encountered error, which stops the run.
+///
+/// See also [`run_reduce`](fn.run_reduce.html), which folds results on
+/// workers.
+///
+/// # Examples
+/// ```
+/// let res: Result<u64, ()> = parstream::reduce_unordered(0..100u64, 4, 0,
+///     |x| Ok(x*x),
+///     |acc, y| *acc = (*acc).max(y),
+/// );
+/// assert_eq!(res, Ok(99*99));
+/// ```
+pub fn reduce_unordered<X: Send, Y: Send, A: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    init: A,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    fold: impl FnMut(&mut A, Y) + Send,
+) -> Result<A, E> {
+    Config::new().threads(threads).reduce_unordered(xs, init, f, fold)
+}
+
+/// Same as [`collect_ordered`](fn.collect_ordered.html), but on error
+/// results collected before the error are returned together with it.
+///
+/// Collected results always form a prefix of results in order of elements,
+/// which ends right before the failed element.
+///
+/// # Examples
+/// ```
+/// let (ys, err) = parstream::collect_partial(0..100u64, 4, |x| {
+///     if x == 10 { Err(x) } else { Ok(x*x) }
+/// });
+/// assert_eq!(err, Some(10));
+/// assert_eq!(ys, (0..10u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn collect_partial<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+) -> (Vec<Y>, Option<E>) {
+    Config::new().threads(threads).collect_partial(xs, f)
+}
+
+/// Same as [`collect_ordered`](fn.collect_ordered.html), but also returns
+/// number of processed elements.
+///
+/// On success the number is equal to length of the returned vector.
+///
+/// # Examples
+/// ```
+/// let res: Result<_, ()> = parstream::run_collect(0..5u64, 4, |x| Ok(x*x));
+/// assert_eq!(res, Ok((5, vec![0, 1, 4, 9, 16])));
+/// ```
+pub fn run_collect<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+) -> Result<(usize, Vec<Y>), E> {
+    Config::new().threads(threads).run_collect(xs, f)
+}
+
+/// Same as [`run`](fn.run.html), but elements are taken from the back of
+/// `xs`, so results are reported from the last element to the first one.
+///
+/// `xs` must be a `DoubleEndedIterator`, e.g. a slice or a range, so
+/// elements are not collected to find the last one. Positions of elements,
+/// e.g. in errors, are counted from the back: the last element of `xs` has
+/// position 0.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_rev(&[1u64, 2, 3, 4], 2,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(4));
+/// assert_eq!(ys, vec![16, 9, 4, 1]);
+/// ```
+pub fn run_rev<X: Send, Y: Send, E: Send, I>(
+    xs: impl IntoIterator<Item=X, IntoIter=I>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E>
+    where I: DoubleEndedIterator<Item=X>
+{
+    Config::new().threads(threads).run_rev(xs, f, report)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and fold results
+/// in order of elements using `combine`.
+///
+/// Results are combined sequentially, so `combine` does not have to be
+/// associative. Returns either the final accumulator or first encountered
+/// error.
+///
+/// The first error of either `f` or `combine` stops the run: no new tasks
+/// will be started and the accumulator is discarded. Since results are
+/// combined in order of elements, an error of `f` is returned only after
+/// results of all preceding elements were combined.
+///
+/// # Examples
+/// ```
+/// let res: Result<String, ()> = parstream::fold_ordered(0..10u64, 4,
+///     String::new(),
+///     |x| Ok(x.to_string()),
+///     |acc, y| Ok(acc + &y),
+/// );
+/// assert_eq!(res.unwrap(), "0123456789");
+/// ```
+pub fn fold_ordered<X: Send, Y: Send, A: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    init: A,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    combine: impl FnMut(A, Y) -> Result<A, E> + Send,
+) -> Result<A, E> {
+    Config::new().threads(threads).fold_ordered(xs, init, f, combine)
+}
+
+/// Compute `map(x)` for every `x` in `xs` using thread pool and fold results
+/// in order of elements using `fold`, stopping on the first error.
+///
+/// Same as [`fold_ordered`](fn.fold_ordered.html) with names of closures
+/// following `Iterator::try_fold`. Mapped results are passed to `fold` in
+/// order of elements together with the accumulator, whose type may differ
+/// from type of results. The first error of either closure stops the run
+/// and is returned, while the accumulator is discarded. Returns the final
+/// accumulator if all elements were processed successfully.
+///
+/// # Examples
+/// ```
+/// let res = parstream::try_fold_ordered(0..10u64, 4, Vec::new(),
+///     |x| Ok(x*x),
+///     |mut acc, y| {
+///         if y > 50 { return Err(y) }
+///         acc.push(y);
+///         Ok(acc)
+///     },
+/// );
+/// assert_eq!(res, Err(64));
+/// ```
+pub fn try_fold_ordered<X: Send, Y: Send, A: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    init: A,
+    map: impl Fn(X) -> Result<Y, E> + Sync,
+    fold: impl FnMut(A, Y) -> Result<A, E> + Send,
+) -> Result<A, E> {
+    Config::new().threads(threads).try_fold_ordered(xs, init, map, fold)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and scan results
+/// in order of elements using `step`.
+///
+/// `step` updates the accumulator with every result in order of elements
+/// and returns a value which is passed to `report`, e.g. a prefix sum.
+/// Both closures run sequentially, so they can hold state. Returns either
+/// the final accumulator or first encountered error, which stops the run.
+///
+/// # Examples
+/// ```
+/// let mut sums = Vec::new();
+/// let res: Result<u64, ()> = parstream::scan_ordered(1..=5u64, 4, 0,
+///     |x| Ok(x*x),
+///     |acc, y| { *acc += y; Ok(*acc) },
+///     |s| { sums.push(s); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(55));
+/// assert_eq!(sums, vec![1, 5, 14, 30, 55]);
+/// ```
+pub fn scan_ordered<X: Send, Y: Send, A: Send, B, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    init: A,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    step: impl FnMut(&mut A, Y) -> Result<B, E> + Send,
+    report: impl FnMut(B) -> Result<(), E> + Send,
+) -> Result<A, E> {
+    Config::new().threads(threads).scan_ordered(xs, init, f, step, report)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool without stopping
+/// on errors.
+///
+/// Returns successful results in order of elements and errors together
+/// with positions of elements which have produced them.
+///
+/// # Examples
+/// ```
+/// let (ys, errors) = parstream::run_collect_errors(0..10u64, 4, |x| {
+///     if x % 3 == 0 { Err(x) } else { Ok(x*x) }
+/// });
+/// assert_eq!(ys, vec![1, 4, 16, 25, 49, 64]);
+/// assert_eq!(errors, vec![(0, 0), (3, 3), (6, 6), (9, 9)]);
+/// ```
+pub fn run_collect_errors<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+) -> (Vec<Y>, Vec<(usize, E)>) {
+    Config::new().threads(threads).run_collect_errors(xs, f)
+}
+
+/// Same as [`run`](fn.run.html), but errors of `f` are handled according
+/// to `policy`.
+///
+/// Returns number of successfully reported results or errors together with
+/// positions of elements which have produced them:
+/// - [`ErrorPolicy::FailFast`]: the first error in order of elements,
+///   same as `run`.
+/// - [`ErrorPolicy::CollectAll`]: all errors in order of elements, returned
+///   after all elements were processed.
+/// - [`ErrorPolicy::Continue`]: errors are passed to the hook, so only
+///   an error of `report` can be returned.
+///
+/// An error of `report` stops the run regardless of the policy and it's
+/// returned as the last error.
+///
+/// # Examples
+/// ```
+/// use parstream::ErrorPolicy;
+///
+/// let mut ys = Vec::new();
+/// let res = parstream::run_with_error_policy(0..10u64, 4,
+///     ErrorPolicy::CollectAll,
+///     |x| if x % 3 == 0 { Err(x) } else { Ok(x*x) },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Err(vec![(0, 0), (3, 3), (6, 6), (9, 9)]));
+/// assert_eq!(ys, vec![1, 4, 16, 25, 49, 64]);
+/// ```
+///
+/// [`ErrorPolicy::FailFast`]: enum.ErrorPolicy.html#variant.FailFast
+/// [`ErrorPolicy::CollectAll`]: enum.ErrorPolicy.html#variant.CollectAll
+/// [`ErrorPolicy::Continue`]: enum.ErrorPolicy.html#variant.Continue
+pub fn run_with_error_policy<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    policy: ErrorPolicy<'_, E>,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, Vec<(usize, E)>> {
+    Config::new().threads(threads)
+        .run_with_error_policy(xs, policy, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but every error of `f` is passed to
+/// `on_error` together with position of the element.
+///
+/// `on_error` is called on the worker thread right after `f` has returned
+/// the error, so it also observes errors which are discarded because
+/// an error of an element with a lower position was returned. Elements
+/// with positions after an already failed one and elements which were not
+/// sent to workers before the run was stopped may be not processed, so
+/// their errors are not observed.
+///
+/// # Examples
+/// ```
+/// use std::sync::Mutex;
+///
+/// let failed = Mutex::new(Vec::new());
+/// let res = parstream::run_with_error_hook(0..10u64, 4,
+///     |i, _| failed.lock().unwrap().push(i),
+///     |x| if x == 3 { Err(x) } else { Ok(x) },
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Err(3));
+/// assert_eq!(*failed.lock().unwrap(), vec![3]);
+/// ```
+pub fn run_with_error_hook<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    on_error: impl Fn(usize, &E) + Sync,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_with_error_hook(xs, on_error, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but the run is stopped only after
+/// `threshold` consecutive elements have failed.
+///
+/// Results are processed in order of elements. Errors of `f` are held back
+/// until a successful result is reached, after which they are passed to
+/// `on_error` together with positions of elements. If `threshold` errors
+/// follow each other, the circuit opens: the run is stopped and
+/// [`CircuitError::Open`] with the held errors is returned. Errors which
+/// remain held after the last element are passed to `on_error` before
+/// the run returns. An error of `report` stops the run immediately.
+///
+/// Returns number of successfully reported results.
+///
+/// # Panics
+/// If `threshold` is equal to 0.
+///
+/// # Examples
+/// ```
+/// use parstream::CircuitError;
+///
+/// let mut skipped = Vec::new();
+/// let mut ys = Vec::new();
+/// let res = parstream::run_with_circuit_breaker(0..100u64, 4, 3,
+///     |i, _| skipped.push(i),
+///     |x| if x % 10 == 5 || x >= 50 { Err(x) } else { Ok(x) },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Err(CircuitError::Open(vec![(50, 50), (51, 51), (52, 52)])));
+/// assert_eq!(skipped, vec![5, 15, 25, 35, 45]);
+/// assert_eq!(ys.len(), 45);
+/// ```
+///
+/// [`CircuitError::Open`]: enum.CircuitError.html#variant.Open
+pub fn run_with_circuit_breaker<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    threshold: usize,
+    on_error: impl FnMut(usize, E) + Send,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, CircuitError<E>> {
+    Config::new().threads(threads)
+        .run_with_circuit_breaker(xs, threshold, on_error, f, report)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and collect all
+/// results including errors, `i`-th element of the returned vector is equal
+/// to result of `f(xs[i])`.
+///
+/// Errors do not stop the run.
+///
+/// # Examples
+/// ```
+/// let ys = parstream::run_results(0..5u64, 4, |x| {
+///     if x == 2 { Err(x) } else { Ok(x*x) }
+/// });
+/// assert_eq!(ys, vec![Ok(0), Ok(1), Err(2), Ok(9), Ok(16)]);
+/// ```
+pub fn run_results<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+) -> Vec<Result<Y, E>> {
+    Config::new().threads(threads).run_results(xs, f)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and write returned
+/// bytes to `w` preserving order of elements.
+///
+/// IO errors are converted into the error type of `f` using `From`. If
+/// writing fails, the run is stopped and the error is returned, so `w` may
+/// contain only a part of the output. `w` is flushed after all results were
+/// written. Returns number of written results.
+///
+/// # Examples
+/// ```
+/// let mut out = Vec::new();
+/// let res: Result<usize, std::io::Error> = parstream::run_to_writer(
+///     0..5u64, 4,
+///     |x| Ok(format!("{}\n", x*x).into_bytes()),
+///     &mut out,
+/// );
+/// assert_eq!(res.unwrap(), 5);
+/// assert_eq!(out, b"0\n1\n4\n9\n16\n");
+/// ```
+pub fn run_to_writer<X: Send, E: Send + From<std::io::Error>>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Vec<u8>, E> + Sync,
+    w: impl std::io::Write + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_to_writer(xs, f, w)
+}
+
+/// Compute `f(line)` for every line read from `reader` using thread pool and
+/// call `report` for every result preserving order of lines.
+///
+/// Lines are read using [`BufRead::lines`], so line terminators are not
+/// included. Read errors are converted into the error type of `f` using
+/// `From` and stop the run like errors returned by `f`, no lines are read
+/// after the first read error.
+///
+/// # Examples
+/// ```
+/// let text = "1\n2\n3\n";
+/// let mut ys = Vec::new();
+/// let res: Result<usize, std::io::Error> = parstream::run_lines(
+///     text.as_bytes(), 4,
+///     |line| Ok(line.len()),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res.unwrap(), 3);
+/// assert_eq!(ys, vec![1, 1, 1]);
+/// ```
+///
+/// [`BufRead::lines`]: https://doc.rust-lang.org/std/io/trait.BufRead.html#method.lines
+pub fn run_lines<Y: Send, E: Send + From<std::io::Error>>(
+    reader: impl std::io::BufRead,
+    threads: usize,
+    f: impl Fn(String) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_lines(reader, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `report` is called on the calling
+/// thread, so it does not have to be `Send`.
+///
+/// Elements of `xs` are sent to workers by a separate feeder thread
+/// instead, so iterator must be `Send`. The calling thread is busy
+/// consuming results until the run finishes.
+///
+/// # Examples
+/// ```
+/// use std::rc::Rc;
+/// use std::cell::RefCell;
+///
+/// let ys = Rc::new(RefCell::new(Vec::new()));
+/// let res: Result<usize, ()> = parstream::run_inline_report(0..100u64, 4,
+///     |x| Ok(x*x),
+///     |y| { ys.borrow_mut().push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(*ys.borrow(), (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_inline_report<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X, IntoIter=impl Iterator<Item=X> + Send>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E>,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_inline_report(xs, f, report)
+}
+
+/// Same as [`run_indexed_report`](fn.run_indexed_report.html), but results
+/// are reported in the order of keys provided together with elements instead
+/// of the order of elements in `xs`.
+///
+/// Keys are expected to form a sequence `0..n`. If some key is missing,
+/// results with bigger keys are buffered until all elements have been
+/// processed and after that they are reported in the order of keys. Results
+/// with duplicate keys are reported in the order of their completion, e.g.
+/// with a single thread in the order of elements in `xs`.
+///
+/// Note that
+/// [`Config::max_reorder_buffer`](struct.Config.html#method.max_reorder_buffer)
+/// limits number of elements in the order of `xs`, so if keys are shuffled
+/// more than the limit or there are gaps in keys, the run will block
+/// forever.
+///
+/// # Examples
+/// ```
+/// let xs = vec![(2, "c"), (0, "a"), (3, "d"), (1, "b")];
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_by_key(xs, 4,
+///     |x| Ok(x.to_uppercase()),
+///     |key, y| { ys.push((key, y)); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(4));
+/// assert_eq!(ys, vec![
+///     (0, "A".to_string()), (1, "B".to_string()),
+///     (2, "C".to_string()), (3, "D".to_string()),
+/// ]);
+/// ```
+pub fn run_by_key<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=(usize, X)>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(usize, Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_by_key(xs, f, report)
+}
+
+/// Compute `f(x)` for every `x` in `seeds` and for every element generated
+/// by `f` using thread pool and report results.
+///
+/// `f` returns results of the element together with new elements, which
+/// are processed in the same way, e.g. links discovered by a crawler.
+/// The run completes after there are no queued elements and all processed
+/// elements were completed. Returns number of processed elements.
+///
+/// Elements are processed and their results are reported in order of
+/// discovery: seeds come first, followed by elements generated by them in
+/// the order of elements which have generated them, i.e. elements are
+/// traversed in breadth-first order. Results of one element are reported
+/// in order of the returned vector.
+///
+/// [`Config::prefetch`](struct.Config.html#method.prefetch) is ignored by
+/// this function.
+///
+/// # Examples
+/// ```
+/// // expand binary tree of depth 3
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_expanding(vec![1u64], 4,
+///     |x| {
+///         let children = if x < 8 { vec![2*x, 2*x + 1] } else { vec![] };
+///         Ok((vec![x], children))
+///     },
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(15));
+/// assert_eq!(ys, (1..16).collect::<Vec<_>>());
+/// ```
+pub fn run_expanding<X: Send, Y: Send, E: Send>(
+    seeds: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<(Vec<Y>, Vec<X>), E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_expanding(seeds, f, report)
+}
+
+/// Same as [`run_by_key`](fn.run_by_key.html), but keys which are missing
+/// for `timeout` are skipped.
+///
+/// If the smallest unreported key has not arrived during `timeout`, while
+/// results with bigger keys are buffered, all keys up to the smallest
+/// buffered one are skipped and passed to `on_gap`, and buffered results are
+/// reported. Results with skipped keys which arrive later are dropped, so
+/// results are never reported out of order. Gaps which remain after all
+/// elements were processed are passed to `on_gap` too.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// // key 1 never arrives
+/// let xs = vec![(0, "a"), (2, "c"), (3, "d")];
+/// let mut gaps = Vec::new();
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_by_key_gapped(xs, 4,
+///     Duration::from_millis(10),
+///     |key| gaps.push(key),
+///     |x| Ok(x.to_uppercase()),
+///     |key, y| { ys.push((key, y)); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(3));
+/// assert_eq!(gaps, vec![1]);
+/// assert_eq!(ys, vec![
+///     (0, "A".to_string()), (2, "C".to_string()), (3, "D".to_string()),
+/// ]);
+/// ```
+pub fn run_by_key_gapped<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=(usize, X)>,
+    threads: usize,
+    timeout: std::time::Duration,
+    on_gap: impl FnMut(usize) + Send,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(usize, Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads)
+        .run_by_key_gapped(xs, timeout, on_gap, f, report)
+}
+
+/// Same as [`run_checked`](fn.run_checked.html), but `f` and `report` can
+/// return errors of different types.
+///
+/// Errors of `f` are returned as [`RunError::Callback`] and errors of
+/// `report` are returned as [`RunError::Report`].
+///
+/// # Examples
+/// ```
+/// use parstream::RunError;
+///
+/// let res = parstream::run_split_errors(["1", "2", "x"].iter(), 2,
+///     |s| s.parse::<u64>(),
+///     |y| if y > 1 { Err("sink is full") } else { Ok(()) },
+/// );
+/// match res {
+///     Err(RunError::Report(e)) => assert_eq!(e, "sink is full"),
+///     Err(RunError::Callback(e)) => println!("parse error: {}", e),
+///     _ => panic!("unexpected result"),
+/// }
+/// ```
+///
+/// [`RunError::Callback`]: enum.RunError.html#variant.Callback
+/// [`RunError::Report`]: enum.RunError.html#variant.Report
+pub fn run_split_errors<X: Send, Y: Send, EF: Send, ER: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, EF> + Sync,
+    report: impl FnMut(Y) -> Result<(), ER> + Send,
+) -> Result<usize, RunError<EF, ER>> {
+    Config::new().threads(threads).run_split_errors(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but on error also returns number of
+/// elements which were successfully reported before it.
+///
+/// Reported elements always form a prefix of `xs`, so the run can be
+/// resumed from the element at position `completed`.
+///
+/// # Examples
+/// ```
+/// use parstream::RunFailure;
+///
+/// let res = parstream::run_with_count(0..100u64, 4,
+///     |x| if x == 42 { Err("oops") } else { Ok(x) },
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Err(RunFailure { error: "oops", completed: 42 }));
+/// ```
+pub fn run_with_count<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, RunFailure<E>> {
+    Config::new().threads(threads).run_with_count(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but if `f(x)` returns an error, it's
+/// retried up to `retries` times with `backoff` delay between attempts.
+///
+/// Error of the last attempt is returned if all attempts have failed.
+/// Use zero `backoff` to retry without delay. Failed elements are not
+/// retried after the run was stopped, e.g. by cancellation or deadline.
+///
+/// # Examples
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// let attempts = AtomicUsize::new(0);
+/// let res: Result<usize, ()> = parstream::run_with_retries(0..10u64, 4,
+///     2, Duration::from_millis(1),
+///     |x| {
+///         if x == 5 && attempts.fetch_add(1, Ordering::Relaxed) < 2 {
+///             return Err(());
+///         }
+///         Ok(x)
+///     },
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Ok(10));
+/// ```
+pub fn run_with_retries<X: Send + Clone, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    retries: usize,
+    backoff: std::time::Duration,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads)
+        .run_with_retries(xs, retries, backoff, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but elements are sent to workers in chunks
+/// of up to `chunk_size` elements, which reduces synchronization overhead
+/// for cheap `f`.
+///
+/// `f` must return results for all elements of a chunk, `report` is called
+/// for every result in order of elements. Returns number of reported
+/// results, which is equal to number of processed elements unless the run
+/// was stopped by cancellation or deadline.
+///
+/// # Panics
+/// If `chunk_size` is equal to 0.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_chunked(0..1000u64, 4, 64,
+///     |xs| Ok(xs.into_iter().map(|x| x*x).collect()),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(1000));
+/// assert_eq!(ys, (0..1000u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+pub fn run_chunked<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    chunk_size: usize,
+    f: impl Fn(Vec<X>) -> Result<Vec<Y>, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_chunked(xs, chunk_size, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but `report` can stop the run without
+/// an error by returning `ControlFlow::Break`.
+///
+/// After break no new tasks will be started and number of reported elements
+/// including the last one is returned.
+///
+/// # Examples
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_control_flow(0.., 4,
+///     |x: u64| Ok(x*x),
+///     |y| {
+///         ys.push(y);
+///         if y >= 49 { Ok(ControlFlow::Break(())) } else { Ok(ControlFlow::Continue(())) }
+///     },
+/// );
+/// assert_eq!(res, Ok(8));
+/// assert_eq!(ys, vec![0, 1, 4, 9, 16, 25, 36, 49]);
+/// ```
+pub fn run_control_flow<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<std::ops::ControlFlow<()>, E> + Send,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_control_flow(xs, f, report)
+}
+
+/// Compute `f(x)` for every `x` in `xs` using thread pool and return
+/// position and result of the first element in order of `xs` for which `f`
+/// returns `Some`.
+///
+/// Results are checked in order of elements, so a match is returned only
+/// after all preceding elements were checked, even if a later element has
+/// matched earlier. After the first match no new tasks will be started.
+/// If `f` returns an error for a preceding element, the error is returned.
+///
+/// # Examples
+/// ```
+/// let res: Result<_, ()> = parstream::find_first(0..100u64, 4, |x| {
+///     Ok(if x*x > 50 { Some(x*x) } else { None })
+/// });
+/// assert_eq!(res, Ok(Some((8, 64))));
+/// ```
+pub fn find_first<X: Send, Y: Send, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Option<Y>, E> + Sync,
+) -> Result<Option<(usize, Y)>, E> {
+    Config::new().threads(threads).find_first(xs, f)
+}
+
+/// Same as [`run`](fn.run.html), but every result is passed to all closures
+/// in `reports` in the order of their positions in the vector.
+///
+/// If one of report closures returns an error, the run is stopped and
+/// the result is not passed to the subsequent closures.
+///
+/// # Examples
+/// ```
+/// let mut a = Vec::new();
+/// let mut b = 0;
+/// let res: Result<usize, ()> = parstream::run_tee(0..10u64, 4,
+///     |x| Ok(x*x),
+///     vec![
+///         Box::new(|y| { a.push(y); Ok(()) }),
+///         Box::new(|y| { b += y; Ok(()) }),
+///     ],
+/// );
+/// assert_eq!(res, Ok(10));
+/// assert_eq!(a, (0..10u64).map(|x| x*x).collect::<Vec<_>>());
+/// assert_eq!(b, 285);
+/// ```
+pub fn run_tee<'a, X: Send, Y: Send + Clone, E: Send>(
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    reports: Vec<BoxedReport<'a, Y, E>>,
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_tee(xs, f, reports)
+}
+
+/// Same as [`run`](fn.run.html), but the run is executed on a background
+/// thread and a handle which allows to wait for its result or to cancel it
+/// is returned right away.
+///
+/// Because the run outlives the calling function, `xs`, `f`, `report` and
+/// elements must be `'static`.
+///
+/// # Examples
+/// ```
+/// let handle = parstream::spawn_run(0..100u64, 4,
+///     |x| Ok::<u64, ()>(x*x),
+///     |_| Ok(()),
+/// );
+/// // the calling thread is free to do other work here
+/// assert_eq!(handle.join(), Ok(100));
+/// ```
+pub fn spawn_run<X, Y, E>(
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+) -> RunHandle<E>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads).spawn_run(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but returns as soon as the report closure
+/// has finished without waiting for workers to exit.
+///
+/// `run` joins all threads before returning, so after an error or a break
+/// it waits until workers finish the elements they are processing, even if
+/// their results will be discarded. This function instead runs the
+/// computation on a background thread and returns after the last result or
+/// the first error was reported. Workers finish their current elements in
+/// the background and exit without starting new ones.
+///
+/// The cost of the prompt return is that threads may still run after it, so
+/// `xs`, `f`, `report` and elements must be `'static`, and the remaining
+/// work competes for CPU with the caller. If the run is stopped by a panic,
+/// cancellation or deadline, the function waits for all threads as `run`.
+///
+/// # Examples
+/// ```
+/// use std::{thread, time::Duration};
+///
+/// let res = parstream::run_detached(0..100u64, 4,
+///     |x| {
+///         if x == 10 { return Err(x) }
+///         // results of these elements are discarded
+///         if x > 10 { thread::sleep(Duration::from_millis(100)) }
+///         Ok(x)
+///     },
+///     |_| Ok(()),
+/// );
+/// assert_eq!(res, Err(10));
+/// ```
+pub fn run_detached<X, Y, E>(
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+) -> Result<usize, E>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    Config::new().threads(threads).run_detached(xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but workers and the report thread are
+/// spawned in the existing crossbeam `scope` instead of a new one.
+///
+/// This allows the run to take part in a larger set of scoped threads.
+/// The function still returns only after all threads spawned by it are
+/// finished, so results are returned in the same way as by `run`. Thread
+/// names and stack size set in [`Config`](struct.Config.html) are ignored.
+///
+/// # Examples
+/// ```
+/// let mut ys = Vec::new();
+/// crossbeam_utils::thread::scope(|s| {
+///     s.spawn(|_| {
+///         // other work in the same scope
+///     });
+///     let res: Result<usize, ()> = parstream::run_in_scope(s, 0..100u64, 4,
+///         |x| Ok(x*x),
+///         |y| { ys.push(y); Ok(()) },
+///     );
+///     assert_eq!(res, Ok(100));
+/// }).unwrap();
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+#[cfg(feature = "crossbeam")]
+pub fn run_in_scope<'env, X: Send, Y: Send, E: Send>(
+    scope: &crossbeam_utils::thread::Scope<'env>,
+    xs: impl IntoIterator<Item=X>,
+    threads: usize,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send
+) -> Result<usize, E> {
+    Config::new().threads(threads).run_in_scope(scope, xs, f, report)
+}
+
+/// Same as [`run`](fn.run.html), but workers and the report closure are run
+/// on threads of the rayon `pool` instead of newly spawned threads.
+///
+/// Number of workers is equal to number of threads in `pool` minus one,
+/// since one more pool thread is used by the report closure. Those threads
+/// are blocked until the run finishes, so they are not available for other
+/// rayon tasks in the meantime. Elements are still sent to workers from the
+/// calling thread, thus the function should not be called from a thread of
+/// the same pool. Use
+/// [`Config::run_in_pool`](struct.Config.html#method.run_in_pool) to set
+/// a smaller number of workers.
+///
+/// # Panics
+/// If `pool` has less than 2 threads.
+///
+/// # Examples
+/// ```
+/// let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+/// let mut ys = Vec::new();
+/// let res: Result<usize, ()> = parstream::run_in_pool(&pool, 0..100u64,
+///     |x| Ok(x*x),
+///     |y| { ys.push(y); Ok(()) },
+/// );
+/// assert_eq!(res, Ok(100));
+/// assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// ```
+#[cfg(feature = "rayon")]
+pub fn run_in_pool<X: Send, Y: Send, E: Send>(
+    pool: &rayon::ThreadPool,
+    xs: impl IntoIterator<Item=X>,
+    f: impl Fn(X) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    Config::new().run_in_pool(pool, xs, f, report)
 }
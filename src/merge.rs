@@ -0,0 +1,58 @@
+/// Order in which elements of several sources are processed by
+/// [`run_merged`](fn.run_merged.html).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MergeOrder {
+    /// All elements of the first source, then of the second one and so on.
+    Concat,
+    /// One element of every source in turn, exhausted sources are skipped.
+    RoundRobin,
+}
+
+/// Iterator over elements of `sources` in the merge order.
+pub(crate) struct Merged<I> {
+    sources: Vec<I>,
+    order: MergeOrder,
+    // index of the source which yields the next element
+    next: usize,
+}
+
+impl<I: Iterator> Merged<I> {
+    pub(crate) fn new(sources: Vec<I>, order: MergeOrder) -> Self {
+        Self { sources, order, next: 0 }
+    }
+}
+
+impl<I: Iterator> Iterator for Merged<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.sources.is_empty() {
+            let i = self.next;
+            match self.sources[i].next() {
+                Some(x) => {
+                    if self.order == MergeOrder::RoundRobin {
+                        self.next = (i + 1) % self.sources.len();
+                    }
+                    return Some(x);
+                },
+                None => {
+                    // relative order of the remaining sources is preserved
+                    self.sources.remove(i);
+                    if self.next == self.sources.len() { self.next = 0 }
+                },
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sources.iter().fold((0, Some(0)), |(lo, hi), s| {
+            let (l, h) = s.size_hint();
+            let hi = match (hi, h) {
+                (Some(a), Some(b)) => a.checked_add(b),
+                _ => None,
+            };
+            (lo.saturating_add(l), hi)
+        })
+    }
+}
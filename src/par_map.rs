@@ -0,0 +1,133 @@
+use std::any::Any;
+use std::panic;
+use std::thread;
+
+use crate::sync::channel;
+
+use crate::{CancelHandle, CancelToken, Config, RunError};
+
+enum Stop<E> {
+    Error(E),
+    Disconnected,
+}
+
+pub(crate) type Payload = Box<dyn Any + Send>;
+
+/// Iterator over results of parallel computation which preserves order of
+/// elements.
+///
+/// This struct is created by the [`par_map`](fn.par_map.html) function.
+///
+/// Dropping the iterator stops the run and blocks until elements which
+/// are being processed by workers are finished and all threads have exited.
+pub struct ParMap<Y, E> {
+    rx: Option<channel::Receiver<Result<Y, E>>>,
+    handle: Option<thread::JoinHandle<Option<Payload>>>,
+    cancel: Option<CancelHandle>,
+}
+
+/// Run computation on a background thread and pass its results to `send`,
+/// which should return `false` if results are not needed anymore. If
+/// computation has failed, its error is passed as the last result.
+///
+/// Thread returns panic payload if one of callbacks has panicked. Unless
+/// cancellation token is configured, the run uses its own token, handle of
+/// which is returned for stopping the run without waiting for `send`.
+pub(crate) fn spawn<X, Y, E>(
+    config: &Config,
+    xs: impl IntoIterator<Item=X> + Send + 'static,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    mut send: impl FnMut(Result<Y, E>) -> bool + Send + 'static,
+) -> (thread::JoinHandle<Option<Payload>>, Option<CancelHandle>)
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    // cancelling the configured token would affect its other runs
+    let (config, cancel) = match config.cancel_token {
+        Some(_) => (config.clone(), None),
+        None => {
+            let (token, handle) = CancelToken::new();
+            (config.clone().cancel_token(token), Some(handle))
+        },
+    };
+    let handle = thread::spawn(move || {
+        let res = config.run_checked(
+            xs,
+            |x| f(x).map_err(Stop::Error),
+            |y| if send(Ok(y)) { Ok(()) } else { Err(Stop::Disconnected) },
+        );
+        match res {
+            Ok(_) | Err(RunError::Cancelled(_)) => None,
+            Err(RunError::TimedOut(_)) | Err(RunError::Report(_))
+            | Err(RunError::WorkersStuck(_)) => {
+                unreachable!()
+            },
+            Err(RunError::Callback(Stop::Disconnected)) => None,
+            Err(RunError::Callback(Stop::Error(err))) => {
+                send(Err(err));
+                None
+            },
+            Err(RunError::WorkerPanic(payload)) => Some(payload),
+            Err(RunError::ReportPanic(payload)) => Some(payload),
+            Err(RunError::SourcePanic(payload)) => Some(payload),
+            Err(RunError::Stalled(n)) => {
+                let msg = format!("run has stalled after reporting {} elements", n);
+                Some(Box::new(msg) as Payload)
+            },
+        }
+    });
+    (handle, cancel)
+}
+
+/// Stop the run and wait for its thread to exit, dropping the receiver
+/// makes `send` to return `false` in case a token is configured.
+pub(crate) fn stop<T>(
+    rx: &mut Option<channel::Receiver<T>>,
+    handle: &mut Option<thread::JoinHandle<Option<Payload>>>,
+    cancel: &Option<CancelHandle>,
+) {
+    if let Some(cancel) = cancel { cancel.cancel() }
+    *rx = None;
+    if let Some(handle) = handle.take() {
+        let _ = handle.join();
+    }
+}
+
+impl<Y: Send + 'static, E: Send + 'static> ParMap<Y, E> {
+    pub(crate) fn new<X: Send + 'static>(
+        config: &Config,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    ) -> Self {
+        let (tx, rx) = channel::bounded(config.get_report_capacity());
+        let send = move |v| tx.send(v).is_ok();
+        let (handle, cancel) = spawn(config, xs, f, send);
+        Self { rx: Some(rx), handle: Some(handle), cancel }
+    }
+}
+
+impl<Y, E> Iterator for ParMap<Y, E> {
+    type Item = Result<Y, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.rx.as_ref()?.recv();
+        match res {
+            Ok(val) => Some(val),
+            Err(_) => {
+                self.rx = None;
+                let handle = self.handle.take()?;
+                if let Some(payload) = handle.join().unwrap() {
+                    panic::resume_unwind(payload);
+                }
+                None
+            },
+        }
+    }
+}
+
+impl<Y, E> Drop for ParMap<Y, E> {
+    fn drop(&mut self) {
+        // workers skip remaining elements after cancellation, while feeder
+        // clears the input channel
+        stop(&mut self.rx, &mut self.handle, &self.cancel);
+    }
+}
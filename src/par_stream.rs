@@ -0,0 +1,108 @@
+use std::panic;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::sync::channel;
+use futures_core::Stream;
+
+use crate::{CancelHandle, Config};
+use crate::par_map::{spawn, stop, Payload};
+
+type SharedWaker = Arc<Mutex<Option<Waker>>>;
+
+fn wake(waker: &SharedWaker) {
+    if let Some(waker) = waker.lock().unwrap().take() {
+        waker.wake();
+    }
+}
+
+/// Wakes the stream after every sent result and after disconnection.
+struct Sender<T> {
+    tx: Option<channel::Sender<T>>,
+    waker: SharedWaker,
+}
+
+impl<T> Sender<T> {
+    fn send(&mut self, val: T) -> bool {
+        let res = self.tx.as_ref().map(|tx| tx.send(val).is_ok());
+        wake(&self.waker);
+        res.unwrap_or(false)
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // channel must be disconnected before waking up the stream
+        self.tx = None;
+        wake(&self.waker);
+    }
+}
+
+/// Stream over results of parallel computation which preserves order of
+/// elements.
+///
+/// This struct is created by the [`par_stream`](fn.par_stream.html) function.
+///
+/// Dropping the stream stops the run and blocks until elements which are
+/// being processed by workers are finished and all threads have exited.
+pub struct ParStream<Y, E> {
+    rx: Option<channel::Receiver<Result<Y, E>>>,
+    waker: SharedWaker,
+    handle: Option<thread::JoinHandle<Option<Payload>>>,
+    cancel: Option<CancelHandle>,
+}
+
+// stream is never pinned structurally
+impl<Y, E> Unpin for ParStream<Y, E> {}
+
+impl<Y: Send + 'static, E: Send + 'static> ParStream<Y, E> {
+    pub(crate) fn new<X: Send + 'static>(
+        config: &Config,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    ) -> Self {
+        let (tx, rx) = channel::bounded(config.get_report_capacity());
+        let waker = SharedWaker::default();
+        let mut sender = Sender { tx: Some(tx), waker: waker.clone() };
+        let (handle, cancel) = spawn(config, xs, f, move |v| sender.send(v));
+        Self { rx: Some(rx), waker, handle: Some(handle), cancel }
+    }
+}
+
+impl<Y, E> Drop for ParStream<Y, E> {
+    fn drop(&mut self) {
+        stop(&mut self.rx, &mut self.handle, &self.cancel);
+    }
+}
+
+impl<Y, E> Stream for ParStream<Y, E> {
+    type Item = Result<Y, E>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>, cx: &mut Context,
+    ) -> Poll<Option<Self::Item>> {
+        let rx = match &self.rx {
+            Some(rx) => rx,
+            None => return Poll::Ready(None),
+        };
+        // waker is registered before checking the channel, so result sent
+        // after the check will wake up the task
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+        match rx.try_recv() {
+            Ok(val) => Poll::Ready(Some(val)),
+            Err(channel::TryRecvError::Empty) => Poll::Pending,
+            Err(channel::TryRecvError::Disconnected) => {
+                self.rx = None;
+                if let Some(handle) = self.handle.take() {
+                    // thread has dropped sender, so it will exit shortly
+                    if let Some(payload) = handle.join().unwrap() {
+                        panic::resume_unwind(payload);
+                    }
+                }
+                Poll::Ready(None)
+            },
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct State {
+    paused: Mutex<bool>,
+    cvar: Condvar,
+}
+
+/// Handle which allows to temporarily stop sending elements to workers.
+///
+/// Handle is passed to a run using
+/// [`Config::pause_handle`](struct.Config.html#method.pause_handle). While
+/// the run is paused, feeder does not send new elements to workers, but
+/// elements which are already processed by workers are finished and
+/// reported as usual. Unlike cancellation no elements are dropped, the run
+/// just stalls until it's resumed.
+///
+/// # Examples
+/// ```
+/// use parstream::{Config, PauseHandle};
+///
+/// let pause = PauseHandle::new();
+/// pause.pause();
+/// let p = pause.clone();
+/// let t = std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_millis(10));
+///     p.resume();
+/// });
+/// let res: Result<usize, ()> = Config::new()
+///     .threads(4)
+///     .pause_handle(pause)
+///     .run(0..100u64, |x| Ok(x*x), |_| Ok(()));
+/// assert_eq!(res, Ok(100));
+/// t.join().unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PauseHandle {
+    state: Arc<State>,
+}
+
+impl PauseHandle {
+    /// Create new handle in the resumed state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pause runs which use this handle.
+    pub fn pause(&self) {
+        *self.state.paused.lock().unwrap() = true;
+    }
+
+    /// Resume runs which use this handle.
+    pub fn resume(&self) {
+        *self.state.paused.lock().unwrap() = false;
+        self.state.cvar.notify_all();
+    }
+
+    /// Check if handle is paused.
+    pub fn is_paused(&self) -> bool {
+        *self.state.paused.lock().unwrap()
+    }
+
+    /// Block while handle is paused or until `is_stopped` returns `true`.
+    pub(crate) fn wait(&self, is_stopped: impl Fn() -> bool) {
+        // run can be stopped without notification of the condition variable,
+        // so we periodically re-check it
+        const RECHECK: Duration = Duration::from_millis(10);
+        let mut paused = self.state.paused.lock().unwrap();
+        while *paused && !is_stopped() {
+            paused = self.state.cvar.wait_timeout(paused, RECHECK)
+                .unwrap().0;
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::cmp;
+use std::iter;
+use std::panic;
+
+use crate::sync::{self, channel};
+use crate::{Config, RunError};
+use crate::error::unchecked;
+
+/// Split `threads` between two stages, every stage gets at least one.
+fn split_threads(threads: usize) -> (usize, usize) {
+    let first = cmp::max(threads / 2, 1);
+    (first, cmp::max(threads - first, 1))
+}
+
+pub(crate) fn run<X: Send, M: Send, Y: Send, E: Send>(
+    config: &Config,
+    xs: impl IntoIterator<Item=X>,
+    stage1: impl Fn(X) -> Result<M, E> + Sync,
+    stage2: impl Fn(M) -> Result<Y, E> + Sync,
+    report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, E> {
+    let (first, second) = split_threads(config.get_threads());
+    let mut config1 = config.clone().threads(first);
+    // progress is measured by the second stage
+    config1.on_progress = None;
+    // status of a run is stored in the token, so it can not be shared by
+    // both stages, cancelled first stage ends the second one
+    let mut config2 = config.clone().threads(second);
+    config2.cancel_token = None;
+
+    let (tx, rx) = channel::bounded(config.get_input_capacity());
+    let mut res2 = None;
+    let res1 = sync::scope(|scope| {
+        let slot = &mut res2;
+        let stage2 = &stage2;
+        let name = config.thread_name_for(|p| format!("{}-stage2", p));
+        scope.spawn(name, config.stack_size, move || {
+            let ms = iter::from_fn(|| rx.recv().ok());
+            *slot = Some(config2.run_checked(ms, stage2, report));
+        }).expect("failed to spawn thread");
+        // report error means that the second stage has stopped
+        let res = config1.run_split_errors(xs, stage1, |m| {
+            tx.send(m).map_err(drop)
+        });
+        // the second stage ends after receiving all intermediate results
+        drop(tx);
+        res
+    });
+
+    // errors of the second stage belong to earlier elements, since elements
+    // after an error of the first stage never reach it
+    let n = unchecked(res2.expect("second stage has not run"))?;
+    match res1 {
+        Ok(_) | Err(RunError::Report(())) | Err(RunError::Cancelled(_)) => Ok(n),
+        Err(RunError::Callback(err)) => Err(err),
+        Err(RunError::WorkerPanic(payload))
+        | Err(RunError::ReportPanic(payload))
+        | Err(RunError::SourcePanic(payload)) => panic::resume_unwind(payload),
+        Err(RunError::TimedOut(_)) => {
+            unreachable!("unchecked runs do not use timeouts")
+        },
+        Err(RunError::Stalled(n)) => {
+            panic!("run has stalled after reporting {} elements", n)
+        },
+        Err(RunError::WorkersStuck(_)) => {
+            unreachable!("unchecked runs do not use join timeouts")
+        },
+    }
+}
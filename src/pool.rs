@@ -0,0 +1,115 @@
+use std::sync::Mutex;
+use std::thread;
+
+use crate::sync::channel;
+
+use crate::{Config, RunError};
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Pool of persistent threads which are reused by several runs.
+///
+/// Every run spawns `threads` workers and a report thread, so the pool
+/// keeps `threads + 1` threads alive. Runs on the same pool are executed
+/// one at a time, concurrent calls will wait for the current run to finish.
+///
+/// # Examples
+/// ```
+/// let pool = parstream::Pool::new(4);
+/// for _ in 0..10 {
+///     let mut ys = Vec::new();
+///     let res: Result<usize, ()> = pool.run(0..100u64,
+///         |x| Ok(x*x),
+///         |y| { ys.push(y); Ok(()) },
+///     );
+///     assert_eq!(res, Ok(100));
+///     assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Pool {
+    config: Config,
+    lock: Mutex<()>,
+    handles: Vec<thread::JoinHandle<()>>,
+}
+
+impl Pool {
+    /// Create new pool for runs with `threads` workers.
+    ///
+    /// If `threads` is equal to 0, number of workers will be equal to number
+    /// of available CPUs.
+    pub fn new(threads: usize) -> Self {
+        Self::with_config(Config::new().threads(threads))
+    }
+
+    /// Create new pool which will use `config` for all runs.
+    ///
+    /// Pool threads are named using
+    /// [`Config::thread_name`](struct.Config.html#method.thread_name)
+    /// prefix as `{prefix}-pool-{i}` and use
+    /// [`Config::stack_size`](struct.Config.html#method.stack_size).
+    /// Spawn hook of `config` is replaced by the pool.
+    pub fn with_config(config: Config) -> Self {
+        let threads = config.get_threads();
+        let (tx, rx) = channel::unbounded::<Task>();
+        let handles = (0..threads + 1).map(|i| {
+            let rx = rx.clone();
+            let mut builder = thread::Builder::new();
+            if let Some(name) = config.thread_name_for(|p| format!("{}-pool-{}", p, i)) {
+                builder = builder.name(name);
+            }
+            if let Some(size) = config.stack_size {
+                builder = builder.stack_size(size);
+            }
+            builder.spawn(move || {
+                for task in rx.iter() { task() }
+            }).expect("failed to spawn thread")
+        }).collect();
+        let config = config
+            .threads(threads)
+            .spawn_hook(move |task| tx.send(task).expect("pool is closed"));
+        Self { config, lock: Mutex::new(()), handles }
+    }
+
+    /// Number of workers used by runs.
+    pub fn threads(&self) -> usize {
+        self.config.threads
+    }
+
+    /// Run computation using pool threads.
+    ///
+    /// See [`run`](fn.run.html) for details.
+    pub fn run<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, E> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.config.run(xs, f, report)
+    }
+
+    /// Run computation using pool threads and return callback panics as
+    /// errors.
+    ///
+    /// See [`run_checked`](fn.run_checked.html) for details.
+    pub fn run_checked<X: Send, Y: Send, E: Send>(
+        &self,
+        xs: impl IntoIterator<Item=X>,
+        f: impl Fn(X) -> Result<Y, E> + Sync,
+        report: impl FnMut(Y) -> Result<(), E> + Send
+    ) -> Result<usize, RunError<E>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.config.run_checked(xs, f, report)
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        // dropping spawn hook disconnects tasks channel, so threads will exit
+        self.config.spawn_hook = None;
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
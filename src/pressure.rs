@@ -0,0 +1,48 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Hook which receives positions of dropped results.
+pub(crate) type ShedHook = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// Behavior of a run when the report closure does not keep up with
+/// workers, set using
+/// [`Config::report_pressure`](struct.Config.html#method.report_pressure).
+#[derive(Clone)]
+pub enum ReportPressure {
+    /// Workers block when the report channel is full (default).
+    Block,
+    /// Report channel is unbounded, so workers never wait for the report
+    /// thread, while their results may pile up in memory.
+    Spill,
+    /// Results which wait in the reorder buffer for a missing result are
+    /// dropped starting from the lowest position when more than
+    /// [`Config::report_capacity`](struct.Config.html#method.report_capacity)
+    /// results are buffered. Positions of dropped results are passed to
+    /// the hook. Errors are never dropped.
+    Shed(ShedHook),
+}
+
+impl ReportPressure {
+    /// Create `Shed` policy with the given hook.
+    pub fn shed(hook: impl Fn(usize) + Send + Sync + 'static) -> Self {
+        ReportPressure::Shed(Arc::new(hook))
+    }
+}
+
+// `#[default]` attribute requires Rust 1.62
+#[allow(clippy::derivable_impls)]
+impl Default for ReportPressure {
+    fn default() -> Self {
+        ReportPressure::Block
+    }
+}
+
+impl fmt::Debug for ReportPressure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportPressure::Block => f.write_str("Block"),
+            ReportPressure::Spill => f.write_str("Spill"),
+            ReportPressure::Shed(_) => f.write_str("Shed(..)"),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+struct Pending<P, X> {
+    priority: P,
+    pos: usize,
+    x: X,
+}
+
+impl<P: Ord, X> PartialEq for Pending<P, X> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<P: Ord, X> Eq for Pending<P, X> { }
+
+impl<P: Ord, X> Ord for Pending<P, X> {
+    // higher priority goes first, elements with equal priorities are
+    // ordered by their positions
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+            .then_with(|| other.pos.cmp(&self.pos))
+    }
+}
+
+impl<P: Ord, X> PartialOrd for Pending<P, X> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Iterator which buffers up to `lookahead` elements of `xs` and yields
+/// them together with their positions in the order of priorities.
+pub(crate) struct Prioritized<P, X, I> {
+    xs: I,
+    heap: BinaryHeap<Pending<P, X>>,
+    lookahead: usize,
+    pos: usize,
+}
+
+impl<P: Ord, X, I: Iterator<Item=(P, X)>> Prioritized<P, X, I> {
+    pub(crate) fn new(xs: I, lookahead: usize) -> Self {
+        Self { xs, heap: BinaryHeap::new(), lookahead, pos: 0 }
+    }
+}
+
+impl<P: Ord, X, I: Iterator<Item=(P, X)>> Iterator for Prioritized<P, X, I> {
+    type Item = (usize, X);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.heap.len() < self.lookahead {
+            let (priority, x) = match self.xs.next() {
+                Some(v) => v,
+                None => break,
+            };
+            self.heap.push(Pending { priority, pos: self.pos, x });
+            self.pos += 1;
+        }
+        self.heap.pop().map(|p| (p.pos, p.x))
+    }
+}
@@ -0,0 +1,57 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::{Config, RunError};
+use crate::error::unchecked;
+
+struct Slot<E> {
+    res: Option<Result<usize, RunError<E>>>,
+    waker: Option<Waker>,
+}
+
+/// Future which is completed after the run on a background thread finishes.
+pub(crate) struct RunFuture<E> {
+    slot: Arc<Mutex<Slot<E>>>,
+}
+
+impl<E: Send + 'static> RunFuture<E> {
+    pub(crate) fn new<X, Y>(
+        config: &Config,
+        xs: impl IntoIterator<Item=X> + Send + 'static,
+        f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+        report: impl FnMut(Y) -> Result<(), E> + Send + 'static,
+    ) -> Self
+        where X: Send + 'static, Y: Send + 'static
+    {
+        let config = config.clone();
+        let slot = Arc::new(Mutex::new(Slot { res: None, waker: None }));
+        let s = slot.clone();
+        thread::spawn(move || {
+            let res = config.run_checked(xs, f, report);
+            let mut slot = s.lock().unwrap();
+            slot.res = Some(res);
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        });
+        Self { slot }
+    }
+}
+
+impl<E> Future for RunFuture<E> {
+    type Output = Result<usize, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap();
+        match slot.res.take() {
+            Some(res) => Poll::Ready(unchecked(res)),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
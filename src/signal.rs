@@ -0,0 +1,53 @@
+//! `SIGINT` handler which cancels runs.
+use std::io;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicPtr, AtomicU8, Ordering};
+
+use crate::coord::{self, Status};
+
+/// Flag of the token returned by `CancelToken::ctrl_c`, it's never freed.
+static FLAG: AtomicPtr<AtomicU8> = AtomicPtr::new(ptr::null_mut());
+
+extern "C" fn on_sigint(_: libc::c_int) {
+    // only async-signal-safe operations are allowed here
+    let flag = FLAG.load(Ordering::Acquire);
+    if !flag.is_null() {
+        // SAFETY: pointer was created by `Arc::into_raw` and never released
+        coord::try_stop(unsafe { &*flag }, Status::Cancelled);
+    }
+    // SAFETY: `signal` is async-signal-safe
+    unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL); }
+}
+
+/// Get the shared flag and install the `SIGINT` handler.
+pub(crate) fn install() -> io::Result<Arc<AtomicU8>> {
+    let new = Arc::new(AtomicU8::new(Status::Running as u8));
+    let raw = Arc::into_raw(new) as *mut AtomicU8;
+    let flag = match FLAG.compare_exchange(
+        ptr::null_mut(), raw, Ordering::AcqRel, Ordering::Acquire,
+    ) {
+        Ok(_) => raw,
+        Err(cur) => {
+            // SAFETY: `raw` was not published, so we still own it
+            drop(unsafe { Arc::from_raw(raw) });
+            cur
+        },
+    };
+    // SAFETY: `flag` was created by `Arc::into_raw` and its reference
+    // stored in `FLAG` is never released
+    let flag = unsafe {
+        Arc::increment_strong_count(flag);
+        Arc::from_raw(flag)
+    };
+
+    let handler = on_sigint as extern "C" fn(libc::c_int);
+    // SAFETY: handler performs only async-signal-safe operations
+    let res = unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t)
+    };
+    if res == libc::SIG_ERR {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(flag)
+}
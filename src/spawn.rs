@@ -0,0 +1,128 @@
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::coord::Status;
+use crate::engine::Shared;
+use crate::executor::Executor;
+use crate::sync::Scope;
+
+/// Executor which is used for spawning threads instead of the scoped threads.
+pub(crate) type SpawnHook = Arc<dyn Executor>;
+
+/// Counter of tasks which were passed to the spawn hook, but were not
+/// finished yet.
+pub(crate) struct Latch {
+    count: Mutex<usize>,
+    cvar: Condvar,
+}
+
+impl Latch {
+    pub(crate) fn new() -> Self {
+        Self { count: Mutex::new(0), cvar: Condvar::new() }
+    }
+
+    fn add(&self) {
+        *self.count.lock().unwrap() += 1;
+    }
+
+    fn done(&self) {
+        let mut count = self.count.lock().unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.cvar.notify_all();
+        }
+    }
+
+    fn wait(&self) {
+        let mut count = self.count.lock().unwrap();
+        while *count != 0 {
+            count = self.cvar.wait(count).unwrap();
+        }
+    }
+}
+
+/// Waits on drop until all tasks passed to the spawn hook will finish.
+///
+/// Tasks borrow data from the stack of the run function, so it must not
+/// return or unwind until this guard is dropped.
+pub(crate) struct LatchGuard<'a>(pub(crate) &'a Latch);
+
+impl<'a> Drop for LatchGuard<'a> {
+    fn drop(&mut self) {
+        self.0.wait();
+    }
+}
+
+/// Decrements latch after the task body is dropped. If task was dropped
+/// without being run, the run is stopped with a worker panic.
+struct TaskGuard<'env> {
+    latch: &'env Latch,
+    shared: &'env Shared,
+    ran: bool,
+}
+
+impl<'env> Drop for TaskGuard<'env> {
+    fn drop(&mut self) {
+        if !self.ran {
+            let msg = "spawned task was dropped without being run";
+            self.shared.store_panic(Box::new(msg), Status::WorkerPanic);
+        }
+        self.latch.done();
+    }
+}
+
+/// Fields are dropped in order of declaration, so `body` is always dropped
+/// before `guard` decrements latch.
+struct Task<'env, F> {
+    body: F,
+    guard: TaskGuard<'env>,
+}
+
+/// Spawns threads either using scope or spawn hook.
+pub(crate) struct Spawner<'a, 'env> {
+    pub(crate) scope: Scope<'a, 'env>,
+    pub(crate) hook: Option<&'a SpawnHook>,
+    pub(crate) stack_size: Option<usize>,
+    pub(crate) latch: &'env Latch,
+    pub(crate) shared: &'env Shared,
+}
+
+impl<'a, 'env> Spawner<'a, 'env> {
+    /// Spawn thread with an optional name, names and stack size are ignored
+    /// by spawn hook.
+    pub(crate) fn spawn(
+        &self,
+        name: Option<String>,
+        f: impl FnOnce() + Send + 'env,
+    ) {
+        let hook = match self.hook {
+            Some(hook) => hook,
+            None => {
+                self.scope.spawn(name, self.stack_size, f)
+                    .expect("failed to spawn thread");
+                return;
+            },
+        };
+
+        self.latch.add();
+        let guard = TaskGuard {
+            latch: self.latch,
+            shared: self.shared,
+            ran: false,
+        };
+        let task = Task { body: f, guard };
+        let task: Box<dyn FnOnce() + Send + 'env> = Box::new(move || {
+            let Task { body, mut guard } = task;
+            guard.ran = true;
+            body();
+            drop(guard);
+        });
+        // SAFETY: task borrows only data which outlives `LatchGuard` created
+        // by the run function, which waits until the task is either finished
+        // or dropped, so the task can not outlive the borrowed data.
+        let task: Box<dyn FnOnce() + Send + 'static> = unsafe {
+            mem::transmute(task)
+        };
+        hook.execute(task);
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Size of the record header: position and length as little endian `u64`.
+const HEADER: usize = 16;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Temporary file which holds results spilled from the reorder buffer.
+///
+/// Every record consists of position of the result and length of its
+/// encoded bytes, both stored as little endian `u64`, followed by the bytes.
+/// Records are appended in order of spilling, the file is truncated after
+/// all records were read back and removed on drop.
+pub(crate) struct SpillFile {
+    // created on the first write
+    file: Option<(File, PathBuf)>,
+    len: u64,
+    // offsets and lengths of encoded results by their positions
+    index: BTreeMap<usize, (u64, usize)>,
+}
+
+impl SpillFile {
+    pub(crate) fn new() -> Self {
+        Self { file: None, len: 0, index: BTreeMap::new() }
+    }
+
+    fn file(&mut self) -> io::Result<&mut File> {
+        if self.file.is_none() {
+            let name = format!(
+                "parstream-{}-{}.spill",
+                process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+            );
+            let path = env::temp_dir().join(name);
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&path)?;
+            self.file = Some((file, path));
+        }
+        match &mut self.file {
+            Some((file, _)) => Ok(file),
+            None => unreachable!(),
+        }
+    }
+
+    /// Append encoded result with position `pos`.
+    pub(crate) fn write(
+        &mut self, pos: usize, bytes: &[u8],
+    ) -> io::Result<()> {
+        let mut header = [0; HEADER];
+        header[..8].copy_from_slice(&(pos as u64).to_le_bytes());
+        header[8..].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        let offset = self.len;
+        let file = self.file()?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&header)?;
+        file.write_all(bytes)?;
+        self.len += (HEADER + bytes.len()) as u64;
+        self.index.insert(pos, (offset + HEADER as u64, bytes.len()));
+        Ok(())
+    }
+
+    /// Read encoded result with position `pos` into `buf`, returns `false`
+    /// if it was not spilled.
+    pub(crate) fn read(
+        &mut self, pos: usize, buf: &mut Vec<u8>,
+    ) -> io::Result<bool> {
+        let (offset, len) = match self.index.remove(&pos) {
+            Some(v) => v,
+            None => return Ok(false),
+        };
+        let empty = self.index.is_empty();
+        let file = self.file()?;
+        buf.resize(len, 0);
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)?;
+        if empty {
+            file.set_len(0)?;
+            self.len = 0;
+        }
+        Ok(true)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if let Some((file, path)) = self.file.take() {
+            drop(file);
+            // the file is temporary, so failure to remove it is ignored
+            let _ = fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Number of sub-buckets in every power of two range of the histogram.
+const SUB_BUCKETS: usize = 8;
+const SUB_BITS: u32 = 3;
+/// Number of buckets covering all `u64` values.
+const BUCKETS: usize = SUB_BUCKETS * (64 - SUB_BITS as usize + 1);
+
+/// Index of the bucket which holds `v`.
+fn bucket(v: u64) -> usize {
+    if v < SUB_BUCKETS as u64 { return v as usize }
+    let k = 63 - v.leading_zeros();
+    let sub = (v >> (k - SUB_BITS)) as usize & (SUB_BUCKETS - 1);
+    SUB_BUCKETS * (k - SUB_BITS + 1) as usize + sub
+}
+
+/// The highest value which is held by the bucket `i`.
+fn bucket_max(i: usize) -> u64 {
+    if i < SUB_BUCKETS { return i as u64 }
+    let shift = (i / SUB_BUCKETS - 1) as u32;
+    let lower = ((SUB_BUCKETS + i % SUB_BUCKETS) as u64) << shift;
+    lower + ((1u64 << shift) - 1)
+}
+
+/// Histogram of task durations returned in
+/// [`RunStats::task_durations`](struct.RunStats.html#structfield.task_durations).
+///
+/// Durations are recorded with nanosecond resolution into buckets with
+/// relative width of 1/8, so percentiles are accurate up to 12.5%.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct DurationHistogram {
+    // empty if no durations were recorded
+    buckets: Vec<u64>,
+    count: u64,
+    max: u64,
+}
+
+impl DurationHistogram {
+    /// Number of recorded durations.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The longest recorded duration, zero if histogram is empty.
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max)
+    }
+
+    /// Duration which is not exceeded by fraction `q` of recorded
+    /// durations, zero if histogram is empty.
+    ///
+    /// # Panics
+    /// If `q` is not in the `0.0..=1.0` range.
+    pub fn percentile(&self, q: f64) -> Duration {
+        assert!((0.0..=1.0).contains(&q), "percentile must be in 0.0..=1.0");
+        let rank = ((q * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Duration::from_nanos(bucket_max(i).min(self.max));
+            }
+        }
+        Duration::from_nanos(self.max)
+    }
+
+    /// Median of recorded durations.
+    pub fn p50(&self) -> Duration {
+        self.percentile(0.5)
+    }
+
+    /// 99th percentile of recorded durations.
+    pub fn p99(&self) -> Duration {
+        self.percentile(0.99)
+    }
+}
+
+impl fmt::Debug for DurationHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DurationHistogram")
+            .field("count", &self.count)
+            .field("p50", &self.p50())
+            .field("p99", &self.p99())
+            .field("max", &self.max())
+            .finish()
+    }
+}
+
+/// Statistics of a run returned by
+/// [`run_with_stats`](fn.run_with_stats.html).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RunStats {
+    /// Maximum number of results which were simultaneously held in the
+    /// reorder buffer.
+    pub peak_buffered: usize,
+    /// Total number of results which were received out of order and had to
+    /// be buffered.
+    pub total_buffered: usize,
+    /// Number of elements processed by each worker.
+    pub worker_items: Vec<usize>,
+    /// Wall time of the run.
+    pub elapsed: Duration,
+    /// Durations of `f` calls for all processed elements.
+    pub task_durations: DurationHistogram,
+}
+
+/// Counters which are updated during a run with enabled statistics.
+#[derive(Debug)]
+pub(crate) struct Counters {
+    peak_buffered: AtomicUsize,
+    total_buffered: AtomicUsize,
+    worker_items: Vec<AtomicUsize>,
+    durations: Vec<AtomicU64>,
+    max_duration: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn new(workers: usize) -> Self {
+        Self {
+            peak_buffered: AtomicUsize::new(0),
+            total_buffered: AtomicUsize::new(0),
+            worker_items: (0..workers).map(|_| AtomicUsize::new(0)).collect(),
+            durations: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            max_duration: AtomicU64::new(0),
+        }
+    }
+
+    /// Record that a result was buffered, `len` is the new size of the
+    /// reorder buffer.
+    pub(crate) fn buffered(&self, len: usize) {
+        self.total_buffered.fetch_add(1, Ordering::Relaxed);
+        self.peak_buffered.fetch_max(len, Ordering::Relaxed);
+    }
+
+    /// Record that worker `w` has processed an element in `elapsed` time.
+    pub(crate) fn processed(&self, w: usize, elapsed: Duration) {
+        self.worker_items[w].fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.durations[bucket(nanos)].fetch_add(1, Ordering::Relaxed);
+        self.max_duration.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn into_stats(self, elapsed: Duration) -> RunStats {
+        let buckets: Vec<u64> = self.durations.into_iter()
+            .map(AtomicU64::into_inner)
+            .collect();
+        let count = buckets.iter().sum();
+        let task_durations = DurationHistogram {
+            buckets: if count == 0 { Vec::new() } else { buckets },
+            count,
+            max: self.max_duration.into_inner(),
+        };
+        RunStats {
+            peak_buffered: self.peak_buffered.into_inner(),
+            total_buffered: self.total_buffered.into_inner(),
+            worker_items: self.worker_items.into_iter()
+                .map(AtomicUsize::into_inner)
+                .collect(),
+            elapsed,
+            task_durations,
+        }
+    }
+}
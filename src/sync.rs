@@ -0,0 +1,333 @@
+//! Channels and scoped threads used by the crate.
+//!
+//! By default they are provided by `crossbeam-channel` and `crossbeam-utils`,
+//! with disabled `crossbeam` feature they are implemented using only `std`.
+use std::io;
+
+#[cfg(feature = "crossbeam")]
+pub(crate) use crossbeam_channel as channel;
+
+#[cfg(feature = "crossbeam")]
+use crossbeam_utils::thread as cb_thread;
+
+/// Scope for spawning threads which may borrow data with lifetime `'env`.
+#[cfg(feature = "crossbeam")]
+#[derive(Clone, Copy)]
+pub(crate) struct Scope<'s, 'env>(&'s cb_thread::Scope<'env>);
+
+/// Scope for spawning threads which may borrow data with lifetime `'env`.
+#[cfg(not(feature = "crossbeam"))]
+#[derive(Clone, Copy)]
+pub(crate) struct Scope<'s, 'env>(&'s std::thread::Scope<'s, 'env>);
+
+/// Run `f` with a new scope, all threads spawned in it are joined before
+/// return. Panics if one of the spawned threads has panicked.
+pub(crate) fn scope<'env, R>(
+    f: impl for<'s> FnOnce(Scope<'s, 'env>) -> R,
+) -> R {
+    #[cfg(feature = "crossbeam")]
+    {
+        cb_thread::scope(|s| f(Scope(s))).unwrap()
+    }
+    #[cfg(not(feature = "crossbeam"))]
+    {
+        std::thread::scope(|s| f(Scope(s)))
+    }
+}
+
+impl<'s, 'env> Scope<'s, 'env> {
+    /// Spawn thread with an optional name and stack size.
+    pub(crate) fn spawn(
+        self,
+        name: Option<String>,
+        stack_size: Option<usize>,
+        f: impl FnOnce() + Send + 'env,
+    ) -> io::Result<()> {
+        #[cfg(feature = "crossbeam")]
+        let mut builder = self.0.builder();
+        #[cfg(not(feature = "crossbeam"))]
+        let mut builder = std::thread::Builder::new();
+        if let Some(name) = name {
+            builder = builder.name(name);
+        }
+        if let Some(size) = stack_size {
+            builder = builder.stack_size(size);
+        }
+        #[cfg(feature = "crossbeam")]
+        let res = builder.spawn(move |_| f());
+        #[cfg(not(feature = "crossbeam"))]
+        let res = builder.spawn_scoped(self.0, f);
+        res.map(drop)
+    }
+}
+
+/// Multi-producer multi-consumer channel with a subset of
+/// `crossbeam-channel` API built on top of `Mutex` and `Condvar`.
+#[cfg(not(feature = "crossbeam"))]
+pub(crate) mod channel {
+    use std::cmp;
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::time::{Duration, Instant};
+
+    struct State<T> {
+        queue: VecDeque<T>,
+        cap: usize,
+        senders: usize,
+        receivers: usize,
+        // numbers of values pushed into and taken from the queue, used for
+        // hand-off in zero capacity channels
+        pushed: u64,
+        taken: u64,
+    }
+
+    struct Chan<T> {
+        state: Mutex<State<T>>,
+        not_empty: Condvar,
+        not_full: Condvar,
+    }
+
+    impl<T> Chan<T> {
+        fn lock(&self) -> MutexGuard<'_, State<T>> {
+            // no user code runs while the lock is held
+            self.state.lock().unwrap()
+        }
+
+        /// Wait for `not_full` notification, returns `true` together with
+        /// the guard if `deadline` has passed.
+        fn wait_not_full<'a>(
+            &self, state: MutexGuard<'a, State<T>>, deadline: Option<Instant>,
+        ) -> (MutexGuard<'a, State<T>>, bool) {
+            match deadline {
+                None => (self.not_full.wait(state).unwrap(), false),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline { return (state, true) }
+                    let res = self.not_full.wait_timeout(state, deadline - now);
+                    (res.unwrap().0, false)
+                },
+            }
+        }
+    }
+
+    pub(crate) struct Sender<T>(Arc<Chan<T>>);
+
+    pub(crate) struct Receiver<T>(Arc<Chan<T>>);
+
+    pub(crate) struct SendError<T>(pub T);
+
+    impl<T> fmt::Debug for SendError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("SendError(..)")
+        }
+    }
+
+    pub(crate) enum SendTimeoutError<T> {
+        Timeout(T),
+        Disconnected(T),
+    }
+
+    #[derive(Debug)]
+    pub(crate) struct RecvError;
+
+    pub(crate) enum RecvTimeoutError {
+        Timeout,
+        Disconnected,
+    }
+
+    pub(crate) enum TryRecvError {
+        Empty,
+        Disconnected,
+    }
+
+    fn new<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+        let chan = Arc::new(Chan {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                cap,
+                senders: 1,
+                receivers: 1,
+                pushed: 0,
+                taken: 0,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        });
+        (Sender(chan.clone()), Receiver(chan))
+    }
+
+    /// Create channel with capacity `cap`. Similarly to `crossbeam-channel`,
+    /// send to a zero capacity channel blocks until a receiver takes
+    /// the value.
+    pub(crate) fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+        new(cap)
+    }
+
+    pub(crate) fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+        new(usize::MAX)
+    }
+
+    /// Remove value which was not taken from a zero capacity channel, so
+    /// it can be returned to the sender.
+    fn withdraw<T>(chan: &Chan<T>, state: &mut State<T>) -> T {
+        let val = state.queue.pop_back().expect("value is in the queue");
+        // the value is accounted as taken, so tickets of next values match
+        state.taken += 1;
+        chan.not_full.notify_all();
+        val
+    }
+
+    impl<T> Sender<T> {
+        pub(crate) fn send(&self, val: T) -> Result<(), SendError<T>> {
+            match self.send_until(val, None) {
+                Ok(()) => Ok(()),
+                Err(SendTimeoutError::Disconnected(val)) => Err(SendError(val)),
+                Err(SendTimeoutError::Timeout(_)) => unreachable!(),
+            }
+        }
+
+        pub(crate) fn send_timeout(&self, val: T, timeout: Duration)
+            -> Result<(), SendTimeoutError<T>>
+        {
+            self.send_until(val, Some(Instant::now() + timeout))
+        }
+
+        fn send_until(&self, val: T, deadline: Option<Instant>)
+            -> Result<(), SendTimeoutError<T>>
+        {
+            let chan = &*self.0;
+            let mut state = chan.lock();
+            // zero capacity channel holds a single value until a receiver
+            // takes it
+            let cap = cmp::max(state.cap, 1);
+            loop {
+                if state.receivers == 0 {
+                    return Err(SendTimeoutError::Disconnected(val));
+                }
+                if state.queue.len() < cap { break }
+                let (guard, timed_out) = chan.wait_not_full(state, deadline);
+                if timed_out { return Err(SendTimeoutError::Timeout(val)) }
+                state = guard;
+            }
+            state.queue.push_back(val);
+            state.pushed += 1;
+            chan.not_empty.notify_one();
+            if state.cap != 0 { return Ok(()) }
+
+            // values are taken in order, so until the value is taken it's
+            // the only one in the queue
+            let ticket = state.pushed;
+            loop {
+                if state.taken >= ticket { return Ok(()) }
+                if state.receivers == 0 {
+                    let val = withdraw(chan, &mut state);
+                    return Err(SendTimeoutError::Disconnected(val));
+                }
+                let (guard, timed_out) = chan.wait_not_full(state, deadline);
+                state = guard;
+                if timed_out && state.taken < ticket {
+                    let val = withdraw(chan, &mut state);
+                    return Err(SendTimeoutError::Timeout(val));
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        pub(crate) fn len(&self) -> usize {
+            self.0.lock().queue.len()
+        }
+
+        pub(crate) fn is_empty(&self) -> bool {
+            self.0.lock().queue.is_empty()
+        }
+    }
+
+    impl<T> Clone for Sender<T> {
+        fn clone(&self) -> Self {
+            self.0.lock().senders += 1;
+            Sender(self.0.clone())
+        }
+    }
+
+    impl<T> Drop for Sender<T> {
+        fn drop(&mut self) {
+            let mut state = self.0.lock();
+            state.senders -= 1;
+            if state.senders == 0 {
+                self.0.not_empty.notify_all();
+            }
+        }
+    }
+
+    impl<T> Receiver<T> {
+        fn take(&self, state: &mut State<T>) -> Option<T> {
+            let val = state.queue.pop_front()?;
+            state.taken += 1;
+            if state.cap == 0 {
+                // wake the sender waiting for hand-off among other senders
+                self.0.not_full.notify_all();
+            } else {
+                self.0.not_full.notify_one();
+            }
+            Some(val)
+        }
+
+        pub(crate) fn recv(&self) -> Result<T, RecvError> {
+            let mut state = self.0.lock();
+            loop {
+                if let Some(val) = self.take(&mut state) { return Ok(val); }
+                if state.senders == 0 { return Err(RecvError); }
+                state = self.0.not_empty.wait(state).unwrap();
+            }
+        }
+
+        pub(crate) fn recv_timeout(&self, timeout: Duration)
+            -> Result<T, RecvTimeoutError>
+        {
+            let deadline = Instant::now() + timeout;
+            let mut state = self.0.lock();
+            loop {
+                if let Some(val) = self.take(&mut state) { return Ok(val); }
+                if state.senders == 0 {
+                    return Err(RecvTimeoutError::Disconnected);
+                }
+                let now = Instant::now();
+                if now >= deadline { return Err(RecvTimeoutError::Timeout); }
+                state = self.0.not_empty.wait_timeout(state, deadline - now)
+                    .unwrap().0;
+            }
+        }
+
+        pub(crate) fn try_recv(&self) -> Result<T, TryRecvError> {
+            let mut state = self.0.lock();
+            match self.take(&mut state) {
+                Some(val) => Ok(val),
+                None if state.senders == 0 => Err(TryRecvError::Disconnected),
+                None => Err(TryRecvError::Empty),
+            }
+        }
+
+        /// Blocking iterator which ends after all senders are dropped.
+        pub(crate) fn iter(&self) -> impl Iterator<Item=T> + '_ {
+            std::iter::from_fn(move || self.recv().ok())
+        }
+    }
+
+    impl<T> Clone for Receiver<T> {
+        fn clone(&self) -> Self {
+            self.0.lock().receivers += 1;
+            Receiver(self.0.clone())
+        }
+    }
+
+    impl<T> Drop for Receiver<T> {
+        fn drop(&mut self) {
+            let mut state = self.0.lock();
+            state.receivers -= 1;
+            if state.receivers == 0 {
+                self.0.not_full.notify_all();
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::any::Any;
+use std::panic;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::sync::channel;
+
+use crate::{Config, RunError};
+
+type Payload = Box<dyn Any + Send>;
+
+enum TaskError<E> {
+    Callback(E),
+    TimedOut(usize),
+}
+
+/// Thread which computes `f(x)` on behalf of a worker.
+struct Helper<X, Y, E> {
+    tx: channel::Sender<X>,
+    rx: channel::Receiver<Result<Result<Y, E>, Payload>>,
+}
+
+impl<X: Send + 'static, Y: Send + 'static, E: Send + 'static> Helper<X, Y, E> {
+    fn spawn(f: Arc<impl Fn(X) -> Result<Y, E> + Send + Sync + 'static>) -> Self {
+        let (tx, helper_rx) = channel::bounded::<X>(1);
+        let (helper_tx, rx) = channel::bounded(1);
+        thread::spawn(move || {
+            for x in helper_rx.iter() {
+                let fp = &f;
+                let res = panic::catch_unwind(panic::AssertUnwindSafe(|| fp(x)));
+                if helper_tx.send(res).is_err() { break }
+            }
+        });
+        Self { tx, rx }
+    }
+}
+
+pub(crate) fn run<X, Y, E>(
+    config: &Config,
+    xs: impl IntoIterator<Item=X>,
+    timeout: Duration,
+    f: impl Fn(X) -> Result<Y, E> + Send + Sync + 'static,
+    mut report: impl FnMut(Y) -> Result<(), E> + Send,
+) -> Result<usize, RunError<E>>
+    where X: Send + 'static, Y: Send + 'static, E: Send + 'static
+{
+    let f = Arc::new(f);
+    // idle helper threads, helpers which have timed out are abandoned
+    let helpers: Mutex<Vec<Helper<X, Y, E>>> = Mutex::new(Vec::new());
+
+    let res = config.run_checked(
+        xs.into_iter().enumerate(),
+        |(i, x)| {
+            let helper = helpers.lock().unwrap().pop();
+            let helper = helper.unwrap_or_else(|| Helper::spawn(f.clone()));
+            helper.tx.send(x).expect("helper thread has exited");
+            match helper.rx.recv_timeout(timeout) {
+                Ok(Ok(res)) => {
+                    helpers.lock().unwrap().push(helper);
+                    res.map_err(TaskError::Callback)
+                },
+                Ok(Err(payload)) => panic::resume_unwind(payload),
+                Err(_) => Err(TaskError::TimedOut(i)),
+            }
+        },
+        |y| report(y).map_err(TaskError::Callback),
+    );
+
+    res.map_err(|err| match err {
+        RunError::Callback(TaskError::Callback(err)) => RunError::Callback(err),
+        RunError::Callback(TaskError::TimedOut(i)) => RunError::TimedOut(i),
+        RunError::WorkerPanic(payload) => RunError::WorkerPanic(payload),
+        RunError::ReportPanic(payload) => RunError::ReportPanic(payload),
+        RunError::SourcePanic(payload) => RunError::SourcePanic(payload),
+        RunError::Cancelled(n) => RunError::Cancelled(n),
+        RunError::TimedOut(i) => RunError::TimedOut(i),
+        RunError::Stalled(n) => RunError::Stalled(n),
+        RunError::WorkersStuck(n) => RunError::WorkersStuck(n),
+        RunError::Report(_) => unreachable!(),
+    })
+}
@@ -0,0 +1,18 @@
+//! Macros which emit `tracing` spans and events if `tracing` feature is
+//! enabled and compile to nothing otherwise.
+
+/// Enter span which lasts until the end of the current block.
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($($arg)*).entered();
+    };
+}
+
+/// Emit trace event.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
@@ -0,0 +1,71 @@
+use parstream::{ActiveWorkers, Config, RunError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn counts_busy_workers() {
+    let active = ActiveWorkers::new();
+    let done = Arc::new(AtomicBool::new(false));
+    let (a, d) = (active.clone(), done.clone());
+    let t = thread::spawn(move || {
+        let res: Result<usize, ()> = Config::new()
+            .threads(4)
+            .active_workers(a)
+            .run(0..4u64, |x| {
+                thread::sleep(Duration::from_millis(300));
+                Ok(x)
+            }, |_| Ok(()));
+        d.store(true, Ordering::Release);
+        res
+    });
+    let mut peak = 0;
+    while !done.load(Ordering::Acquire) {
+        peak = peak.max(active.count());
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(t.join().unwrap(), Ok(4));
+    assert!(peak >= 2, "peak: {}", peak);
+    assert!(peak <= 4, "peak: {}", peak);
+    assert_eq!(active.count(), 0);
+}
+
+#[test]
+fn idle_workers_are_not_counted() {
+    let active = ActiveWorkers::new();
+    let a = active.clone();
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .active_workers(active.clone())
+        .run(0..20u64, Ok, |_| {
+            // workers wait for the slow report closure
+            thread::sleep(Duration::from_millis(5));
+            assert!(a.count() <= 4);
+            Ok(())
+        });
+    assert_eq!(res, Ok(20));
+    assert_eq!(active.count(), 0);
+}
+
+#[test]
+fn counter_is_reset_after_errors_and_panics() {
+    let active = ActiveWorkers::new();
+    let res = Config::new()
+        .threads(4)
+        .active_workers(active.clone())
+        .run_checked(0..100u64, |x| if x == 50 { Err(x) } else { Ok(x) },
+            |_| Ok(()));
+    assert!(matches!(res, Err(RunError::Callback(50))));
+    assert_eq!(active.count(), 0);
+
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(4)
+        .active_workers(active.clone())
+        .run_checked(0..100u64, |x| {
+            if x == 50 { panic!("boom") }
+            Ok(x)
+        }, |_| Ok(()));
+    assert!(matches!(res, Err(RunError::WorkerPanic(_))));
+    assert_eq!(active.count(), 0);
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use parstream::Config;
+
+fn worker_index() -> usize {
+    let name = thread::current().name().unwrap().to_owned();
+    name.rsplit('-').next().unwrap().parse().unwrap()
+}
+
+#[test]
+fn workers_are_activated_under_load() {
+    let used = Mutex::new(HashSet::new());
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .thread_name("adaptive")
+        .run_adaptive(0..200u64, 1, 4,
+            |x| {
+                used.lock().unwrap().insert(worker_index());
+                thread::sleep(Duration::from_millis(2));
+                Ok(x)
+            },
+            |y| { ys.push(y); Ok(()) },
+        );
+    assert_eq!(res, Ok(200));
+    assert_eq!(ys, (0..200).collect::<Vec<_>>());
+    assert!(used.into_inner().unwrap().len() > 1);
+}
+
+#[test]
+fn slow_input_keeps_min_workers() {
+    let used = Mutex::new(HashSet::new());
+    let xs = (0..20u64).inspect(|_| thread::sleep(Duration::from_millis(5)));
+    let res: Result<usize, ()> = Config::new()
+        .thread_name("adaptive")
+        .run_adaptive(xs, 1, 4,
+            |x| {
+                // all workers are activated after the last element was sent
+                if x != 19 { used.lock().unwrap().insert(worker_index()); }
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(20));
+    assert_eq!(used.into_inner().unwrap(), [0].iter().copied().collect());
+}
+
+#[test]
+fn adaptive_error() {
+    let res = parstream::run_adaptive(0..100u64, 2, 4,
+        |x| if x == 30 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(30));
+}
+
+#[test]
+#[should_panic(expected = "invalid range of worker counts")]
+fn invalid_range_panics() {
+    let _ = parstream::run_adaptive(0..10u64, 3, 2, Ok::<u64, ()>, |_| Ok(()));
+}
@@ -0,0 +1,55 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use parstream::Config;
+
+#[test]
+fn callbacks_borrow_locals() {
+    let data: Vec<String> = (0..100).map(|i| format!("item-{}", i)).collect();
+    let prefix = String::from("item-");
+    let mut out = Vec::new();
+    let res: Result<usize, ()> = parstream::run(data.iter(), 4,
+        |s| Ok(s[prefix.len()..].parse::<usize>().unwrap()),
+        |n| { out.push(n); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(out, (0..100).collect::<Vec<_>>());
+    // all borrows have ended
+    drop(data);
+}
+
+#[test]
+fn results_borrow_locals() {
+    let data: Vec<Vec<u64>> = (0..50).map(|i| vec![i; 10]).collect();
+    let mut total = 0;
+    let res: Result<usize, ()> = Config::new().threads(2).run(&data,
+        |v| Ok(&v[..5]),
+        |s: &[u64]| { total += s.iter().sum::<u64>(); Ok(()) },
+    );
+    assert_eq!(res, Ok(50));
+    assert_eq!(total, (0..50).map(|i| i * 5).sum());
+}
+
+#[test]
+fn state_borrows_locals() {
+    let base = vec![10u64, 20, 30];
+    let mut out = Vec::new();
+    let res: Result<usize, ()> = parstream::run_with_init(0..3usize, 3,
+        || &base,
+        |base, i| Ok(base[i] + 1),
+        |y| { out.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(3));
+    assert_eq!(out, vec![11, 21, 31]);
+}
+
+#[test]
+fn inline_report_does_not_require_send() {
+    let out = Rc::new(RefCell::new(Vec::new()));
+    let res: Result<usize, ()> = parstream::run_inline_report(0..10u64, 2,
+        |x| Ok(x * 2),
+        |y| { out.borrow_mut().push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(10));
+    assert_eq!(*out.borrow(), (0..10).map(|x| x * 2).collect::<Vec<_>>());
+}
@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn buffered_bytes_are_bounded() {
+    const SIZE: usize = 1000;
+    let started = AtomicUsize::new(0);
+    let reported = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let mut ys = Vec::new();
+
+    let res: Result<usize, ()> = parstream::run_with_buffer_bytes(
+        0..1000usize, 4, 10 * SIZE,
+        |y: &Vec<u8>| y.len(),
+        |x| {
+            let s = started.fetch_add(1, Ordering::SeqCst) + 1;
+            let in_flight = s - reported.load(Ordering::SeqCst);
+            peak.fetch_max(in_flight, Ordering::SeqCst);
+            if x == 0 { thread::sleep(Duration::from_millis(200)); }
+            Ok(vec![(x % 256) as u8; SIZE])
+        },
+        |y| {
+            reported.fetch_add(1, Ordering::SeqCst);
+            ys.push(y[0]);
+            Ok(())
+        },
+    );
+
+    assert_eq!(res, Ok(1000));
+    assert_eq!(ys, (0..1000).map(|x| (x % 256) as u8).collect::<Vec<_>>());
+    // 11 buffered results, plus elements in channels and workers
+    assert!(peak.load(Ordering::SeqCst) <= 11 + 8 + 8 + 4 + 1);
+}
+
+#[test]
+fn zero_sized_results_are_not_limited() {
+    let res: Result<usize, ()> = parstream::run_with_buffer_bytes(
+        0..1000u64, 4, 0, |_: &u64| 0,
+        |x| {
+            if x == 0 { thread::sleep(Duration::from_millis(50)); }
+            Ok(x)
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Ok(1000));
+}
+
+#[test]
+fn error_while_limited() {
+    let res = parstream::run_with_buffer_bytes(
+        0..1000u64, 4, 10, |_: &u64| 100,
+        |x| {
+            if x == 0 { thread::sleep(Duration::from_millis(50)); }
+            if x == 0 { Err(x) } else { Ok(x) }
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(0));
+}
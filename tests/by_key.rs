@@ -0,0 +1,100 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn shuffled_keys() {
+    // deterministic permutation of 0..1000
+    let xs: Vec<(usize, u64)> = (0..1000)
+        .map(|i| ((i * 7919) % 1000, i as u64))
+        .collect();
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_by_key(xs.clone(), 8,
+        |x| {
+            if x % 10 == 0 { thread::sleep(Duration::from_micros(100)); }
+            Ok(x)
+        },
+        |key, y| { ys.push((key, y)); Ok(()) },
+    );
+    assert_eq!(res, Ok(1000));
+    let mut expected = xs;
+    expected.sort();
+    assert_eq!(ys, expected);
+}
+
+#[test]
+fn gaps_are_reported_at_the_end() {
+    let xs = vec![(5, 5u64), (0, 0), (3, 3), (1, 1), (7, 7)];
+    let mut keys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_by_key(xs, 4, Ok,
+        |key, _| { keys.push(key); Ok(()) });
+    assert_eq!(res, Ok(5));
+    assert_eq!(keys, vec![0, 1, 3, 5, 7]);
+}
+
+#[test]
+fn duplicate_keys_are_reported() {
+    // all duplicates are buffered until the last element is processed
+    let mut xs: Vec<(usize, u64)> = (0..20).map(|i| (1, i)).collect();
+    xs.push((0, 20));
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_by_key(xs, 1, Ok,
+        |key, y| { ys.push((key, y)); Ok(()) });
+    assert_eq!(res, Ok(21));
+    let mut expected = vec![(0, 20)];
+    expected.extend((0..20).map(|i| (1, i)));
+    assert_eq!(ys, expected);
+}
+
+#[test]
+fn gapped_late_key_is_dropped() {
+    let xs = vec![(0, 0u64), (1, 1), (2, 2), (3, 3)];
+    let mut gaps = Vec::new();
+    let mut keys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_by_key_gapped(xs, 4,
+        Duration::from_millis(20),
+        |key| gaps.push(key),
+        |x| {
+            if x == 1 { thread::sleep(Duration::from_millis(300)); }
+            Ok(x)
+        },
+        |key, _| { keys.push(key); Ok(()) },
+    );
+    assert_eq!(res, Ok(4));
+    assert_eq!(gaps, vec![1]);
+    assert_eq!(keys, vec![0, 2, 3]);
+}
+
+#[test]
+fn gapped_slow_key_within_timeout() {
+    let xs: Vec<(usize, u64)> = (0..20).map(|i| (i, i as u64)).collect();
+    let mut gaps = Vec::new();
+    let mut keys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_by_key_gapped(xs, 4,
+        Duration::from_secs(10),
+        |key| gaps.push(key),
+        |x| {
+            if x == 3 { thread::sleep(Duration::from_millis(20)); }
+            Ok(x)
+        },
+        |key, _| { keys.push(key); Ok(()) },
+    );
+    assert_eq!(res, Ok(20));
+    assert!(gaps.is_empty());
+    assert_eq!(keys, (0..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn gapped_missing_keys_at_the_end() {
+    let xs = vec![(5, 5u64), (0, 0), (3, 3), (2, 2)];
+    let mut gaps = Vec::new();
+    let mut keys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_by_key_gapped(xs, 4,
+        Duration::from_secs(10),
+        |key| gaps.push(key),
+        Ok,
+        |key, _| { keys.push(key); Ok(()) },
+    );
+    assert_eq!(res, Ok(4));
+    assert_eq!(gaps, vec![1, 4]);
+    assert_eq!(keys, vec![0, 2, 3, 5]);
+}
@@ -0,0 +1,59 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::{CancelToken, Config, RunError};
+
+#[test]
+fn cancel_from_another_thread() {
+    let (token, handle) = CancelToken::new();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.cancel();
+    });
+    let t = Instant::now();
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(4)
+        .cancel_token(token.clone())
+        .run_checked(0..1_000_000u64,
+            |x| {
+                thread::sleep(Duration::from_millis(1));
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    canceller.join().unwrap();
+    assert!(t.elapsed() < Duration::from_secs(5));
+    assert!(token.is_cancelled());
+    match res {
+        Err(RunError::Cancelled(n)) => assert!(n < 1_000_000),
+        _ => panic!("unexpected result"),
+    }
+}
+
+#[test]
+fn cancelled_run_returns_count() {
+    let (token, handle) = CancelToken::new();
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .cancel_token(token)
+        .run(0.., |x: u64| Ok(x), |y| {
+            ys.push(y);
+            if y == 100 { handle.cancel(); }
+            Ok(())
+        });
+    assert_eq!(res, Ok(ys.len()));
+    // buffered results are not reported after cancellation
+    assert_eq!(ys.len(), 101);
+    assert_eq!(ys, (0..ys.len() as u64).collect::<Vec<_>>());
+}
+
+#[test]
+fn token_reuse_after_successful_run() {
+    let (token, _handle) = CancelToken::new();
+    let config = Config::new().threads(2).cancel_token(token);
+    for _ in 0..3 {
+        let res: Result<usize, ()> = config.run(0..10u64, Ok, |_| Ok(()));
+        assert_eq!(res, Ok(10));
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use parstream::Config;
+
+#[test]
+fn input_capacity_limits_read_ahead_independently_of_report_capacity() {
+    let taken = AtomicUsize::new(0);
+    let ahead = AtomicUsize::new(0);
+    let xs = (0..1000usize).inspect(|_| {
+        taken.fetch_add(1, Ordering::SeqCst);
+    });
+    let res: Result<usize, ()> = Config::new()
+        .threads(1)
+        .input_capacity(1)
+        .report_capacity(64)
+        .run(xs,
+            |x| {
+                let t = taken.load(Ordering::SeqCst);
+                ahead.fetch_max(t - x, Ordering::SeqCst);
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(1000));
+    // the current element, one in the channel and one held by the feeder
+    assert!(ahead.load(Ordering::SeqCst) <= 3);
+}
+
+#[test]
+fn zero_input_capacity_hands_off_elements() {
+    let taken = AtomicUsize::new(0);
+    let seen = AtomicUsize::new(0);
+    let xs = (0..10u64).inspect(|_| { taken.fetch_add(1, Ordering::SeqCst); });
+    let res: Result<usize, ()> = Config::new()
+        .threads(1)
+        .input_capacity(0)
+        .run(xs,
+            |x| {
+                if x == 0 {
+                    thread::sleep(Duration::from_millis(50));
+                    seen.store(taken.load(Ordering::SeqCst), Ordering::SeqCst);
+                }
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(10));
+    // feeder waits with the second element until the worker takes it
+    assert_eq!(seen.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn zero_report_capacity_hands_off_results() {
+    let processed = AtomicUsize::new(0);
+    let seen = AtomicUsize::new(0);
+    let res: Result<usize, ()> = Config::new()
+        .threads(1)
+        .report_capacity(0)
+        .run(0..10u64,
+            |x| { processed.fetch_add(1, Ordering::SeqCst); Ok(x) },
+            |y| {
+                if y == 0 {
+                    thread::sleep(Duration::from_millis(50));
+                    let n = processed.load(Ordering::SeqCst);
+                    seen.store(n, Ordering::SeqCst);
+                }
+                Ok(())
+            },
+        );
+    assert_eq!(res, Ok(10));
+    // worker waits with the second result until it's taken by the report
+    assert_eq!(seen.load(Ordering::SeqCst), 2);
+}
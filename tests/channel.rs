@@ -0,0 +1,91 @@
+#![cfg(feature = "crossbeam")]
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::{CancelToken, Config};
+
+#[test]
+fn elements_trickle_in() {
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    let producer = thread::spawn(move || {
+        for x in 0..50u64 {
+            thread::sleep(Duration::from_millis(1));
+            tx.send(x).unwrap();
+        }
+    });
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_from_channel(rx, 4, Ok,
+        |y| { ys.push(y); Ok(()) });
+    producer.join().unwrap();
+    assert_eq!(res, Ok(50));
+    assert_eq!(ys, (0..50).collect::<Vec<_>>());
+}
+
+#[test]
+fn error_stops_waiting_for_elements() {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    for x in 0..10u64 { tx.send(x).unwrap(); }
+    let t = Instant::now();
+    let res = parstream::run_from_channel(rx, 4,
+        |x| if x == 5 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(5));
+    assert!(t.elapsed() < Duration::from_secs(2));
+    // sender is still alive
+    drop(tx);
+}
+
+#[test]
+fn cancellation_stops_waiting_for_elements() {
+    let (tx, rx) = crossbeam_channel::unbounded::<u64>();
+    let (token, handle) = CancelToken::new();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.cancel();
+    });
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .cancel_token(token)
+        .run_from_channel(rx, Ok, |_| Ok(()));
+    canceller.join().unwrap();
+    assert_eq!(res, Ok(0));
+    drop(tx);
+}
+
+#[test]
+fn results_to_channel() {
+    let (rx, handle) = parstream::run_to_channel(0..100u64, 4, |x| {
+        if x % 7 == 0 { thread::sleep(Duration::from_millis(2)); }
+        Ok::<u64, ()>(x * 2)
+    });
+    let ys: Vec<u64> = rx.iter().map(Result::unwrap).collect();
+    assert_eq!(ys, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+    assert_eq!(handle.join(), Ok(100));
+}
+
+#[test]
+fn error_is_last_message() {
+    let (rx, handle) = Config::new()
+        .threads(4)
+        .max_in_flight(8)
+        .run_to_channel(0..1000u64, |x| if x == 20 { Err(x) } else { Ok(x) });
+    let ys: Vec<Result<u64, u64>> = rx.iter().collect();
+    let mut expected: Vec<_> = (0..20).map(Ok).collect();
+    expected.push(Err(20));
+    assert_eq!(ys, expected);
+    assert_eq!(handle.join(), Ok(21));
+}
+
+#[test]
+fn dropped_receiver_stops_run() {
+    let (rx, handle) = Config::new()
+        .threads(2)
+        .report_capacity(2)
+        .run_to_channel(0.., Ok::<u64, ()>);
+    let ys: Vec<u64> = rx.iter().take(10).map(Result::unwrap).collect();
+    assert_eq!(ys, (0..10).collect::<Vec<_>>());
+    drop(rx);
+    let n = handle.join().unwrap();
+    assert!(n >= 10);
+}
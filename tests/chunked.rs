@@ -0,0 +1,64 @@
+use std::sync::Mutex;
+
+#[test]
+fn partial_final_chunk() {
+    let sizes = Mutex::new(Vec::new());
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_chunked(0..1003u64, 4, 100,
+        |xs| {
+            sizes.lock().unwrap().push(xs.len());
+            Ok(xs.into_iter().map(|x| x + 1).collect())
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(1003));
+    assert_eq!(ys, (1..1004u64).collect::<Vec<_>>());
+    let mut sizes = sizes.into_inner().unwrap();
+    sizes.sort();
+    assert_eq!(sizes[0], 3);
+    assert!(sizes[1..].iter().all(|&s| s == 100));
+}
+
+#[test]
+fn chunk_error_short_circuits() {
+    let res = parstream::run_chunked(0..1000u64, 4, 10,
+        |xs| if xs.contains(&555) { Err(555) } else { Ok(xs) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(555));
+}
+
+#[test]
+fn empty_input() {
+    let res: Result<usize, ()> = parstream::run_chunked(0..0u64, 4, 10,
+        Ok, |_| Ok(()));
+    assert_eq!(res, Ok(0));
+}
+
+#[test]
+#[should_panic(expected = "chunk size must be positive")]
+fn zero_chunk_size() {
+    let _: Result<usize, ()> = parstream::run_chunked(0..10u64, 4, 0,
+        Ok, |_| Ok(()));
+}
+
+#[test]
+fn cancelled_run_returns_reported_count() {
+    let (token, handle) = parstream::CancelToken::new();
+    let mut reported = 0;
+    let res: Result<usize, ()> = parstream::Config::new()
+        .threads(2)
+        .cancel_token(token)
+        .run_chunked(0..10_000u64, 10,
+            Ok,
+            |y| {
+                reported += 1;
+                if y == 94 { handle.cancel() }
+                Ok(())
+            },
+        );
+    // results of a chunk are reported together, while elements of next
+    // chunks were already taken from the input
+    assert_eq!(reported, 100);
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,64 @@
+use parstream::CircuitError;
+
+#[test]
+fn alternating_failures_do_not_open_circuit() {
+    let mut failed = Vec::new();
+    let mut ys = Vec::new();
+    let res = parstream::run_with_circuit_breaker(0..100u64, 4, 2,
+        |i, err| failed.push((i, err)),
+        |x| if x % 2 == 1 { Err(x) } else { Ok(x) },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(50));
+    assert_eq!(ys, (0..100).step_by(2).collect::<Vec<_>>());
+    let expected: Vec<_> = (1..100).step_by(2).map(|x| (x as usize, x))
+        .collect();
+    assert_eq!(failed, expected);
+}
+
+#[test]
+fn consecutive_failures_open_circuit() {
+    let mut failed = Vec::new();
+    let mut ys = Vec::new();
+    let res = parstream::run_with_circuit_breaker(0..1000u64, 4, 3,
+        |i, _| failed.push(i),
+        |x| match x {
+            10 | 11 | 20..=29 => Err(x),
+            _ => Ok(x),
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Err(CircuitError::Open(vec![(20, 20), (21, 21), (22, 22)])));
+    assert_eq!(failed, vec![10, 11]);
+    assert_eq!(ys.len(), 18);
+}
+
+#[test]
+fn trailing_errors_are_passed_to_hook() {
+    let mut failed = Vec::new();
+    let res = parstream::run_with_circuit_breaker(0..10u64, 2, 5,
+        |i, _| failed.push(i),
+        |x| if x >= 7 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Ok(7));
+    assert_eq!(failed, vec![7, 8, 9]);
+}
+
+#[test]
+fn report_error_is_returned() {
+    let res = parstream::run_with_circuit_breaker(0..100u64, 4, 3,
+        |_, _| (),
+        Ok,
+        |y| if y == 42 { Err(y) } else { Ok(()) },
+    );
+    assert_eq!(res, Err(CircuitError::Report(42)));
+}
+
+#[test]
+#[should_panic(expected = "circuit breaker threshold must be non-zero")]
+fn zero_threshold_panics() {
+    let _: Result<usize, CircuitError<()>> =
+        parstream::run_with_circuit_breaker(0..10u64, 2, 0,
+            |_, _| (), Ok, |_| Ok(()));
+}
@@ -0,0 +1,36 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn all_errors_are_collected() {
+    let (ys, errors) = parstream::run_collect_errors(0..1000u64, 8, |x| {
+        if x % 10 == 0 { thread::sleep(Duration::from_micros(200)); }
+        if x % 7 == 0 { Err(format!("bad {}", x)) } else { Ok(x) }
+    });
+    assert_eq!(ys, (0..1000u64).filter(|x| x % 7 != 0).collect::<Vec<_>>());
+    let expected: Vec<_> = (0..1000usize)
+        .filter(|x| x % 7 == 0)
+        .map(|x| (x, format!("bad {}", x)))
+        .collect();
+    assert_eq!(errors, expected);
+}
+
+#[test]
+fn no_errors() {
+    let (ys, errors) = parstream::run_collect_errors(0..100u64, 4,
+        Ok::<_, ()>);
+    assert_eq!(ys, (0..100).collect::<Vec<_>>());
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn results_are_positional() {
+    let ys = parstream::run_results(0..1000u64, 8, |x| {
+        if x % 10 == 0 { thread::sleep(Duration::from_micros(200)); }
+        if x % 7 == 0 { Err(x) } else { Ok(x*x) }
+    });
+    let expected: Vec<_> = (0..1000u64)
+        .map(|x| if x % 7 == 0 { Err(x) } else { Ok(x*x) })
+        .collect();
+    assert_eq!(ys, expected);
+}
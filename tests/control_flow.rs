@@ -0,0 +1,40 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn break_after_three_items() {
+    let computed = AtomicUsize::new(0);
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_control_flow(0..1_000_000u64, 4,
+        |x| {
+            computed.fetch_add(1, Ordering::Relaxed);
+            Ok(x)
+        },
+        |y| {
+            ys.push(y);
+            if ys.len() == 3 {
+                Ok(ControlFlow::Break(()))
+            } else {
+                Ok(ControlFlow::Continue(()))
+            }
+        },
+    );
+    assert_eq!(res, Ok(3));
+    assert_eq!(ys, vec![0, 1, 2]);
+    assert!(computed.load(Ordering::Relaxed) < 1_000_000);
+}
+
+#[test]
+fn errors_are_not_affected() {
+    let res = parstream::run_control_flow(0..100u64, 4,
+        |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(ControlFlow::Continue(())),
+    );
+    assert_eq!(res, Err(10));
+    let res = parstream::run_control_flow(0..100u64, 4, Ok,
+        |y| if y == 20 { Err(y) } else { Ok(ControlFlow::Continue(())) });
+    assert_eq!(res, Err(20));
+    let res: Result<usize, ()> = parstream::run_control_flow(0..100u64, 4,
+        Ok, |_| Ok(ControlFlow::Continue(())));
+    assert_eq!(res, Ok(100));
+}
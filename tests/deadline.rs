@@ -0,0 +1,52 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::Config;
+
+#[test]
+fn run_stops_at_deadline() {
+    let t = Instant::now();
+    let deadline = t + Duration::from_millis(100);
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_until(0..1_000_000u64, 4,
+        deadline,
+        |x| {
+            thread::sleep(Duration::from_millis(1));
+            Ok(x)
+        },
+        |y| {
+            assert!(Instant::now() < deadline);
+            ys.push(y);
+            Ok(())
+        },
+    );
+    assert!(t.elapsed() < Duration::from_secs(2));
+    assert_eq!(res, Ok(ys.len()));
+    assert!(!ys.is_empty() && ys.len() < 1_000_000);
+    assert_eq!(ys, (0..ys.len() as u64).collect::<Vec<_>>());
+}
+
+#[test]
+fn slow_workers_do_not_block_report() {
+    let t = Instant::now();
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .deadline(t + Duration::from_millis(50))
+        .run(0..10u64,
+            |x| {
+                if x == 0 { thread::sleep(Duration::from_millis(300)); }
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(0));
+    assert!(t.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn run_finished_before_deadline() {
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let res: Result<usize, ()> = parstream::run_until(0..100u64, 4, deadline,
+        Ok, |_| Ok(()));
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,55 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn runs_of_repeated_values_are_collapsed() {
+    let xs = vec![1u32, 1, 1, 2, 2, 3, 1, 1, 4, 4, 4, 4, 2, 5];
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_dedup(xs.clone(), 4,
+        |x| {
+            // early elements complete later than subsequent ones
+            thread::sleep(Duration::from_micros(u64::from(5 - x) * 200));
+            Ok(x)
+        },
+        |&y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(xs.len()));
+    // non-consecutive duplicates are reported
+    assert_eq!(ys, vec![1, 2, 3, 1, 4, 2, 5]);
+}
+
+#[test]
+fn all_equal() {
+    let mut n = 0;
+    let res: Result<usize, ()> = parstream::run_dedup(0..100u64, 4,
+        |_| Ok("state"),
+        |_| { n += 1; Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(n, 1);
+}
+
+#[test]
+fn worker_error_is_returned() {
+    let mut ys = Vec::new();
+    let res = parstream::run_dedup(0..100u64, 4,
+        |x| if x == 50 { Err(x) } else { Ok(x / 20) },
+        |&y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Err(50));
+    assert_eq!(ys, vec![0, 1, 2]);
+}
+
+#[test]
+fn results_are_not_cloned() {
+    #[derive(Debug, PartialEq)]
+    struct State(u64);
+
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_dedup(0..30u64, 4,
+        |x| Ok(State(x / 10)),
+        |y| { ys.push(y.0); Ok(()) },
+    );
+    assert_eq!(res, Ok(30));
+    assert_eq!(ys, vec![0, 1, 2]);
+}
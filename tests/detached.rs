@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::{CancelToken, Config};
+
+#[test]
+fn results_are_reported_in_order() {
+    let ys = Arc::new(Mutex::new(Vec::new()));
+    let ys2 = ys.clone();
+    let res: Result<usize, ()> = parstream::run_detached(0..100u64, 4,
+        |x| Ok(x*x),
+        move |y| { ys2.lock().unwrap().push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    let ys = ys.lock().unwrap();
+    assert_eq!(*ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+}
+
+#[test]
+fn error_is_returned_without_waiting_for_workers() {
+    let finished = Arc::new(AtomicUsize::new(0));
+    let fin = finished.clone();
+    let start = Instant::now();
+    let res = parstream::run_detached(0..100u64, 2,
+        move |x| {
+            if x == 0 {
+                thread::sleep(Duration::from_millis(20));
+                return Err(x);
+            }
+            if x == 1 {
+                thread::sleep(Duration::from_secs(2));
+                fin.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(x)
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(0));
+    assert!(start.elapsed() < Duration::from_secs(1));
+    // the slow element is still processed in the background
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn report_error_is_returned() {
+    let res = parstream::run_detached(0..100u64, 4, Ok,
+        |y| if y == 7 { Err(y) } else { Ok(()) },
+    );
+    assert_eq!(res, Err(7));
+}
+
+#[test]
+fn cancelled_run_returns_reported_count() {
+    let (token, handle) = CancelToken::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .cancel_token(token)
+        .run_detached(0.., |x: u64| Ok(x), move |y| {
+            if y == 5 { handle.cancel() }
+            Ok(())
+        });
+    assert!(res.unwrap() > 5);
+}
+
+#[test]
+#[should_panic(expected = "worker panic")]
+fn panics_are_propagated() {
+    let _ = parstream::run_detached(0..100u64, 2,
+        |x| if x == 3 { panic!("worker panic") } else { Ok::<u64, ()>(x) },
+        |_| Ok(()),
+    );
+}
+
+#[test]
+fn token_is_reusable_after_failed_run() {
+    let (token, _handle) = CancelToken::new();
+    let config = Config::new().threads(2).cancel_token(token);
+    let res = config.run_detached(0..100u64,
+        |x| {
+            if x > 10 { thread::sleep(Duration::from_millis(1)) }
+            if x == 10 { Err(x) } else { Ok(x) }
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(10));
+    // workers of the failed run may still be running
+    let res = config.run_checked(0..100u64, Ok::<_, ()>, |_| Ok(()));
+    assert_eq!(res.unwrap(), 100);
+    let res: Result<usize, ()> = config.run_detached(0..100u64, Ok, |_| Ok(()));
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,50 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn hook_observes_discarded_errors() {
+    let failed = Mutex::new(Vec::new());
+    let res = parstream::run_with_error_hook(0..100u64, 4,
+        |i, err| failed.lock().unwrap().push((i, *err)),
+        |x| match x {
+            0 => {
+                thread::sleep(Duration::from_millis(100));
+                Err(x)
+            },
+            1..=3 => Err(x),
+            _ => Ok(x),
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(0));
+    let mut failed = failed.into_inner().unwrap();
+    failed.sort_unstable();
+    // element 1 is processed by another worker while the first one is
+    // still computed, elements after it may be skipped
+    assert_eq!(failed[..2], [(0, 0), (1, 1)]);
+    assert!(failed.iter().all(|&(i, _)| i <= 3));
+}
+
+#[test]
+fn hook_receives_positions() {
+    let failed = Mutex::new(Vec::new());
+    let xs = vec!["a", "1", "b", "2"];
+    let res = parstream::run_with_error_hook(xs, 2,
+        |i, _| failed.lock().unwrap().push(i),
+        |s| s.parse::<u32>().map_err(drop),
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(()));
+    assert!(failed.into_inner().unwrap().contains(&0));
+}
+
+#[test]
+fn hook_is_not_called_without_errors() {
+    let res: Result<usize, ()> = parstream::run_with_error_hook(0..100u64, 4,
+        |_, _| panic!("unexpected error"),
+        Ok,
+        |_| Ok(()),
+    );
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,64 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn lowest_index_error_is_returned() {
+    for _ in 0..20 {
+        let mut ys = Vec::new();
+        let res = parstream::run(0..100u64, 8,
+            |x| match x {
+                // error at index 3 is computed after error at index 7
+                3 => {
+                    thread::sleep(Duration::from_millis(20));
+                    Err(x)
+                },
+                7 => Err(x),
+                _ => Ok(x),
+            },
+            |y| { ys.push(y); Ok(()) },
+        );
+        assert_eq!(res, Err(3));
+        assert_eq!(ys, vec![0, 1, 2]);
+    }
+}
+
+#[test]
+fn lowest_index_error_with_bounded_buffer() {
+    let res = parstream::Config::new()
+        .threads(4)
+        .max_reorder_buffer(4)
+        .run(0..100u64,
+            |x| {
+                if x == 0 { thread::sleep(Duration::from_millis(20)); }
+                if x == 0 || x == 2 { Err(x) } else { Ok(x) }
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Err(0));
+}
+
+#[test]
+fn partial_results_are_kept() {
+    let (ys, err) = parstream::collect_partial(0..1000u64, 8, |x| {
+        if x % 10 == 0 { thread::sleep(Duration::from_micros(200)); }
+        if x == 500 || x == 700 { Err(x) } else { Ok(x) }
+    });
+    assert_eq!(err, Some(500));
+    assert_eq!(ys, (0..500).collect::<Vec<_>>());
+
+    let (ys, err) = parstream::collect_partial(0..100u64, 4, Ok::<_, ()>);
+    assert_eq!(err, None);
+    assert_eq!(ys.len(), 100);
+}
+
+#[test]
+fn count_is_returned_with_error() {
+    use parstream::RunFailure;
+
+    let res = parstream::run_with_count(0..100u64, 4, Ok,
+        |y| if y == 30 { Err(y) } else { Ok(()) });
+    assert_eq!(res, Err(RunFailure { error: 30, completed: 30 }));
+    let res: Result<usize, RunFailure<()>> = parstream::run_with_count(
+        0..100u64, 4, Ok, |_| Ok(()));
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,54 @@
+use parstream::ErrorPolicy;
+
+fn f(x: u64) -> Result<u64, u64> {
+    if x % 10 == 3 { Err(x) } else { Ok(x) }
+}
+
+#[test]
+fn fail_fast_is_default() {
+    let mut ys = Vec::new();
+    let res = parstream::run_with_error_policy(0..100u64, 4,
+        ErrorPolicy::default(), f, |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Err(vec![(3, 3)]));
+    assert_eq!(ys, vec![0, 1, 2]);
+}
+
+#[test]
+fn collect_all() {
+    let mut ys = Vec::new();
+    let res = parstream::run_with_error_policy(0..100u64, 4,
+        ErrorPolicy::CollectAll, f, |y| { ys.push(y); Ok(()) });
+    let errors: Vec<_> = (0..10).map(|i| (i * 10 + 3, i as u64 * 10 + 3))
+        .collect();
+    assert_eq!(res, Err(errors));
+    assert_eq!(ys, (0..100).filter(|x| x % 10 != 3).collect::<Vec<_>>());
+}
+
+#[test]
+fn collect_all_without_errors() {
+    let res = parstream::run_with_error_policy(0..100u64, 4,
+        ErrorPolicy::CollectAll, Ok::<u64, ()>, |_| Ok(()));
+    assert_eq!(res, Ok(100));
+}
+
+#[test]
+fn continue_with_hook() {
+    let mut logged = Vec::new();
+    let mut ys = Vec::new();
+    let res = parstream::run_with_error_policy(0..100u64, 4,
+        ErrorPolicy::Continue(Box::new(|i, e| logged.push((i, e)))),
+        f, |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(90));
+    assert_eq!(ys.len(), 90);
+    assert_eq!(logged.len(), 10);
+    assert!(logged.windows(2).all(|w| w[0].0 < w[1].0));
+}
+
+#[test]
+fn report_error_stops_run() {
+    let res = parstream::run_with_error_policy(0..100u64, 4,
+        ErrorPolicy::CollectAll, f,
+        |y| if y == 50 { Err(1000) } else { Ok(()) });
+    assert_eq!(res, Err(vec![(3, 3), (13, 13), (23, 23), (33, 33), (43, 43),
+        (50, 1000)]));
+}
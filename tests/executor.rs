@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use parstream::{Config, Executor};
+
+#[derive(Default)]
+struct Counting(AtomicUsize);
+
+impl Executor for Counting {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        thread::spawn(task);
+    }
+}
+
+#[test]
+fn executor_threads_can_borrow_stack_data() {
+    let factor = 3u64;
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .executor(Counting::default())
+        .run(0..50u64, |x| Ok(x*factor), |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(50));
+    assert_eq!(ys, (0..50).map(|x| x*factor).collect::<Vec<_>>());
+}
+
+#[test]
+fn executor_is_shared_between_runs() {
+    let executor: Arc<dyn Executor> = Arc::new(Counting::default());
+    let config = Config::new().threads(3).executor(executor);
+    for _ in 0..2 {
+        let res: Result<usize, ()> = config.run(0..20u64, Ok, |_| Ok(()));
+        assert_eq!(res, Ok(20));
+    }
+}
+
+#[test]
+fn executor_replaces_spawn_hook() {
+    let hooked = Arc::new(AtomicUsize::new(0));
+    let h = hooked.clone();
+    let executor = Arc::new(Counting::default());
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .spawn_hook(move |task| {
+            h.fetch_add(1, Ordering::Relaxed);
+            thread::spawn(task);
+        })
+        .executor(executor.clone())
+        .run(0..10u64, Ok, |_| Ok(()));
+    assert_eq!(res, Ok(10));
+    assert_eq!(hooked.load(Ordering::Relaxed), 0);
+    assert_eq!(executor.0.load(Ordering::Relaxed), 3);
+}
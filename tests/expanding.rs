@@ -0,0 +1,86 @@
+use parstream::{CancelToken, Config};
+use std::thread;
+use std::time::Duration;
+
+fn children(x: u64) -> Vec<u64> {
+    if x < 512 { vec![2 * x, 2 * x + 1] } else { vec![] }
+}
+
+#[test]
+fn breadth_first_order() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_expanding(vec![1u64], 8,
+        |x| {
+            thread::sleep(Duration::from_micros((x * 7) % 50));
+            Ok((vec![x], children(x)))
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(1023));
+    assert_eq!(ys, (1..1024).collect::<Vec<_>>());
+}
+
+#[test]
+fn multiple_results_and_seeds() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_expanding(vec![10u64, 20], 4,
+        |x| {
+            let next = if x % 10 < 2 { vec![x + 1] } else { vec![] };
+            Ok((vec![x, 100 + x], next))
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(6));
+    assert_eq!(ys, vec![
+        10, 110, 20, 120, 11, 111, 21, 121, 12, 112, 22, 122,
+    ]);
+}
+
+#[test]
+fn no_seeds() {
+    let res: Result<usize, ()> = parstream::run_expanding(Vec::<u64>::new(),
+        4, |x| Ok((vec![x], vec![x])), |_| Ok(()));
+    assert_eq!(res, Ok(0));
+}
+
+#[test]
+fn prefetch_and_in_flight_limit() {
+    let mut n = 0;
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .prefetch(16)
+        .max_in_flight(4)
+        .run_expanding(vec![1u64], |x| Ok((vec![x], children(x))),
+            |_| { n += 1; Ok(()) });
+    assert_eq!(res, Ok(1023));
+    assert_eq!(n, 1023);
+}
+
+#[test]
+fn error_stops_run() {
+    let res = parstream::run_expanding(vec![1u64], 4, |x| {
+        if x == 100 { Err(x) } else { Ok((vec![x], children(x))) }
+    }, |_| Ok(()));
+    assert_eq!(res, Err(100));
+
+    let res = parstream::run_expanding(vec![1u64], 4,
+        |x| Ok((vec![x], children(x))),
+        |y| if y == 100 { Err(y) } else { Ok(()) });
+    assert_eq!(res, Err(100));
+}
+
+#[test]
+fn infinite_expansion_is_cancelled() {
+    let (token, handle) = CancelToken::new();
+    let mut n = 0;
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .cancel_token(token)
+        .run_expanding(vec![0u64], |x| Ok((vec![x], vec![x + 1])), |_| {
+            n += 1;
+            if n == 100 { handle.cancel() }
+            Ok(())
+        });
+    assert!(res.unwrap() >= 100);
+    assert_eq!(n, 100);
+}
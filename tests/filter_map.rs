@@ -0,0 +1,26 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn filtered_slots_out_of_completion_order() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_filter_map(0..200u64, 8,
+        |x| {
+            // early elements complete later than subsequent ones
+            thread::sleep(Duration::from_micros((200 - x) * 20));
+            Ok(if x % 2 == 0 { Some(x) } else { None })
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(200));
+    assert_eq!(ys, (0..200u64).filter(|x| x % 2 == 0).collect::<Vec<_>>());
+}
+
+#[test]
+fn all_filtered() {
+    let res: Result<usize, ()> = parstream::run_filter_map(0..100u64, 4,
+        |_| Ok(None::<u64>),
+        |_| panic!("report must not be called"),
+    );
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn earlier_match_wins() {
+    let res: Result<_, ()> = parstream::find_first(0..10u64, 4, |x| {
+        // element 2 completes long after element 5
+        if x == 2 { thread::sleep(Duration::from_millis(100)); }
+        Ok(if x == 2 || x == 5 { Some(x * 10) } else { None })
+    });
+    assert_eq!(res, Ok(Some((2, 20))));
+}
+
+#[test]
+fn no_match() {
+    let res: Result<Option<(usize, u64)>, ()> =
+        parstream::find_first(0..100u64, 4, |_| Ok(None));
+    assert_eq!(res, Ok(None));
+}
+
+#[test]
+fn match_stops_run() {
+    let processed = AtomicUsize::new(0);
+    let res: Result<_, ()> = parstream::Config::new()
+        .threads(4)
+        .max_in_flight(16)
+        .find_first(0.., |x: u64| {
+            processed.fetch_add(1, Ordering::SeqCst);
+            Ok(if x == 10 { Some(x) } else { None })
+        });
+    assert_eq!(res, Ok(Some((10, 10))));
+    // only elements already sent to workers are processed after the match
+    assert!(processed.load(Ordering::SeqCst) <= 10 + 16 + 1);
+}
+
+#[test]
+fn error_before_match() {
+    let res = parstream::find_first(0..100u64, 4, |x| match x {
+        3 => Err(x),
+        7 => Ok(Some(x)),
+        _ => Ok(None),
+    });
+    assert_eq!(res, Err(3));
+}
+
+#[test]
+fn match_before_error() {
+    let res = parstream::find_first(0..100u64, 4, |x| match x {
+        3 => Ok(Some(x)),
+        7 => Err(x),
+        _ => Ok(None),
+    });
+    assert_eq!(res, Ok(Some((3, 3))));
+}
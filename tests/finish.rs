@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+
+use parstream::{CancelToken, Config};
+
+#[test]
+fn on_finish_is_called_once_after_all_reports() {
+    let events = Mutex::new(Vec::new());
+    let res: Result<usize, ()> = parstream::run_with_finish(0..100u64, 4,
+        |x| Ok(x*x),
+        |y| { events.lock().unwrap().push(Some(y)); Ok(()) },
+        || { events.lock().unwrap().push(None); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    let mut expected: Vec<_> = (0..100u64).map(|x| Some(x*x)).collect();
+    expected.push(None);
+    assert_eq!(events.into_inner().unwrap(), expected);
+}
+
+#[test]
+fn on_finish_is_called_for_empty_input() {
+    let mut finished = 0;
+    let res: Result<usize, ()> = parstream::run_with_finish(0..0u64, 4,
+        Ok,
+        |_| Ok(()),
+        || { finished += 1; Ok(()) },
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(finished, 1);
+}
+
+#[test]
+fn on_finish_is_not_called_on_error() {
+    let mut finished = false;
+    let res = parstream::run_with_finish(0..100u64, 4,
+        |x| if x == 50 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+        || { finished = true; Ok(()) },
+    );
+    assert_eq!(res, Err(50));
+    assert!(!finished);
+
+    let res = parstream::run_with_finish(0..100u64, 4,
+        Ok,
+        |y| if y == 10 { Err(y) } else { Ok(()) },
+        || { finished = true; Ok(()) },
+    );
+    assert_eq!(res, Err(10));
+    assert!(!finished);
+}
+
+#[test]
+fn on_finish_is_not_called_on_cancel() {
+    let (token, handle) = CancelToken::new();
+    let mut finished = false;
+    let mut reported = 0;
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .cancel_token(token)
+        .run_with_finish(0..100u64, Ok,
+            |y| {
+                reported += 1;
+                if y == 5 { handle.cancel() }
+                Ok(())
+            },
+            || { finished = true; Ok(()) },
+        );
+    // report closure is not called after cancellation, even if all results
+    // were already computed
+    assert_eq!(reported, 6);
+    assert_eq!(res, Ok(6));
+    assert!(!finished);
+}
+
+#[test]
+fn error_of_on_finish_is_returned() {
+    let res = parstream::run_with_finish(0..10u64, 2,
+        Ok,
+        |_| Ok(()),
+        || Err(u64::MAX),
+    );
+    assert_eq!(res, Err(u64::MAX));
+}
@@ -0,0 +1,48 @@
+use std::thread;
+use std::time::Duration;
+
+use parstream::Config;
+
+#[test]
+fn outputs_in_global_order() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_flat_map(0..100u64, 8,
+        |x| {
+            thread::sleep(Duration::from_micros((100 - x) * 50));
+            Ok((0..x % 5).map(move |i| (x, i)).collect::<Vec<_>>())
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    let expected: Vec<_> = (0..100u64)
+        .flat_map(|x| (0..x % 5).map(move |i| (x, i)))
+        .collect();
+    assert_eq!(ys, expected);
+}
+
+#[test]
+fn lazy_iterators_with_bounded_buffer() {
+    let mut n = 0u64;
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .max_reorder_buffer(8)
+        .run_flat_map(0..20u64,
+            |x| {
+                if x == 0 { thread::sleep(Duration::from_millis(20)); }
+                // huge output is produced lazily by the report thread
+                Ok(0..100_000u64)
+            },
+            |_| { n += 1; Ok(()) },
+        );
+    assert_eq!(res, Ok(20));
+    assert_eq!(n, 20 * 100_000);
+}
+
+#[test]
+fn report_error_inside_collection() {
+    let res = parstream::run_flat_map(0..10u64, 4,
+        |x| Ok(vec![x, x]),
+        |y| if y == 5 { Err(y) } else { Ok(()) },
+    );
+    assert_eq!(res, Err(5));
+}
@@ -0,0 +1,106 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn fold_is_ordered() {
+    let res: Result<Vec<u64>, ()> = parstream::fold_ordered(0..100u64, 8,
+        Vec::new(),
+        |x| {
+            thread::sleep(Duration::from_micros((100 - x) * 20));
+            Ok(x*x)
+        },
+        |mut acc, y| { acc.push(y); Ok(acc) },
+    );
+    assert_eq!(res.unwrap(), (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+}
+
+#[test]
+fn non_associative_combine() {
+    let res: Result<u64, ()> = parstream::fold_ordered(1..20u64, 4, 0,
+        Ok, |acc, y| Ok(acc * 2 + y));
+    assert_eq!(res, Ok((1..20u64).fold(0, |acc, y| acc * 2 + y)));
+}
+
+#[test]
+fn combine_error() {
+    let res = parstream::fold_ordered(0..100u64, 4, 0u64, Ok,
+        |acc, y| if y == 50 { Err(acc) } else { Ok(acc + y) });
+    assert_eq!(res, Err((0..50).sum()));
+}
+
+#[test]
+fn map_error_stops_run() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let processed = AtomicUsize::new(0);
+    let combined = AtomicUsize::new(0);
+    let res = parstream::Config::new()
+        .threads(4)
+        .max_in_flight(8)
+        .fold_ordered(0..10_000u64, 0u64,
+            |x| {
+                processed.fetch_add(1, Ordering::SeqCst);
+                if x == 20 { Err(x) } else { Ok(x) }
+            },
+            |acc, y| {
+                combined.fetch_add(1, Ordering::SeqCst);
+                Ok(acc + y)
+            },
+        );
+    assert_eq!(res, Err(20));
+    assert_eq!(combined.load(Ordering::SeqCst), 20);
+    assert!(processed.load(Ordering::SeqCst) <= 20 + 8 + 1);
+}
+
+#[test]
+fn try_fold_returns_accumulator() {
+    let res: Result<(usize, String), ()> = parstream::try_fold_ordered(
+        0..10u64, 4, (0, String::new()),
+        |x| Ok(x * 2),
+        |(n, s), y| Ok((n + 1, s + &y.to_string())),
+    );
+    assert_eq!(res, Ok((10, "024681012141618".to_string())));
+}
+
+#[test]
+fn try_fold_stops_on_first_fold_error() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let folded = AtomicUsize::new(0);
+    let res = parstream::Config::new()
+        .threads(4)
+        .max_in_flight(8)
+        .try_fold_ordered(0..10_000u64, 0u64, Ok,
+            |acc, y| {
+                folded.fetch_add(1, Ordering::SeqCst);
+                if y == 30 { Err(acc) } else { Ok(acc + y) }
+            },
+        );
+    assert_eq!(res, Err((0..30).sum()));
+    assert_eq!(folded.load(Ordering::SeqCst), 31);
+}
+
+#[test]
+fn try_fold_stops_on_first_map_error() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let processed = AtomicUsize::new(0);
+    let folded = AtomicUsize::new(0);
+    let res = parstream::Config::new()
+        .threads(4)
+        .max_in_flight(8)
+        .try_fold_ordered(0..10_000u64, Vec::new(),
+            |x| {
+                processed.fetch_add(1, Ordering::SeqCst);
+                if x == 20 || x == 25 { Err(x) } else { Ok(x) }
+            },
+            |mut acc, y| {
+                folded.fetch_add(1, Ordering::SeqCst);
+                acc.push(y);
+                Ok(acc)
+            },
+        );
+    assert_eq!(res, Err(20));
+    assert_eq!(folded.load(Ordering::SeqCst), 20);
+    assert!(processed.load(Ordering::SeqCst) <= 20 + 8 + 1);
+}
@@ -0,0 +1,66 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::{Config, RunError};
+
+#[test]
+fn stalled_run_is_stopped() {
+    let start = Instant::now();
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(4)
+        .heartbeat(Duration::from_millis(50))
+        .run_checked(0..1000u64,
+            |x| {
+                if x == 10 { thread::sleep(Duration::from_millis(500)); }
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    match res {
+        Err(RunError::Stalled(n)) => assert_eq!(n, 10),
+        _ => panic!("unexpected result: {:?}", res),
+    }
+    // run waits for the blocked worker, but not for remaining elements
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn slow_progress_is_not_stall() {
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(2)
+        .heartbeat(Duration::from_millis(200))
+        .run_checked(0..50u64,
+            |x| {
+                thread::sleep(Duration::from_millis(5));
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res.unwrap(), 50);
+}
+
+#[test]
+fn slow_input_is_not_stall() {
+    // no elements are pending while feeder waits for the input
+    let xs = (0..5u64).inspect(|_| thread::sleep(Duration::from_millis(100)));
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(2)
+        .heartbeat(Duration::from_millis(50))
+        .run_checked(xs, Ok, |_| Ok(()));
+    assert_eq!(res.unwrap(), 5);
+}
+
+#[test]
+#[should_panic(expected = "run has stalled after reporting 0 elements")]
+fn unchecked_run_panics_on_stall() {
+    let _: Result<usize, ()> = Config::new()
+        .threads(1)
+        .heartbeat(Duration::from_millis(20))
+        .run(0..10u64,
+            |x| {
+                thread::sleep(Duration::from_millis(200));
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+}
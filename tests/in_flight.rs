@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use parstream::Config;
+
+fn check_limit(config: Config, limit: usize) {
+    let in_flight = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    // elements are counted as in flight right before they are sent
+    let xs = (0..200u64).inspect(|_| {
+        let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(n, Ordering::SeqCst);
+    });
+    let res: Result<usize, ()> = config.run(xs,
+        |x| {
+            thread::sleep(Duration::from_micros(100 * (x % 7)));
+            Ok(x)
+        },
+        |_| {
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        },
+    );
+    assert_eq!(res, Ok(200));
+    let peak = peak.load(Ordering::SeqCst);
+    assert!(peak <= limit, "peak {} exceeds limit {}", peak, limit);
+}
+
+#[test]
+fn in_flight_never_exceeds_limit() {
+    for &limit in &[1, 3, 10] {
+        let config = Config::new()
+            .threads(4)
+            .input_capacity(32)
+            .report_capacity(32)
+            .max_in_flight(limit);
+        check_limit(config, limit);
+    }
+}
+
+#[test]
+fn smaller_limit_is_used() {
+    let config = Config::new()
+        .threads(4)
+        .max_reorder_buffer(8)
+        .max_in_flight(2);
+    check_limit(config, 2);
+    let config = Config::new()
+        .threads(4)
+        .max_reorder_buffer(2)
+        .max_in_flight(8);
+    check_limit(config, 2);
+}
+
+#[test]
+#[should_panic(expected = "number of elements in flight must be non-zero")]
+fn zero_limit_panics() {
+    let _ = Config::new().max_in_flight(0);
+}
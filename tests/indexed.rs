@@ -0,0 +1,17 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn indexed_report_positions() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_indexed_report(
+        (0..100u64).map(|x| x * 7), 8,
+        |x| {
+            thread::sleep(Duration::from_micros(700 - x));
+            Ok(x + 1)
+        },
+        |i, y| { ys.push((i, y)); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100).map(|i| (i, i as u64 * 7 + 1)).collect::<Vec<_>>());
+}
@@ -0,0 +1,56 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use parstream::Config;
+
+#[test]
+fn infinite_iterator_with_break() {
+    let taken = AtomicUsize::new(0);
+    let reported = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let xs = (0u64..).inspect(|_| {
+        let t = taken.fetch_add(1, Ordering::SeqCst) + 1;
+        let live = t - reported.load(Ordering::SeqCst);
+        peak.fetch_max(live, Ordering::SeqCst);
+    });
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .max_in_flight(32)
+        .run_control_flow(xs, |x| Ok(x*x), |y| {
+            reported.fetch_add(1, Ordering::SeqCst);
+            ys.push(y);
+            if ys.len() == 10_000 {
+                Ok(ControlFlow::Break(()))
+            } else {
+                Ok(ControlFlow::Continue(()))
+            }
+        });
+    assert_eq!(res, Ok(10_000));
+    assert_eq!(ys, (0..10_000u64).map(|x| x*x).collect::<Vec<_>>());
+    // elements taken from the iterator, but not reported yet, are limited
+    // by the window and do not grow with number of processed elements
+    assert!(peak.load(Ordering::SeqCst) <= 33);
+}
+
+#[test]
+fn infinite_iterator_with_error() {
+    let res = parstream::run(0u64.., 4,
+        |x| if x == 5000 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(5000));
+}
+
+#[test]
+fn infinite_iterator_is_collected_until_error() {
+    let res = parstream::collect_ordered(0u64.., 4,
+        |x| if x == 1000 { Err(x) } else { Ok(x) },
+    );
+    assert_eq!(res, Err(1000));
+    let (ys, err) = parstream::collect_partial(0u64.., 4,
+        |x| if x == 1000 { Err(x) } else { Ok(x) },
+    );
+    assert_eq!(ys, (0..1000).collect::<Vec<_>>());
+    assert_eq!(err, Some(1000));
+}
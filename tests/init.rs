@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+#[test]
+fn init_is_called_once_per_worker() {
+    let inits = AtomicUsize::new(0);
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_with_init(0..1000u64, 4,
+        || {
+            inits.fetch_add(1, Ordering::Relaxed);
+            0u64
+        },
+        |calls, x| {
+            *calls += 1;
+            Ok(x*x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(1000));
+    assert_eq!(inits.load(Ordering::Relaxed), 4);
+    assert_eq!(ys, (0..1000u64).map(|x| x*x).collect::<Vec<_>>());
+}
+
+#[test]
+#[should_panic(expected = "init panic")]
+fn init_panic_is_propagated() {
+    let _: Result<usize, ()> = parstream::run_with_init(0..1000u64, 4,
+        || panic!("init panic"),
+        |_: &mut (), x| Ok(x),
+        |_| Ok(()),
+    );
+}
+
+#[test]
+fn worker_indices() {
+    let ids = Mutex::new(HashMap::new());
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_with_worker_index(
+        0..1000u64, 4,
+        |w, x| {
+            assert!(w < 4);
+            let id = thread::current().id();
+            // the same worker always runs on the same thread
+            assert_eq!(*ids.lock().unwrap().entry(w).or_insert(id), id);
+            Ok(x*x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(1000));
+    assert_eq!(ys, (0..1000u64).map(|x| x*x).collect::<Vec<_>>());
+}
+
+#[test]
+fn clone_per_worker() {
+    use std::cell::Cell;
+
+    // `Cell` is not `Sync`, every worker counts its own elements
+    let calls = Cell::new(0usize);
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_clone(0..1000u64, 4,
+        move |x| {
+            calls.set(calls.get() + 1);
+            Ok((x, calls.get()))
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(1000));
+    assert!(ys.iter().map(|&(x, _)| x).eq(0..1000));
+    assert!(ys.iter().all(|&(_, n)| (1..=1000).contains(&n)));
+}
+
+#[test]
+fn clone_mut_state() {
+    let mut seen = 0u64;
+    let res: Result<usize, u64> = parstream::run_clone(0..100u64, 4,
+        move |x| {
+            seen += 1;
+            if seen > 100 { Err(x) } else { Ok(x) }
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,41 @@
+use std::cell::Cell;
+use std::thread;
+
+#[test]
+fn same_output_as_run() {
+    let f = |x: u64| if x == 70 { Err(x) } else { Ok(x * 3) };
+    let mut ys1 = Vec::new();
+    let res1 = parstream::run(0..100u64, 4, f, |y| { ys1.push(y); Ok(()) });
+    let mut ys2 = Vec::new();
+    let res2 = parstream::run_inline(0..100u64, f, |y| { ys2.push(y); Ok(()) });
+    assert_eq!(res1, res2);
+    assert_eq!(ys1, ys2);
+}
+
+#[test]
+fn runs_on_calling_thread() {
+    let id = thread::current().id();
+    // closures do not have to be `Sync` or `Send`
+    let calls = Cell::new(0);
+    let res: Result<usize, ()> = parstream::run_inline(0..10u64,
+        |x| {
+            assert_eq!(thread::current().id(), id);
+            calls.set(calls.get() + 1);
+            Ok(x)
+        },
+        |_| { assert_eq!(thread::current().id(), id); Ok(()) },
+    );
+    assert_eq!(res, Ok(10));
+    assert_eq!(calls.get(), 10);
+}
+
+#[test]
+fn report_error_stops_run() {
+    let calls = Cell::new(0);
+    let res = parstream::run_inline(0..100u64,
+        |x| { calls.set(calls.get() + 1); Ok(x) },
+        |y| if y == 5 { Err(y) } else { Ok(()) },
+    );
+    assert_eq!(res, Err(5));
+    assert_eq!(calls.get(), 6);
+}
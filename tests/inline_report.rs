@@ -0,0 +1,58 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use parstream::Config;
+
+#[test]
+fn report_runs_on_calling_thread() {
+    let id = thread::current().id();
+    let ys = Rc::new(RefCell::new(Vec::new()));
+    let res: Result<usize, ()> = parstream::run_inline_report(0..1000u64, 4,
+        |x| {
+            if x % 10 == 0 { thread::sleep(Duration::from_micros(100)); }
+            Ok(x*x)
+        },
+        |y| {
+            assert_eq!(thread::current().id(), id);
+            ys.borrow_mut().push(y);
+            Ok(())
+        },
+    );
+    assert_eq!(res, Ok(1000));
+    assert_eq!(*ys.borrow(), (0..1000u64).map(|x| x*x).collect::<Vec<_>>());
+}
+
+#[test]
+fn errors_and_feeder_name() {
+    let res = Config::new()
+        .threads(4)
+        .thread_name("inline")
+        .run_inline_report((0..1000u64).inspect(|_| {
+                assert_eq!(thread::current().name(), Some("inline-feeder"));
+            }),
+            |x| if x == 500 { Err(x) } else { Ok(x) },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Err(500));
+    let res: Result<usize, u64> = parstream::run_inline_report(0..1000u64, 4,
+        Ok, |y| if y == 10 { Err(y) } else { Ok(()) });
+    assert_eq!(res, Err(10));
+}
+
+#[test]
+#[should_panic(expected = "report panic")]
+fn report_panic_is_propagated() {
+    let _: Result<usize, ()> = parstream::run_inline_report(0..1000u64, 4,
+        Ok, |y| if y == 10 { panic!("report panic") } else { Ok(()) });
+}
+
+#[test]
+fn spawn_hook_is_used_for_feeder() {
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .spawn_hook(|task| { thread::spawn(task); })
+        .run_inline_report(0..100u64, Ok, |_| Ok(()));
+    assert_eq!(res, Ok(100));
+}
@@ -0,0 +1,107 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::{Config, RunError};
+
+const TIMEOUT: Duration = Duration::from_millis(50);
+
+#[test]
+fn results_are_reported_in_order() {
+    let ys = Arc::new(Mutex::new(Vec::new()));
+    let ys2 = ys.clone();
+    let res: Result<usize, RunError<()>> = parstream::run_with_join_timeout(
+        0..100u64, 4, TIMEOUT,
+        |x| Ok(x*x),
+        move |y| { ys2.lock().unwrap().push(y); Ok(()) },
+    );
+    assert_eq!(res.unwrap(), 100);
+    let ys = ys.lock().unwrap();
+    assert_eq!(*ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+}
+
+#[test]
+fn error_is_returned_if_workers_exit_in_time() {
+    let res = parstream::run_with_join_timeout(0..100u64, 4, TIMEOUT,
+        |x| {
+            if x == 10 { return Err(x) }
+            if x > 10 { thread::sleep(Duration::from_millis(1)) }
+            Ok(x)
+        },
+        |_| Ok(()),
+    );
+    match res {
+        Err(RunError::Callback(10)) => (),
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn stuck_worker_is_abandoned() {
+    let started = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicUsize::new(0));
+    let (st, fin) = (started.clone(), finished.clone());
+    let start = Instant::now();
+    let res = parstream::run_with_join_timeout(0..100u64, 4, TIMEOUT,
+        move |x| {
+            if x == 3 {
+                while !st.load(Ordering::Acquire) { thread::yield_now() }
+                return Err(x);
+            }
+            if x == 5 {
+                st.store(true, Ordering::Release);
+                thread::sleep(Duration::from_millis(500));
+                fin.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(x)
+        },
+        |_| Ok(()),
+    );
+    match res {
+        Err(RunError::WorkersStuck(3)) => (),
+        res => panic!("unexpected result: {:?}", res),
+    }
+    assert!(start.elapsed() < Duration::from_millis(400));
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+    // the leaked thread keeps running after return
+    thread::sleep(Duration::from_millis(700));
+    assert_eq!(finished.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn stalled_run_with_stuck_worker() {
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(4)
+        .heartbeat(TIMEOUT)
+        .run_with_join_timeout(0..100u64, TIMEOUT,
+            |x| {
+                if x == 5 { thread::sleep(Duration::from_secs(1)) }
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    match res {
+        Err(RunError::WorkersStuck(5)) => (),
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn report_panic_is_returned() {
+    let res: Result<usize, RunError<()>> = parstream::run_with_join_timeout(
+        0..100u64, 4, TIMEOUT,
+        Ok,
+        |y| { if y == 7 { panic!("report panic") } Ok(()) },
+    );
+    match res {
+        Err(RunError::ReportPanic(_)) => (),
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn display() {
+    let err: RunError<String> = RunError::WorkersStuck(3);
+    assert_eq!(err.to_string(), "workers are stuck after reporting 3 elements");
+}
@@ -0,0 +1,61 @@
+use std::io::{self, BufReader, Read};
+
+#[test]
+fn lines_are_reported_in_order() {
+    let text: String = (0..1000).map(|x| format!("{}\n", x)).collect();
+    let mut ys = Vec::new();
+    let res: Result<usize, io::Error> = parstream::run_lines(
+        text.as_bytes(), 4,
+        |line| Ok(line.parse::<u64>().unwrap() * 2),
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res.unwrap(), 1000);
+    assert_eq!(ys, (0..1000).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+/// Reader which fails after returning `data`.
+struct Failing<'a> {
+    data: &'a [u8],
+    reads: usize,
+}
+
+impl<'a> Read for Failing<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads += 1;
+        if self.data.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"));
+        }
+        let n = self.data.read(buf)?;
+        Ok(n)
+    }
+}
+
+#[test]
+fn read_error_stops_run() {
+    let mut reader = BufReader::with_capacity(4, Failing {
+        data: b"a\nb\nc\n",
+        reads: 0,
+    });
+    let mut ys = Vec::new();
+    let res = parstream::run_lines(&mut reader, 2,
+        |line| Ok::<_, io::Error>(line.to_uppercase()),
+        |y| { ys.push(y); Ok(()) },
+    );
+    let err = res.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(ys, vec!["A", "B", "C"]);
+    // no reads were made after the error
+    let reads = reader.get_ref().reads;
+    assert_eq!(reads, 3);
+}
+
+#[test]
+fn callback_error_in_line() {
+    let res: Result<usize, io::Error> = parstream::run_lines(
+        "1\n2\nx\n4\n".as_bytes(), 2,
+        |line| line.parse::<u64>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        |_| Ok(()),
+    );
+    assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,53 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn large_latency_preserves_order() {
+    let mut late = Vec::new();
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_with_max_latency(0..100u64, 4,
+        Duration::from_secs(10),
+        |i| late.push(i),
+        |x| {
+            thread::sleep(Duration::from_millis((x * 7) % 5));
+            Ok(x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert!(late.is_empty());
+    assert_eq!(ys, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn slow_element_is_reported_late() {
+    let mut late = Vec::new();
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_with_max_latency(0..50u64, 4,
+        Duration::from_millis(20),
+        |i| late.push(i),
+        |x| {
+            if x == 5 { thread::sleep(Duration::from_millis(300)); }
+            Ok(x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(50));
+    assert_eq!(late, vec![5]);
+    let pos = ys.iter().position(|&y| y == 5).unwrap();
+    assert!(pos > 5);
+    // all other results are reported in order
+    let rest: Vec<u64> = ys.iter().copied().filter(|&y| y != 5).collect();
+    assert_eq!(rest, (0..50).filter(|&y| y != 5).collect::<Vec<_>>());
+}
+
+#[test]
+fn error_stops_run() {
+    let res = parstream::run_with_max_latency(0..100u64, 4,
+        Duration::from_millis(20),
+        |_| (),
+        |x| if x == 50 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(50));
+}
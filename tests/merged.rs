@@ -0,0 +1,44 @@
+use std::thread;
+use std::time::Duration;
+
+use parstream::MergeOrder;
+
+fn f(x: u64) -> Result<u64, ()> {
+    // later elements complete earlier
+    thread::sleep(Duration::from_micros(1000 - x % 1000));
+    Ok(x)
+}
+
+#[test]
+fn concat() {
+    let mut ys = Vec::new();
+    let res = parstream::run_merged(vec![0..10u64, 100..105, 200..220], 4,
+        MergeOrder::Concat, f, |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(35));
+    let expected: Vec<u64> = (0..10).chain(100..105).chain(200..220).collect();
+    assert_eq!(ys, expected);
+}
+
+#[test]
+fn round_robin() {
+    let mut ys = Vec::new();
+    let res = parstream::run_merged(vec![0..2u64, 100..105, 200..203], 4,
+        MergeOrder::RoundRobin, f, |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(10));
+    assert_eq!(ys, vec![0, 100, 200, 1, 101, 201, 102, 202, 103, 104]);
+}
+
+#[test]
+fn empty_sources() {
+    let sources: Vec<Vec<u64>> = vec![vec![], vec![1, 2], vec![]];
+    let mut ys = Vec::new();
+    let res = parstream::run_merged(sources, 4, MergeOrder::RoundRobin, f,
+        |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(2));
+    assert_eq!(ys, vec![1, 2]);
+
+    let sources: Vec<Vec<u64>> = Vec::new();
+    let res = parstream::run_merged(sources, 4, MergeOrder::Concat, f,
+        |_| Ok(()));
+    assert_eq!(res, Ok(0));
+}
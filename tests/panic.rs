@@ -0,0 +1,166 @@
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn worker_panic_does_not_deadlock() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = panic::catch_unwind(|| {
+            let xs = 0..1000u64;
+            parstream::run(xs, 4,
+                |x| {
+                    if x == 10 { panic!("worker panic at {}", x); }
+                    Ok::<_, ()>(x)
+                },
+                |_| Ok(()),
+            )
+        });
+        tx.send(res.is_err()).unwrap();
+    });
+    let panicked = rx.recv_timeout(Duration::from_secs(10))
+        .expect("run has not returned in time");
+    assert!(panicked);
+}
+
+#[test]
+fn worker_panic_payload_is_propagated() {
+    let res = panic::catch_unwind(|| {
+        parstream::run(0..100u64, 2,
+            |x| {
+                if x == 5 { panic!("boom"); }
+                Ok::<_, ()>(x)
+            },
+            |_| Ok(()),
+        )
+    });
+    let payload = res.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+}
+
+#[test]
+fn report_panic_payload_is_propagated() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = panic::catch_unwind(|| {
+            parstream::run(0..1000u64, 4,
+                Ok::<_, ()>,
+                |y| {
+                    if y == 3 { panic!("report boom"); }
+                    Ok(())
+                },
+            )
+        });
+        tx.send(res).unwrap();
+    });
+    let res = rx.recv_timeout(Duration::from_secs(10))
+        .expect("run has not returned in time");
+    let payload = res.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"report boom"));
+}
+
+#[test]
+fn run_checked_returns_report_panic() {
+    let res = parstream::run_checked(0..100u64, 4,
+        Ok::<_, ()>,
+        |y| {
+            if y == 3 { panic!("report boom"); }
+            Ok(())
+        },
+    );
+    match res {
+        Err(parstream::RunError::ReportPanic(payload)) => {
+            assert_eq!(payload.downcast_ref::<&str>(), Some(&"report boom"));
+        },
+        _ => panic!("unexpected result"),
+    }
+}
+
+#[test]
+fn report_panic_on_third_item_stops_workers() {
+    let computed = AtomicUsize::new(0);
+    let mut reported = Vec::new();
+    let res = parstream::run_checked(0..100_000u64, 4,
+        |x| {
+            computed.fetch_add(1, Ordering::Relaxed);
+            Ok::<_, ()>(x)
+        },
+        |y| {
+            if reported.len() == 2 { panic!("third item"); }
+            reported.push(y);
+            Ok(())
+        },
+    );
+    match res {
+        Err(parstream::RunError::ReportPanic(payload)) => {
+            assert_eq!(payload.downcast_ref::<&str>(), Some(&"third item"));
+        },
+        _ => panic!("unexpected result"),
+    }
+    assert_eq!(reported, vec![0, 1]);
+    assert!(computed.load(Ordering::Relaxed) < 100_000);
+}
+
+/// Iterator which panics on the third call of `next`.
+struct PanickingIter(u64);
+
+impl Iterator for PanickingIter {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.0 += 1;
+        if self.0 == 3 { panic!("source panic"); }
+        Some(self.0)
+    }
+}
+
+#[test]
+fn source_panic_is_propagated() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = panic::catch_unwind(|| {
+            parstream::run(PanickingIter(0), 4, Ok::<u64, ()>, |_| Ok(()))
+        });
+        tx.send(res.map_err(|p| p.downcast_ref::<&str>().copied())).unwrap();
+    });
+    let res = rx.recv_timeout(Duration::from_secs(10))
+        .expect("run has not returned in time");
+    assert_eq!(res.unwrap_err(), Some("source panic"));
+}
+
+#[test]
+fn source_panic_is_returned_by_checked_runs() {
+    use parstream::{Config, RunError};
+
+    let mut ys = Vec::new();
+    let res = Config::new()
+        .threads(4)
+        .run_checked(PanickingIter(0), Ok::<u64, ()>, |y| {
+            ys.push(y);
+            Ok(())
+        });
+    match res {
+        Err(RunError::SourcePanic(p)) => {
+            assert_eq!(p.downcast_ref::<&str>(), Some(&"source panic"));
+        },
+        res => panic!("unexpected result: {:?}", res),
+    }
+    assert!(ys.len() <= 2);
+
+    // elements are taken from the iterator before workers are spawned
+    let res = Config::new()
+        .threads(4)
+        .prefetch(8)
+        .run_checked(PanickingIter(0), Ok::<u64, ()>, |_| Ok(()));
+    assert!(matches!(res, Err(RunError::SourcePanic(_))));
+
+    let res = panic::catch_unwind(|| {
+        Config::new()
+            .threads(4)
+            .run_inline_report(PanickingIter(0), Ok::<u64, ()>, |_| Ok(()))
+    });
+    let payload = res.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"source panic"));
+}
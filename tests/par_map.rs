@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn yields_ordered_results() {
+    let xs: Vec<u64> = vec![50, 4, 3, 2, 1, 0, 1, 2, 3, 4, 5];
+    let expected: Vec<Result<u64, ()>> = xs.iter().map(|x| Ok(x*x)).collect();
+    let ys: Vec<Result<u64, ()>> = parstream::par_map(xs, 4, |x| {
+        std::thread::sleep(std::time::Duration::from_millis(x));
+        Ok(x*x)
+    }).collect();
+    assert_eq!(ys, expected);
+}
+
+#[test]
+fn error_ends_iteration() {
+    let ys: Vec<Result<u64, u64>> = parstream::par_map(0..1000u64, 4, |x| {
+        if x == 10 { Err(x) } else { Ok(x) }
+    }).collect();
+    let (last, oks) = ys.split_last().unwrap();
+    assert_eq!(last, &Err(10));
+    let expected: Vec<Result<u64, u64>> = (0..oks.len() as u64).map(Ok).collect();
+    assert_eq!(oks, &expected[..]);
+}
+
+#[test]
+fn early_drop_stops_workers() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let c = counter.clone();
+    let mut iter = parstream::par_map(0..10_000u64, 4, move |x| {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        c.fetch_add(1, Ordering::Relaxed);
+        Ok::<_, ()>(x)
+    });
+    assert_eq!(iter.next(), Some(Ok(0)));
+    drop(iter);
+    let processed = counter.load(Ordering::Relaxed);
+    assert!(processed < 100);
+    // all threads were joined, so counter will not change anymore
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert_eq!(counter.load(Ordering::Relaxed), processed);
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn worker_panic_is_propagated() {
+    let iter = parstream::par_map(0..100u64, 4, |x| {
+        if x == 5 { panic!("boom"); }
+        Ok::<_, ()>(x)
+    });
+    for _ in iter {}
+}
+
+/// Sets flag after being dropped together with the worker closure.
+struct DropFlag(Arc<AtomicUsize>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn early_drop_joins_threads() {
+    let processed = Arc::new(AtomicUsize::new(0));
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let flag = DropFlag(dropped.clone());
+    let p = processed.clone();
+    let iter = parstream::par_map(0.., 4, move |x: u64| {
+        let _ = &flag;
+        p.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        Ok::<_, ()>(x)
+    });
+    let ys: Vec<_> = iter.take(5).collect();
+    assert_eq!(ys, (0..5).map(Ok).collect::<Vec<_>>());
+    // `take` drops the iterator, closure is dropped after the run thread
+    // has exited
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    let n = processed.load(Ordering::SeqCst);
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert_eq!(processed.load(Ordering::SeqCst), n);
+}
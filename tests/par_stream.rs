@@ -0,0 +1,108 @@
+#![cfg(feature = "futures")]
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, Wake};
+use std::thread;
+use std::time::Duration;
+
+use futures_core::Stream;
+
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match Pin::new(&mut *stream).poll_next(&mut cx) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn ordered_results() {
+    let mut stream = parstream::par_stream(0..1000u64, 4, |x| {
+        if x % 7 == 0 { thread::sleep(Duration::from_millis(1)); }
+        Ok::<_, ()>(x*x)
+    });
+    let mut ys = Vec::new();
+    while let Some(y) = next(&mut stream) {
+        ys.push(y.unwrap());
+    }
+    assert_eq!(ys, (0..1000u64).map(|x| x*x).collect::<Vec<_>>());
+    assert!(next(&mut stream).is_none());
+}
+
+#[test]
+fn error_ends_stream() {
+    let mut stream = parstream::par_stream(0..1000u64, 4, |x| {
+        if x == 500 { Err(x) } else { Ok(x) }
+    });
+    let mut ys = Vec::new();
+    while let Some(y) = next(&mut stream) {
+        ys.push(y);
+    }
+    let (last, prefix) = ys.split_last().unwrap();
+    assert_eq!(last, &Err(500));
+    for (i, y) in prefix.iter().enumerate() {
+        assert_eq!(y, &Ok(i as u64));
+    }
+}
+
+#[test]
+fn drop_stops_workers() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let c = counter.clone();
+    let mut stream = parstream::par_stream(0..10_000u64, 4, move |x| {
+        c.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(1));
+        Ok::<_, ()>(x)
+    });
+    assert_eq!(next(&mut stream), Some(Ok(0)));
+    drop(stream);
+    thread::sleep(Duration::from_millis(100));
+    let n = counter.load(Ordering::Relaxed);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(counter.load(Ordering::Relaxed), n);
+    assert!(n < 10_000);
+}
+
+#[test]
+#[should_panic(expected = "worker panic")]
+fn worker_panic_is_propagated() {
+    let mut stream = parstream::par_stream(0..100u64, 4, |x| {
+        if x == 10 { panic!("worker panic") }
+        Ok::<_, ()>(x)
+    });
+    while next(&mut stream).is_some() {}
+}
+
+struct DropFlag(Arc<AtomicUsize>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn drop_joins_threads() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let flag = DropFlag(dropped.clone());
+    let mut stream = parstream::par_stream(0.., 4, move |x: u64| {
+        let _ = &flag;
+        thread::sleep(Duration::from_millis(1));
+        Ok::<_, ()>(x)
+    });
+    assert_eq!(next(&mut stream), Some(Ok(0)));
+    drop(stream);
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+}
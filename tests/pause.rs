@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use parstream::{Config, PauseHandle};
+
+#[test]
+fn pause_stalls_progress() {
+    let progress = Arc::new(AtomicUsize::new(0));
+    let p = progress.clone();
+    let pause = PauseHandle::new();
+    let handle = Config::new()
+        .threads(4)
+        .pause_handle(pause.clone())
+        .on_progress(move |n| p.store(n, Ordering::Relaxed))
+        .spawn_run(0..1000u64,
+            |x| {
+                thread::sleep(Duration::from_millis(1));
+                Ok::<u64, ()>(x)
+            },
+            |_| Ok(()),
+        );
+
+    while progress.load(Ordering::Relaxed) < 10 {
+        thread::sleep(Duration::from_millis(1));
+    }
+    pause.pause();
+    assert!(pause.is_paused());
+    // let elements which are already in flight to finish
+    thread::sleep(Duration::from_millis(100));
+    let stalled = progress.load(Ordering::Relaxed);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(progress.load(Ordering::Relaxed), stalled);
+    assert!(stalled < 1000);
+
+    pause.resume();
+    assert_eq!(handle.join(), Ok(1000));
+    assert_eq!(progress.load(Ordering::Relaxed), 1000);
+}
+
+#[test]
+fn paused_run_can_be_cancelled() {
+    let (token, cancel) = parstream::CancelToken::new();
+    let pause = PauseHandle::new();
+    pause.pause();
+    let handle = Config::new()
+        .threads(2)
+        .pause_handle(pause)
+        .cancel_token(token)
+        .spawn_run(0.., |x: u64| Ok::<u64, ()>(x), |_| Ok(()));
+    thread::sleep(Duration::from_millis(20));
+    cancel.cancel();
+    assert_eq!(handle.join(), Ok(0));
+}
@@ -0,0 +1,95 @@
+use parstream::Config;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn order_is_preserved() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_pipeline(0..200u64, 4,
+        |x| {
+            thread::sleep(Duration::from_micros((x * 7) % 50));
+            Ok(x + 1)
+        },
+        |m| {
+            thread::sleep(Duration::from_micros((m * 13) % 50));
+            Ok(m * 2)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(200));
+    assert_eq!(ys, (0..200u64).map(|x| (x + 1) * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn single_thread() {
+    let res: Result<usize, ()> = parstream::run_pipeline(0..10u64, 1,
+        Ok, Ok, |_| Ok(()));
+    assert_eq!(res, Ok(10));
+}
+
+#[test]
+fn intermediate_results_are_bounded() {
+    // number of intermediate results which were not consumed yet
+    let live = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .max_in_flight(16)
+        .run_pipeline(0..2000u64,
+            |x| {
+                let n = live.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(n, Ordering::SeqCst);
+                Ok(x)
+            },
+            |m| {
+                thread::sleep(Duration::from_micros(50));
+                live.fetch_sub(1, Ordering::SeqCst);
+                Ok(m)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(2000));
+    let peak = peak.load(Ordering::SeqCst);
+    // in-flight elements of both stages and the channel between them
+    assert!(peak < 64, "peak: {}", peak);
+}
+
+#[test]
+fn errors_of_both_stages() {
+    let res = parstream::run_pipeline(0..100u64, 4,
+        |x| if x == 50 { Err(x) } else { Ok(x) },
+        Ok,
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(50));
+
+    let res = parstream::run_pipeline(0..100u64, 4,
+        Ok,
+        |m| if m == 30 { Err(m) } else { Ok(m) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(30));
+
+    let mut n = 0;
+    let res = parstream::run_pipeline(0..100u64, 4, Ok, Ok,
+        |y| { n += 1; if y == 10 { Err(y) } else { Ok(()) } });
+    assert_eq!(res, Err(10));
+    assert_eq!(n, 11);
+}
+
+#[test]
+fn panic_of_second_stage_is_propagated() {
+    let res = panic::catch_unwind(|| {
+        parstream::run_pipeline(0..100u64, 4, Ok::<u64, ()>,
+            |m| {
+                if m == 10 { panic!("stage panic") }
+                Ok(m)
+            },
+            |_| Ok(()),
+        )
+    });
+    let payload = res.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"stage panic"));
+}
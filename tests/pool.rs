@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use parstream::{Config, Pool, RunError};
+
+#[test]
+fn runs_reuse_threads() {
+    let pool = Pool::new(4);
+    let ids = Mutex::new(HashSet::new());
+    for _ in 0..20 {
+        let mut ys = Vec::new();
+        let res: Result<usize, ()> = pool.run(0..100u64,
+            |x| {
+                ids.lock().unwrap().insert(thread::current().id());
+                Ok(x*x)
+            },
+            |y| {
+                ids.lock().unwrap().insert(thread::current().id());
+                ys.push(y);
+                Ok(())
+            },
+        );
+        assert_eq!(res, Ok(100));
+        assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+    }
+    assert!(ids.lock().unwrap().len() <= 5);
+}
+
+#[test]
+fn pool_survives_errors_and_panics() {
+    let pool = Pool::new(2);
+    let res = pool.run(0..100u64, |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(()));
+    assert_eq!(res, Err(10));
+    let res = pool.run_checked(0..100u64,
+        |x| if x == 10 { panic!("worker panic") } else { Ok::<_, ()>(x) },
+        |_| Ok(()));
+    match res {
+        Err(RunError::WorkerPanic(_)) => (),
+        _ => panic!("unexpected result"),
+    }
+    let res: Result<usize, ()> = pool.run(0..100u64, Ok, |_| Ok(()));
+    assert_eq!(res, Ok(100));
+}
+
+#[test]
+fn concurrent_runs_are_serialized() {
+    let pool = Arc::new(Pool::with_config(Config::new().threads(2)
+        .thread_name("pool")));
+    let handles: Vec<_> = (0..4).map(|_| {
+        let pool = pool.clone();
+        thread::spawn(move || {
+            pool.run(0..100u64,
+                |x| {
+                    let name = thread::current().name().unwrap().to_owned();
+                    assert!(name.starts_with("pool-pool-"));
+                    Ok::<_, ()>(x)
+                },
+                |_| Ok(()),
+            )
+        })
+    }).collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), Ok(100));
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn elements_are_prefetched() {
+    let taken = AtomicUsize::new(0);
+    let first = AtomicUsize::new(0);
+    let xs = (0..100u64).inspect(|_| { taken.fetch_add(1, Ordering::SeqCst); });
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::Config::new()
+        .threads(2)
+        .input_capacity(8)
+        .prefetch(6)
+        .run(xs,
+            |x| {
+                if x == 0 { first.store(taken.load(Ordering::SeqCst), Ordering::SeqCst) }
+                Ok(x)
+            },
+            |y| { ys.push(y); Ok(()) },
+        );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100).collect::<Vec<_>>());
+    assert!(first.load(Ordering::SeqCst) >= 6);
+}
+
+#[test]
+fn prefetch_is_limited_by_capacity() {
+    let taken = AtomicUsize::new(0);
+    let first = AtomicUsize::new(0);
+    let xs = (0..100u64).inspect(|_| { taken.fetch_add(1, Ordering::SeqCst); });
+    let res: Result<usize, ()> = parstream::Config::new()
+        .threads(1)
+        .input_capacity(0)
+        .prefetch(50)
+        .run(xs,
+            |x| {
+                if x == 0 { first.store(taken.load(Ordering::SeqCst), Ordering::SeqCst) }
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(100));
+    assert!(first.load(Ordering::SeqCst) < 50);
+}
+
+#[test]
+fn short_input() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::Config::new()
+        .threads(4)
+        .prefetch(8)
+        .run_inline_report(0..3u64, Ok, |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(3));
+    assert_eq!(ys, vec![0, 1, 2]);
+}
@@ -0,0 +1,87 @@
+use parstream::{Config, ReportPressure};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn block_is_default() {
+    let config = Config::new();
+    assert!(format!("{:?}", config).contains("report_pressure: Block"));
+}
+
+#[test]
+fn spill_does_not_block_workers() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .report_capacity(1)
+        .report_pressure(ReportPressure::Spill)
+        .run(0..100u64, |x| Ok(x * 2), |y| {
+            if y == 0 { thread::sleep(Duration::from_millis(50)); }
+            ys.push(y);
+            Ok(())
+        });
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100u64).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn shed_drops_buffered_results() {
+    let dropped = Arc::new(Mutex::new(Vec::new()));
+    let d = dropped.clone();
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .report_capacity(4)
+        .report_pressure(ReportPressure::shed(move |i| {
+            d.lock().unwrap().push(i);
+        }))
+        .run(0..50u64, |x| {
+            if x == 0 { thread::sleep(Duration::from_millis(200)); }
+            Ok(x)
+        }, |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(50));
+    let dropped = dropped.lock().unwrap();
+    assert!(!dropped.is_empty());
+    assert!(!dropped.contains(&0));
+    // reported results are still ordered and complement dropped ones
+    assert!(ys.windows(2).all(|w| w[0] < w[1]));
+    let mut all: Vec<u64> = ys.iter().copied()
+        .chain(dropped.iter().map(|&i| i as u64))
+        .collect();
+    all.sort_unstable();
+    assert_eq!(all, (0..50).collect::<Vec<_>>());
+}
+
+#[test]
+fn shed_keeps_errors() {
+    let res = Config::new()
+        .threads(4)
+        .report_capacity(2)
+        .report_pressure(ReportPressure::shed(|_| ()))
+        .run(0..50u64, |x| {
+            if x == 0 { thread::sleep(Duration::from_millis(100)); }
+            if x == 5 { Err(x) } else { Ok(x) }
+        }, |_| Ok(()));
+    assert_eq!(res, Err(5));
+}
+
+#[test]
+fn shed_with_in_flight_limit() {
+    let dropped = Arc::new(Mutex::new(0));
+    let d = dropped.clone();
+    let mut n = 0;
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .report_capacity(2)
+        .max_in_flight(4)
+        .report_pressure(ReportPressure::shed(move |_| {
+            *d.lock().unwrap() += 1;
+        }))
+        .run(0..200u64, |x| {
+            if x % 50 == 0 { thread::sleep(Duration::from_millis(20)); }
+            Ok(x)
+        }, |_| { n += 1; Ok(()) });
+    assert_eq!(res, Ok(200));
+    assert_eq!(n + *dropped.lock().unwrap(), 200);
+}
@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+
+use parstream::Config;
+
+#[test]
+fn high_priority_is_processed_first() {
+    let order = Mutex::new(Vec::new());
+    let xs = (0..8u64).map(|x| (x >= 4, x));
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(1)
+        .input_capacity(8)
+        .run_prioritized(xs,
+            |x| { order.lock().unwrap().push(x); Ok(x) },
+            |y| { ys.push(y); Ok(()) },
+        );
+    assert_eq!(res, Ok(8));
+    assert_eq!(ys, (0..8).collect::<Vec<_>>());
+    assert_eq!(*order.lock().unwrap(), vec![4, 5, 6, 7, 0, 1, 2, 3]);
+}
+
+#[test]
+fn priority_is_limited_by_lookahead() {
+    let order = Mutex::new(Vec::new());
+    // the only high priority element is beyond the buffered window
+    let xs = (0..20u64).map(|x| (x == 19, x));
+    let res: Result<usize, ()> = Config::new()
+        .threads(1)
+        .input_capacity(2)
+        .run_prioritized(xs,
+            |x| { order.lock().unwrap().push(x); Ok(x) },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(20));
+    let order = order.into_inner().unwrap();
+    assert_eq!(order[0], 0);
+    assert_eq!(order.len(), 20);
+}
+
+#[test]
+fn prioritized_error() {
+    let xs = (0..100u64).map(|x| (x % 2, x));
+    let res = parstream::run_prioritized(xs, 4,
+        |x| if x == 50 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(50));
+}
+
+#[test]
+fn bounded_window_is_not_applied() {
+    // the first element has the lowest priority, so it's sent last
+    let xs = (0..10u64).map(|x| (x, x));
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .max_reorder_buffer(2)
+        .max_in_flight(2)
+        .run_prioritized(xs, Ok, |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(10));
+    assert_eq!(ys, (0..10).collect::<Vec<_>>());
+}
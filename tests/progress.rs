@@ -0,0 +1,22 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn progress_is_reported_for_every_element() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let c = calls.clone();
+    let xs: &[u64] = &[50, 4, 3, 2, 1, 0, 1, 2, 3, 4, 5];
+    let res: Result<usize, ()> = parstream::Config::new()
+        .threads(4)
+        .on_progress(move |n| c.lock().unwrap().push(n))
+        .run(xs,
+            |x| {
+                thread::sleep(Duration::from_millis(*x));
+                Ok(*x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(xs.len()));
+    assert_eq!(*calls.lock().unwrap(), (1..=xs.len()).collect::<Vec<_>>());
+}
@@ -0,0 +1,43 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use parstream::{CancelToken, Config};
+
+#[test]
+fn dispatch_is_spaced() {
+    let starts = Mutex::new(Vec::new());
+    let t = Instant::now();
+    let res: Result<usize, ()> = Config::new()
+        .threads(4)
+        .max_rate(200)
+        .run(0..20u64,
+            |x| {
+                starts.lock().unwrap().push(Instant::now());
+                Ok(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(20));
+    // 20 elements at 200 per second take at least 95ms to dispatch
+    assert!(t.elapsed() >= Duration::from_millis(95));
+    let mut starts = starts.into_inner().unwrap();
+    starts.sort();
+    let span = *starts.last().unwrap() - starts[0];
+    assert!(span >= Duration::from_millis(90));
+}
+
+#[test]
+fn throttled_feeder_respects_cancellation() {
+    let (token, handle) = CancelToken::new();
+    let t = Instant::now();
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .max_rate(1)
+        .cancel_token(token)
+        .run(0..100u64, Ok, |y| {
+            if y == 0 { handle.cancel(); }
+            Ok(())
+        });
+    assert_eq!(res, Ok(1));
+    assert!(t.elapsed() < Duration::from_millis(900));
+}
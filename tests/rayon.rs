@@ -0,0 +1,81 @@
+#![cfg(feature = "rayon")]
+use std::sync::Mutex;
+
+use parstream::Config;
+
+fn pool(threads: usize) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("rayon-{}", i))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn runs_on_pool_threads() {
+    let pool = pool(3);
+    let names = Mutex::new(Vec::new());
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_in_pool(&pool, 0..100u64,
+        |x| {
+            let name = std::thread::current().name().unwrap().to_owned();
+            names.lock().unwrap().push(name);
+            Ok(x*x)
+        },
+        |y| {
+            let name = std::thread::current().name().unwrap().to_owned();
+            assert!(name.starts_with("rayon-"));
+            ys.push(y);
+            Ok(())
+        },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100u64).map(|x| x*x).collect::<Vec<_>>());
+    assert!(names.into_inner().unwrap().iter().all(|n| n.starts_with("rayon-")));
+}
+
+#[test]
+fn error_and_panics_match_run() {
+    let pool = pool(4);
+    let res = parstream::run_in_pool(&pool, 0..100u64,
+        |x| if x == 10 || x == 50 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(10));
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = parstream::run_in_pool(&pool, 0..100u64,
+            |x| if x == 10 { panic!("worker panic") } else { Ok::<u64, ()>(x) },
+            |_| Ok(()),
+        );
+    }));
+    assert!(res.is_err());
+    // pool is still usable after the panic
+    let res: Result<usize, ()> = Config::new().threads(2)
+        .run_in_pool(&pool, 0..10u64, Ok, |_| Ok(()));
+    assert_eq!(res, Ok(10));
+}
+
+#[test]
+#[should_panic(expected = "rayon pool must have at least 5 threads")]
+fn small_pool_panics() {
+    let pool = pool(2);
+    let _: Result<usize, ()> = Config::new().threads(4)
+        .run_in_pool(&pool, 0..10u64, Ok, |_| Ok(()));
+}
+
+#[test]
+fn pool_as_executor() {
+    let pool = std::sync::Arc::new(pool(3));
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .executor(pool)
+        .run(0..100u64, Ok,
+            |_| {
+                let name = std::thread::current().name().unwrap().to_owned();
+                assert!(name.starts_with("rayon-"));
+                Ok(())
+            },
+        );
+    assert_eq!(res, Ok(100));
+}
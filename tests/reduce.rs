@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+#[test]
+fn sum() {
+    let res: Result<u64, ()> = parstream::run_reduce(0..1000u64, 4,
+        || 0, |acc, y| acc + y, |x| Ok(x * 3), |a, b| a + b);
+    assert_eq!(res, Ok((0..1000u64).map(|x| x * 3).sum()));
+}
+
+#[test]
+fn histogram() {
+    let res: Result<HashMap<u64, usize>, ()> = parstream::run_reduce(
+        0..1000u64, 4,
+        HashMap::new,
+        |mut acc, y| { *acc.entry(y).or_insert(0) += 1; acc },
+        |x| Ok(x % 7),
+        |mut a, b| {
+            for (k, v) in b { *a.entry(k).or_insert(0) += v }
+            a
+        },
+    );
+    let hist = res.unwrap();
+    assert_eq!(hist.len(), 7);
+    assert_eq!(hist.values().sum::<usize>(), 1000);
+    assert_eq!(hist[&0], 143);
+}
+
+#[test]
+fn empty_input() {
+    let res: Result<u64, ()> = parstream::run_reduce(0..0u64, 4,
+        || 5, |acc, y| acc + y, Ok, |a, b| a.max(b));
+    assert_eq!(res, Ok(5));
+}
+
+#[test]
+fn error() {
+    let res = parstream::run_reduce(0..1000u64, 4,
+        || 0, |acc, y| acc + y,
+        |x| if x == 500 { Err(x) } else { Ok(x) },
+        |a, b| a + b);
+    assert_eq!(res, Err(500));
+}
+
+#[test]
+#[should_panic(expected = "fold panic")]
+fn fold_panic() {
+    let _: Result<u64, ()> = parstream::run_reduce(0..100u64, 4,
+        || 0,
+        |acc, y| if y == 50 { panic!("fold panic") } else { acc + y },
+        Ok, |a, b| a + b);
+}
+
+#[test]
+fn unordered_sum() {
+    let res: Result<u64, ()> = parstream::reduce_unordered(0..1000u64, 4, 0,
+        |x| Ok(x * 3), |acc, y| *acc += y);
+    assert_eq!(res, Ok((0..1000u64).map(|x| x * 3).sum()));
+}
+
+#[test]
+fn unordered_slow_element_does_not_block() {
+    use std::{thread, time::Duration};
+    let mut order = Vec::new();
+    let res: Result<usize, ()> = parstream::reduce_unordered(0..20u64, 4, 0,
+        |x| {
+            if x == 0 { thread::sleep(Duration::from_millis(200)); }
+            Ok(x)
+        },
+        |acc, y| { *acc += 1; order.push(y) },
+    );
+    assert_eq!(res, Ok(20));
+    assert_eq!(order.last(), Some(&0));
+}
+
+#[test]
+fn unordered_error() {
+    let res = parstream::reduce_unordered(0..1000u64, 4, 0u64,
+        |x| if x == 500 { Err(x) } else { Ok(x) }, |acc, y| *acc += y);
+    assert_eq!(res, Err(500));
+}
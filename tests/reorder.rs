@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn reorder_buffer_is_bounded() {
+    const MAX: usize = 10;
+    let started = AtomicUsize::new(0);
+    let reported = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let mut ys = Vec::new();
+
+    let res: Result<usize, ()> = parstream::Config::new()
+        .threads(4)
+        .max_reorder_buffer(MAX)
+        .run(0..1000u64,
+            |x| {
+                let s = started.fetch_add(1, Ordering::SeqCst) + 1;
+                let in_flight = s - reported.load(Ordering::SeqCst);
+                peak.fetch_max(in_flight, Ordering::SeqCst);
+                if x == 0 { thread::sleep(Duration::from_millis(200)); }
+                Ok(x)
+            },
+            |y| {
+                reported.fetch_add(1, Ordering::SeqCst);
+                ys.push(y);
+                Ok(())
+            },
+        );
+
+    assert_eq!(res, Ok(1000));
+    assert_eq!(ys, (0..1000).collect::<Vec<_>>());
+    assert!(peak.load(Ordering::SeqCst) <= MAX);
+}
+
+#[test]
+fn bounded_reorder_buffer_with_error() {
+    let res = parstream::Config::new()
+        .threads(4)
+        .max_reorder_buffer(2)
+        .run(0..1000u64,
+            |x| {
+                if x == 0 { thread::sleep(Duration::from_millis(50)); }
+                if x == 1 { Err(x) } else { Ok(x) }
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Err(1));
+}
+
+#[test]
+fn huge_size_hint() {
+    // reorder buffer must not preallocate space for all elements
+    let res = parstream::run(0..usize::MAX, 4,
+        |x| if x == 100 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(100));
+}
+
+#[test]
+fn inexact_size_hint() {
+    let xs = (0..1000u64).filter(|x| x % 3 == 0);
+    let ys = parstream::collect_ordered(xs, 4, |x| Ok::<u64, ()>(2 * x));
+    let expected: Vec<u64> = (0..1000).filter(|x| x % 3 == 0)
+        .map(|x| 2 * x).collect();
+    assert_eq!(ys, Ok(expected));
+}
+
+#[test]
+fn run_collect_returns_count() {
+    let res = parstream::run_collect(0..1000u64, 4, |x| Ok::<u64, ()>(x * x));
+    let (n, ys) = res.unwrap();
+    assert_eq!(n, 1000);
+    assert_eq!(ys, (0..1000u64).map(|x| x * x).collect::<Vec<_>>());
+
+    let res = parstream::run_collect(0..1000u64, 4,
+        |x| if x == 500 { Err(x) } else { Ok(x) });
+    assert_eq!(res, Err(500));
+}
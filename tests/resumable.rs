@@ -0,0 +1,61 @@
+use parstream::{CancelToken, RunError};
+
+#[test]
+fn complete_run() {
+    let mut ys = Vec::new();
+    let res = parstream::run_resumable(0..100u64, 4,
+        |x| Ok::<u64, ()>(x * 2),
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res.unwrap(), 100);
+    assert_eq!(ys, (0..100u64).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn resume_after_error() {
+    let mut ys = Vec::new();
+    let res = parstream::Config::new()
+        .threads(4)
+        .max_in_flight(16)
+        .run_resumable(0..1000u64,
+            |x| if x == 100 { Err(x) } else { Ok(x) },
+            |y| { ys.push(y); Ok(()) },
+        );
+    let int = res.unwrap_err();
+    assert!(matches!(int.error, Some(RunError::Callback(100))));
+    assert_eq!(int.reported, 100);
+    assert_eq!(ys, (0..100).collect::<Vec<_>>());
+    assert!(int.dispatched > 100 && int.dispatched <= 100 + 16);
+
+    // retry elements which were not reported
+    let lost = int.reported as u64..int.dispatched as u64;
+    let res = parstream::run_resumable(lost.chain(int.rest), 4,
+        Ok::<u64, ()>,
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res.unwrap(), 900);
+    assert_eq!(ys, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn cancelled_run() {
+    let (token, handle) = CancelToken::new();
+    let res = parstream::Config::new()
+        .threads(4)
+        .max_in_flight(16)
+        .cancel_token(token)
+        .run_resumable(0..1000u64,
+            Ok::<u64, ()>,
+            |y| { if y == 9 { handle.cancel() } Ok(()) },
+        );
+    let int = res.unwrap_err();
+    // buffered results may be reported after cancellation
+    match int.error {
+        Some(RunError::Cancelled(n)) => assert_eq!(n, int.reported),
+        _ => panic!("unexpected error"),
+    }
+    assert!(int.reported >= 10);
+    assert!(int.dispatched <= int.reported + 16);
+    let mut rest = int.rest;
+    assert_eq!(rest.next(), Some(int.dispatched as u64));
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[test]
+fn item_fails_twice_then_succeeds() {
+    let attempts = Mutex::new(HashMap::new());
+    let mut ys = Vec::new();
+    let res: Result<usize, String> = parstream::run_with_retries(0..100u64, 4,
+        2, Duration::from_millis(1),
+        |x| {
+            let mut attempts = attempts.lock().unwrap();
+            let n = attempts.entry(x).or_insert(0);
+            *n += 1;
+            if x % 10 == 0 && *n <= 2 {
+                return Err(format!("attempt {} for {}", n, x));
+            }
+            Ok(x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100).collect::<Vec<_>>());
+    assert_eq!(attempts.lock().unwrap()[&10], 3);
+    assert_eq!(attempts.lock().unwrap()[&11], 1);
+}
+
+#[test]
+fn last_attempt_error_is_returned() {
+    let attempts = Mutex::new(0);
+    let res = parstream::run_with_retries(0..10u64, 2, 3, Duration::from_secs(0),
+        |x| {
+            if x == 5 {
+                let mut attempts = attempts.lock().unwrap();
+                *attempts += 1;
+                return Err(*attempts);
+            }
+            Ok(x)
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(4));
+}
+
+#[test]
+fn cancel_stops_retries() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Instant;
+
+    let (token, handle) = parstream::CancelToken::new();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(30));
+        handle.cancel();
+    });
+    let attempts = AtomicUsize::new(0);
+    let start = Instant::now();
+    let res: Result<usize, ()> = parstream::Config::new()
+        .threads(2)
+        .cancel_token(token)
+        .run_with_retries(0..4u64, 10, Duration::from_millis(50),
+            |_| { attempts.fetch_add(1, Ordering::SeqCst); Err::<u64, _>(()) },
+            |_| Ok(()),
+        );
+    canceller.join().unwrap();
+    assert_eq!(res, Ok(0));
+    // without the check every element would take 500ms
+    assert!(start.elapsed() < Duration::from_millis(400));
+    assert!(attempts.load(Ordering::SeqCst) <= 4);
+}
@@ -0,0 +1,29 @@
+use parstream::Config;
+
+#[test]
+fn results_are_reported_from_the_back() {
+    let xs = ["a", "bb", "ccc", "dddd", "eeeee"];
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_rev(&xs, 3,
+        |s| Ok(s.len()),
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(5));
+    assert_eq!(ys, vec![5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn error_of_the_last_failed_element_is_returned() {
+    let res = Config::new().threads(4).run_rev(0..100u64,
+        |x| if x == 20 || x == 80 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(80));
+}
+
+#[test]
+fn empty_input() {
+    let res: Result<usize, ()> = parstream::run_rev(Vec::<u64>::new(), 2,
+        Ok, |_| Ok(()));
+    assert_eq!(res, Ok(0));
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "async")]
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread;
+
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(mut fut: F) -> F::Output {
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    // SAFETY: `fut` is not moved after being pinned
+    let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn run_async_completes() {
+    let res: Result<usize, ()> = block_on(parstream::run_async(
+        0..100u64, 4,
+        |x| {
+            thread::sleep(std::time::Duration::from_millis(1));
+            Ok(x*x)
+        },
+        |_| Ok(()),
+    ));
+    assert_eq!(res, Ok(100));
+}
+
+#[test]
+fn run_async_error() {
+    let res = block_on(parstream::run_async(
+        0..100u64, 4,
+        |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    ));
+    assert_eq!(res, Err(10));
+}
+
+#[test]
+#[should_panic(expected = "worker panic")]
+fn run_async_panic() {
+    let _ = block_on(parstream::run_async(
+        0..100u64, 4,
+        |x| if x == 10 { panic!("worker panic") } else { Ok::<_, ()>(x) },
+        |_| Ok(()),
+    ));
+}
@@ -0,0 +1,41 @@
+/// Element which is `Sync`, but not `Send` and not `Clone`.
+struct Big {
+    data: [u64; 64],
+    _not_send: std::marker::PhantomData<std::sync::MutexGuard<'static, ()>>,
+}
+
+#[test]
+fn run_ref_preserves_order() {
+    let xs: Vec<Big> = (0..100u64)
+        .map(|i| Big { data: [i; 64], _not_send: Default::default() })
+        .collect();
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_ref(&xs, 4,
+        |x| Ok(x.data.iter().sum::<u64>()),
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100).map(|i| i * 64).collect::<Vec<_>>());
+}
+
+#[test]
+fn results_may_borrow_slice() {
+    let xs: Vec<String> = (0..10).map(|i| format!("{:02}", i)).collect();
+    let mut ys: Vec<&str> = Vec::new();
+    let res: Result<usize, ()> = parstream::run_ref(&xs, 2,
+        |s| Ok(&s[1..]),
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(10));
+    assert_eq!(ys, vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+}
+
+#[test]
+fn run_ref_error() {
+    let xs: Vec<u64> = (0..100).collect();
+    let res = parstream::run_ref(&xs, 4,
+        |&x| if x == 7 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(7));
+}
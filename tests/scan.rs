@@ -0,0 +1,38 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn prefix_sums_are_ordered() {
+    let mut sums = Vec::new();
+    let res: Result<u64, ()> = parstream::scan_ordered(0..100u64, 8, 0,
+        |x| {
+            thread::sleep(Duration::from_micros((100 - x) * 20));
+            Ok(x)
+        },
+        |acc, y| { *acc += y; Ok(*acc) },
+        |s| { sums.push(s); Ok(()) },
+    );
+    assert_eq!(res, Ok((0..100).sum()));
+    let expected: Vec<u64> = (0..100u64).map(|x| x * (x + 1) / 2).collect();
+    assert_eq!(sums, expected);
+}
+
+#[test]
+fn step_error_stops_run() {
+    let mut reported = 0;
+    let res = parstream::scan_ordered(0..100u64, 4, 0u64, Ok,
+        |acc, y| if y == 50 { Err(*acc) } else { *acc += y; Ok(y) },
+        |_| { reported += 1; Ok(()) },
+    );
+    assert_eq!(res, Err((0..50).sum()));
+    assert_eq!(reported, 50);
+}
+
+#[test]
+fn report_error_stops_run() {
+    let res = parstream::scan_ordered(0..100u64, 4, 0u64, Ok,
+        |acc, y| { *acc += y; Ok(*acc) },
+        |s| if s > 100 { Err(s) } else { Ok(()) },
+    );
+    assert_eq!(res, Err(105));
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "crossbeam")]
+use std::panic;
+
+use crossbeam_utils::thread;
+use parstream::Config;
+
+#[test]
+fn runs_share_caller_scope() {
+    let factor = 3u64;
+    let (a, b) = thread::scope(|s| {
+        let h = s.spawn(|s| {
+            let mut ys = Vec::new();
+            let res: Result<usize, ()> = parstream::run_in_scope(s, 0..50u64,
+                2, |x| Ok(x*factor), |y| { ys.push(y); Ok(()) });
+            assert_eq!(res, Ok(50));
+            ys
+        });
+        let mut ys = Vec::new();
+        let res: Result<usize, ()> = Config::new().threads(3)
+            .run_in_scope(s, 0..50u64, |x| Ok(x+factor),
+                |y| { ys.push(y); Ok(()) });
+        assert_eq!(res, Ok(50));
+        (h.join().unwrap(), ys)
+    }).unwrap();
+    assert_eq!(a, (0..50).map(|x| x*factor).collect::<Vec<_>>());
+    assert_eq!(b, (0..50).map(|x| x+factor).collect::<Vec<_>>());
+}
+
+#[test]
+fn errors_and_panics_match_run() {
+    thread::scope(|s| {
+        let res = parstream::run_in_scope(s, 0..100u64, 4,
+            |x| if x == 10 { Err(x) } else { Ok(x) },
+            |_| Ok(()),
+        );
+        assert_eq!(res, Err(10));
+
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let _ = parstream::run_in_scope(s, 0..100u64, 4,
+                |x| if x == 10 { panic!("worker panic") } else { Ok::<u64, ()>(x) },
+                |_| Ok(()),
+            );
+        }));
+        assert!(res.is_err());
+    }).unwrap();
+}
@@ -0,0 +1,24 @@
+#![cfg(all(feature = "signal", unix))]
+use parstream::{CancelToken, Config, RunError};
+
+#[test]
+fn sigint_cancels_run() {
+    let token = CancelToken::ctrl_c().unwrap();
+    assert!(!token.is_cancelled());
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(4)
+        .max_in_flight(16)
+        .cancel_token(token.clone())
+        .run_checked(0.., |x: u64| Ok(x), |y| {
+            if y == 10 {
+                assert_eq!(unsafe { libc::raise(libc::SIGINT) }, 0);
+            }
+            Ok(())
+        });
+    match res {
+        Err(RunError::Cancelled(n)) => assert!(n >= 11),
+        res => panic!("unexpected result: {:?}", res),
+    }
+    assert!(token.is_cancelled());
+    assert!(CancelToken::ctrl_c().unwrap().is_cancelled());
+}
@@ -0,0 +1,32 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use parstream::{Config, RunError};
+
+#[test]
+fn hook_threads_can_borrow_stack_data() {
+    let factor = 3u64;
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .spawn_hook(|task| { thread::spawn(task); })
+        .run(0..50u64, |x| Ok(x*factor), |y| { ys.push(y); Ok(()) });
+    assert_eq!(res, Ok(50));
+    assert_eq!(ys, (0..50).map(|x| x*factor).collect::<Vec<_>>());
+}
+
+#[test]
+fn dropped_task_stops_run() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res: Result<usize, RunError<()>> = Config::new()
+            .threads(4)
+            .spawn_hook(drop)
+            .run_checked(0..1000u64, Ok, |_| Ok(()));
+        tx.send(matches!(res, Err(RunError::WorkerPanic(_)))).unwrap();
+    });
+    let is_panic = rx.recv_timeout(Duration::from_secs(10))
+        .expect("run has not returned in time");
+    assert!(is_panic);
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn spawn_run_joins() {
+    let sum = Arc::new(AtomicUsize::new(0));
+    let s = sum.clone();
+    let handle = parstream::spawn_run(0..100usize, 4,
+        |x| Ok::<usize, ()>(x*x),
+        move |y| { s.fetch_add(y, Ordering::Relaxed); Ok(()) },
+    );
+    assert_eq!(handle.join(), Ok(100));
+    assert_eq!(sum.load(Ordering::Relaxed), (0..100).map(|x| x*x).sum());
+}
+
+#[test]
+fn spawn_run_error() {
+    let handle = parstream::spawn_run(0..100u64, 4,
+        |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(handle.join(), Err(10));
+}
+
+#[test]
+fn spawn_run_cancel() {
+    let reported = Arc::new(AtomicUsize::new(0));
+    let r = reported.clone();
+    let handle = parstream::spawn_run(0.., 4,
+        |x: u64| {
+            thread::sleep(Duration::from_millis(1));
+            Ok::<u64, ()>(x)
+        },
+        move |_| { r.fetch_add(1, Ordering::Relaxed); Ok(()) },
+    );
+    while reported.load(Ordering::Relaxed) < 10 {
+        thread::sleep(Duration::from_millis(1));
+    }
+    handle.cancel();
+    let n = handle.join().unwrap();
+    assert!(n >= 10);
+    assert_eq!(n, reported.load(Ordering::Relaxed));
+}
+
+#[test]
+#[should_panic(expected = "worker panic")]
+fn spawn_run_propagates_panic() {
+    let handle = parstream::spawn_run(0..10u64, 2,
+        |x| if x == 5 { panic!("worker panic") } else { Ok::<u64, ()>(x) },
+        |_| Ok(()),
+    );
+    let _ = handle.join();
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "spill")]
+use std::convert::TryInto;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+fn encode(y: &u64, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&y.to_le_bytes());
+}
+
+fn decode(buf: &[u8]) -> Result<u64, io::Error> {
+    Ok(u64::from_le_bytes(buf.try_into().unwrap()))
+}
+
+fn slow_first(x: u64) -> Result<u64, io::Error> {
+    if x == 0 { thread::sleep(Duration::from_millis(200)); }
+    Ok(x*x)
+}
+
+#[test]
+fn spilled_results_are_reported_in_order() {
+    let mut encoded = 0;
+    let mut decoded = 0;
+    let mut ys = Vec::new();
+    let res = parstream::run_spilled(0..1000u64, 4, 10,
+        |y, buf| { encoded += 1; encode(y, buf) },
+        |buf| { decoded += 1; decode(buf) },
+        slow_first,
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res.unwrap(), 1000);
+    assert_eq!(ys, (0..1000u64).map(|x| x*x).collect::<Vec<_>>());
+    assert!(encoded > 0);
+    assert_eq!(encoded, decoded);
+}
+
+#[test]
+fn errors_are_not_spilled() {
+    let res = parstream::run_spilled(0..1000u64, 4, 2,
+        encode,
+        decode,
+        |x| match x {
+            0 => slow_first(x),
+            500 => Err(io::Error::new(io::ErrorKind::InvalidInput, "worker")),
+            _ => Ok(x),
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res.unwrap_err().to_string(), "worker");
+}
+
+#[test]
+fn decode_error_stops_run() {
+    let mut ys = Vec::new();
+    let res = parstream::run_spilled(0..1000u64, 4, 10,
+        encode,
+        |_| Err(io::Error::new(io::ErrorKind::InvalidData, "decode")),
+        slow_first,
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert!(ys.len() < 1000);
+}
@@ -0,0 +1,54 @@
+//! Spill files of a process share the same prefix, so this test is kept in
+//! a separate binary to not observe files of concurrently running tests.
+#![cfg(feature = "spill")]
+use std::convert::TryInto;
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+fn spill_files() -> usize {
+    let prefix = format!("parstream-{}-", process::id());
+    fs::read_dir(env::temp_dir()).unwrap()
+        .filter(|e| {
+            let name = e.as_ref().unwrap().file_name();
+            name.to_string_lossy().starts_with(&prefix)
+        })
+        .count()
+}
+
+fn run(fail: bool) -> (Result<usize, io::Error>, usize) {
+    let mut encoded = 0;
+    let res = parstream::run_spilled(0..1000u64, 4, 10,
+        |y: &u64, buf| {
+            encoded += 1;
+            buf.extend_from_slice(&y.to_le_bytes());
+        },
+        |buf| Ok(u64::from_le_bytes(buf.try_into().unwrap())),
+        |x| {
+            if x == 0 { thread::sleep(Duration::from_millis(200)); }
+            Ok(x)
+        },
+        |y| if fail && y == 0 {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "report"))
+        } else {
+            Ok(())
+        },
+    );
+    (res, encoded)
+}
+
+#[test]
+fn spill_file_is_removed() {
+    let (res, encoded) = run(false);
+    assert_eq!(res.unwrap(), 1000);
+    assert!(encoded > 0);
+    assert_eq!(spill_files(), 0);
+
+    let (res, encoded) = run(true);
+    assert_eq!(res.unwrap_err().to_string(), "report");
+    assert!(encoded > 0);
+    assert_eq!(spill_files(), 0);
+}
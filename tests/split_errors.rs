@@ -0,0 +1,40 @@
+use parstream::RunError;
+
+#[derive(Debug, PartialEq)]
+struct ParseError(usize);
+
+#[derive(Debug, PartialEq)]
+struct SinkError(u64);
+
+#[test]
+fn worker_error() {
+    let res = parstream::run_split_errors(0..100usize, 4,
+        |x| if x == 10 { Err(ParseError(x)) } else { Ok(x as u64) },
+        |_| Ok::<_, SinkError>(()),
+    );
+    match res {
+        Err(RunError::Callback(ParseError(10))) => (),
+        _ => panic!("unexpected result"),
+    }
+}
+
+#[test]
+fn report_error() {
+    let res = parstream::run_split_errors(0..100usize, 4,
+        |x| Ok::<_, ParseError>(x as u64),
+        |y| if y == 20 { Err(SinkError(y)) } else { Ok(()) },
+    );
+    match res {
+        Err(RunError::Report(SinkError(20))) => (),
+        _ => panic!("unexpected result"),
+    }
+}
+
+#[test]
+fn success() {
+    let res = parstream::run_split_errors(0..100usize, 4,
+        |x| Ok::<_, ParseError>(x as u64),
+        |_| Ok::<_, SinkError>(()),
+    );
+    assert_eq!(res.unwrap(), 100);
+}
@@ -0,0 +1,27 @@
+use parstream::Config;
+
+// uses roughly 16 MiB of stack, which overflows the default 2 MiB stack
+#[inline(never)]
+fn recurse(depth: usize, prev: &[u8; 1024]) -> u64 {
+    let mut buf = [0u8; 1024];
+    for (i, b) in buf.iter_mut().enumerate() {
+        // volatile read prevents optimizing the buffer out
+        *b = unsafe { std::ptr::read_volatile(&prev[i]) } ^ depth as u8;
+    }
+    if depth == 0 {
+        return buf.iter().map(|&b| b as u64).sum();
+    }
+    recurse(depth - 1, &buf) + buf[depth % 1024] as u64
+}
+
+#[test]
+fn deep_recursion_with_enlarged_stack() {
+    let res: Result<usize, ()> = Config::new()
+        .threads(2)
+        .stack_size(64 << 20)
+        .run(0..4usize,
+            |_| Ok(recurse(16 * 1024, &[1; 1024])),
+            |y| { assert!(y > 0); Ok(()) },
+        );
+    assert_eq!(res, Ok(4));
+}
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+use std::thread::{self, ThreadId};
+
+#[derive(Default)]
+struct State {
+    threads: HashSet<ThreadId>,
+    processed: Vec<u64>,
+}
+
+#[test]
+fn states_are_disjoint() {
+    let mut states: Vec<State> = (0..4).map(|_| State::default()).collect();
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_with_states(0..1000u64,
+        &mut states,
+        |s, x| {
+            s.threads.insert(thread::current().id());
+            s.processed.push(x);
+            Ok(x * 2)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(1000));
+    assert_eq!(ys, (0..1000).map(|x| x * 2).collect::<Vec<_>>());
+
+    let mut all_threads = HashSet::new();
+    let mut processed = Vec::new();
+    for s in &states {
+        // every state is used by at most one worker
+        assert!(s.threads.len() <= 1);
+        for t in &s.threads { assert!(all_threads.insert(*t)) }
+        processed.extend_from_slice(&s.processed);
+    }
+    processed.sort_unstable();
+    assert_eq!(processed, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn states_are_reused() {
+    let mut bufs = vec![Vec::<u64>::new(); 2];
+    for _ in 0..3 {
+        let res: Result<usize, ()> = parstream::run_with_states(0..10u64,
+            &mut bufs,
+            |buf, x| { buf.push(x); Ok(()) },
+            |_| Ok(()),
+        );
+        assert_eq!(res, Ok(10));
+    }
+    assert_eq!(bufs.iter().map(Vec::len).sum::<usize>(), 30);
+}
+
+#[test]
+#[should_panic(expected = "at least one state must be provided")]
+fn empty_states() {
+    let mut states: Vec<()> = Vec::new();
+    let _: Result<usize, ()> = parstream::run_with_states(0..10u64,
+        &mut states, |_, x| Ok(x), |_| Ok(()));
+}
@@ -0,0 +1,89 @@
+use std::thread;
+use std::time::Duration;
+
+use parstream::Config;
+
+#[test]
+fn stats_are_collected() {
+    let (res, stats) = Config::new()
+        .threads(4)
+        .run_with_stats(0..20u64,
+            |x| {
+                if x == 0 { thread::sleep(Duration::from_millis(100)); }
+                Ok::<u64, ()>(x)
+            },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Ok(20));
+    assert_eq!(stats.worker_items.len(), 4);
+    assert_eq!(stats.worker_items.iter().sum::<usize>(), 20);
+    // remaining results have waited for the slow first element
+    assert_eq!(stats.total_buffered, 19);
+    assert_eq!(stats.peak_buffered, 19);
+    assert!(stats.elapsed >= Duration::from_millis(100));
+}
+
+#[test]
+fn stats_of_failed_run() {
+    let (res, stats) = parstream::run_with_stats(0..100u64, 2,
+        |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(10));
+    assert_eq!(stats.worker_items.len(), 2);
+    assert!(stats.worker_items.iter().sum::<usize>() >= 11);
+}
+
+#[test]
+fn peak_of_reorder_buffer() {
+    let (res, peak) = parstream::run_with_peak(0..20u64, 4,
+        |x| {
+            if x == 0 { thread::sleep(Duration::from_millis(100)); }
+            Ok::<u64, ()>(x)
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Ok(20));
+    assert_eq!(peak, 19);
+
+    let (res, peak) = Config::new()
+        .threads(1)
+        .run_with_peak(0..20u64, Ok::<u64, ()>, |_| Ok(()));
+    assert_eq!(res, Ok(20));
+    assert_eq!(peak, 0);
+}
+
+#[test]
+fn task_durations_are_collected() {
+    let (res, stats) = parstream::run_with_stats(0..100u64, 4,
+        |x| {
+            let ms = if x % 10 == 0 { 20 } else { 1 };
+            thread::sleep(Duration::from_millis(ms));
+            Ok::<u64, ()>(x)
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Ok(100));
+    let d = &stats.task_durations;
+    assert_eq!(d.count(), 100);
+    assert!(d.p50() >= Duration::from_millis(1));
+    assert!(d.p50() < Duration::from_millis(15));
+    // 10% of tasks are slow
+    assert!(d.percentile(0.95) >= Duration::from_millis(20));
+    assert!(d.max() >= Duration::from_millis(20));
+    assert!(d.p99() <= d.max());
+    assert!(d.percentile(0.0) <= d.p50());
+    assert_eq!(d.percentile(1.0), d.max());
+}
+
+#[test]
+fn empty_histogram() {
+    let (res, stats) = parstream::run_with_stats(0..0u64, 2, Ok::<u64, ()>,
+        |_| Ok(()));
+    assert_eq!(res, Ok(0));
+    let d = &stats.task_durations;
+    assert_eq!(d.count(), 0);
+    assert_eq!(d.max(), Duration::from_secs(0));
+    assert_eq!(d.p99(), Duration::from_secs(0));
+    assert_eq!(*d, parstream::DurationHistogram::default());
+}
@@ -0,0 +1,20 @@
+#[test]
+fn sink_error_short_circuits() {
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+    let res = parstream::run_tee(0..100u64, 4, Ok, vec![
+        Box::new(|y| { a.push(y); Ok(()) }),
+        Box::new(|y| if y == 10 { Err(y) } else { Ok(()) }),
+        Box::new(|y| { b.push(y); Ok(()) }),
+    ]);
+    assert_eq!(res, Err(10));
+    assert_eq!(a, (0..11).collect::<Vec<_>>());
+    assert_eq!(b, (0..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn no_sinks() {
+    let res: Result<usize, ()> = parstream::run_tee(0..100u64, 4, Ok,
+        Vec::new());
+    assert_eq!(res, Ok(100));
+}
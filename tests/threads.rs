@@ -0,0 +1,48 @@
+#[test]
+fn run_auto_processes_slice() {
+    let xs: &[u64] = &[5, 4, 3, 2, 1, 0];
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_auto(xs,
+        |x| Ok(x*x),
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(xs.len()));
+    assert_eq!(ys, vec![25, 16, 9, 4, 1, 0]);
+}
+
+#[test]
+fn zero_threads_resolves_to_available_parallelism() {
+    let workers = std::sync::Mutex::new(std::collections::HashSet::new());
+    let res: Result<usize, ()> = parstream::run(0..1000u64, 0,
+        |x| {
+            workers.lock().unwrap().insert(std::thread::current().id());
+            Ok(x)
+        },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Ok(1000));
+    let n = workers.lock().unwrap().len();
+    let max = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    assert!(n >= 1 && n <= max);
+}
+
+#[test]
+fn zero_threads_in_other_runs() {
+    let expected: Vec<u64> = (0..100).map(|x| x*x).collect();
+
+    let ys = parstream::collect_ordered(0..100u64, 0, |x| Ok::<u64, ()>(x*x));
+    assert_eq!(ys, Ok(expected.clone()));
+
+    let ys: Result<Vec<u64>, ()> = parstream::par_map(0..100u64, 0, |x| Ok(x*x))
+        .collect();
+    assert_eq!(ys, Ok(expected.clone()));
+
+    let pool = parstream::Pool::new(0);
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = pool.run(0..100u64,
+        |x| Ok(x*x),
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, expected);
+}
@@ -0,0 +1,48 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::{Config, RunError};
+
+#[test]
+fn slow_element_times_out() {
+    let t = Instant::now();
+    let res = parstream::run_with_timeout(0..100u64, 4,
+        Duration::from_millis(50),
+        |x| {
+            if x == 42 { thread::sleep(Duration::from_secs(3)); }
+            Ok::<_, ()>(x)
+        },
+        |_| Ok(()),
+    );
+    assert!(t.elapsed() < Duration::from_secs(2));
+    match res {
+        Err(RunError::TimedOut(42)) => (),
+        _ => panic!("unexpected result"),
+    }
+}
+
+#[test]
+fn fast_run_is_not_affected() {
+    let mut ys = Vec::new();
+    let res: Result<usize, RunError<()>> = Config::new()
+        .threads(4)
+        .run_with_timeout(0..1000u64, Duration::from_secs(5), Ok, |y| {
+            ys.push(y);
+            Ok(())
+        });
+    assert_eq!(res.unwrap(), 1000);
+    assert_eq!(ys, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn error_is_propagated() {
+    let res = parstream::run_with_timeout(0..100u64, 4,
+        Duration::from_secs(5),
+        |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    match res {
+        Err(RunError::Callback(10)) => (),
+        _ => panic!("unexpected result"),
+    }
+}
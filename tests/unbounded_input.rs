@@ -0,0 +1,62 @@
+use std::iter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parstream::Config;
+
+fn iterator_is_drained(unbounded: bool) -> bool {
+    let drained = AtomicBool::new(false);
+    let xs = (0..100u64).chain(iter::from_fn(|| {
+        drained.store(true, Ordering::SeqCst);
+        None
+    }));
+    let mut seen = false;
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = Config::new()
+        .threads(1)
+        .unbounded_input(unbounded)
+        .run(xs,
+            |x| {
+                if x == 0 {
+                    let start = Instant::now();
+                    while !drained.load(Ordering::SeqCst)
+                        && start.elapsed() < Duration::from_millis(500)
+                    {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
+                Ok(x)
+            },
+            |y| {
+                if y == 0 { seen = drained.load(Ordering::SeqCst) }
+                ys.push(y);
+                Ok(())
+            },
+        );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100).collect::<Vec<_>>());
+    seen
+}
+
+#[test]
+fn iterator_is_drained_before_first_result() {
+    assert!(iterator_is_drained(true));
+}
+
+#[test]
+fn bounded_input_applies_backpressure() {
+    assert!(!iterator_is_drained(false));
+}
+
+#[test]
+fn errors_stop_unbounded_run() {
+    let res = Config::new()
+        .threads(2)
+        .unbounded_input(true)
+        .run(0..1000u64,
+            |x| if x == 10 { Err(x) } else { Ok(x) },
+            |_| Ok(()),
+        );
+    assert_eq!(res, Err(10));
+}
@@ -0,0 +1,26 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn slow_first_element_does_not_block_others() {
+    let xs: &[u64] = &[200, 0, 0, 0, 0, 0, 0, 0];
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_unordered(xs, 2,
+        |x| {
+            thread::sleep(Duration::from_millis(*x));
+            Ok(*x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(xs.len()));
+    assert_eq!(ys.last(), Some(&200));
+}
+
+#[test]
+fn error_short_circuits() {
+    let res = parstream::run_unordered(0..1000u64, 4,
+        |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(10));
+}
@@ -0,0 +1,52 @@
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn large_window_preserves_order() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_windowed(0..100u64, 4, 1000,
+        |x| {
+            thread::sleep(Duration::from_millis((x * 7) % 5));
+            Ok(x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(100));
+    assert_eq!(ys, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn slow_element_is_reported_late() {
+    let mut ys = Vec::new();
+    let res: Result<usize, ()> = parstream::run_windowed(0..50u64, 4, 4,
+        |x| {
+            if x == 5 { thread::sleep(Duration::from_millis(200)); }
+            Ok(x)
+        },
+        |y| { ys.push(y); Ok(()) },
+    );
+    assert_eq!(res, Ok(50));
+    let pos = ys.iter().position(|&y| y == 5).unwrap();
+    // remaining results did not wait for the slow element
+    assert!(pos > 20);
+    ys.sort();
+    assert_eq!(ys, (0..50).collect::<Vec<_>>());
+}
+
+#[test]
+fn window_error() {
+    let res = parstream::run_windowed(0..100u64, 4, 8,
+        |x| if x == 10 { Err(x) } else { Ok(x) },
+        |_| Ok(()),
+    );
+    assert_eq!(res, Err(10));
+}
+
+#[test]
+#[should_panic(expected = "reorder window must be non-zero")]
+fn zero_window_panics() {
+    let _ = parstream::run_windowed(0..10u64, 2, 0,
+        Ok::<u64, ()>,
+        |_| Ok(()),
+    );
+}
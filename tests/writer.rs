@@ -0,0 +1,85 @@
+use std::io::{self, Write};
+
+#[test]
+fn writes_in_order() {
+    let mut out = Vec::new();
+    let res: Result<usize, io::Error> = parstream::run_to_writer(
+        0..1000u64, 4,
+        |x| Ok(format!("{}\n", x).into_bytes()),
+        &mut out,
+    );
+    assert_eq!(res.unwrap(), 1000);
+    let expected: String = (0..1000).map(|x| format!("{}\n", x)).collect();
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+/// Writer which fails after accepting `limit` bytes.
+struct Limited {
+    buf: Vec<u8>,
+    limit: usize,
+    flushed: bool,
+}
+
+impl Write for Limited {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > self.limit {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "limit"));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushed = true;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum MyError {
+    Io(io::Error),
+    Callback(u64),
+}
+
+impl From<io::Error> for MyError {
+    fn from(err: io::Error) -> Self {
+        MyError::Io(err)
+    }
+}
+
+#[test]
+fn write_error_stops_run() {
+    let mut w = Limited { buf: Vec::new(), limit: 10, flushed: false };
+    let res = parstream::run_to_writer(0..1000u64, 4,
+        |x| Ok::<_, MyError>(vec![x as u8; 3]),
+        &mut w,
+    );
+    match res {
+        Err(MyError::Io(err)) => assert_eq!(err.to_string(), "limit"),
+        _ => panic!("unexpected result: {:?}", res),
+    }
+    assert_eq!(w.buf, vec![0, 0, 0, 1, 1, 1, 2, 2, 2]);
+    assert!(!w.flushed);
+}
+
+#[test]
+fn callback_error() {
+    let mut w = Limited { buf: Vec::new(), limit: 1000, flushed: false };
+    let res = parstream::run_to_writer(0..100u64, 4,
+        |x| if x == 5 { Err(MyError::Callback(x)) } else { Ok(vec![x as u8]) },
+        &mut w,
+    );
+    assert!(matches!(res, Err(MyError::Callback(5))));
+    assert_eq!(w.buf, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn output_is_flushed() {
+    let mut w = Limited { buf: Vec::new(), limit: 1000, flushed: false };
+    let res: Result<usize, MyError> = parstream::run_to_writer(0..10u64, 2,
+        |x| Ok(vec![x as u8]),
+        &mut w,
+    );
+    assert_eq!(res.unwrap(), 10);
+    assert!(w.flushed);
+}